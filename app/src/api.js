@@ -228,6 +228,10 @@ export const api = {
 			return await getArray('/api/tags')(data)
 		},
 
+		autocomplete: async (data = {}) => {
+			return await getArray(`/api/tags/autocomplete?q=${encodeURIComponent(data.q)}`)()
+		},
+
 		save: save({
 			create: '/api/tags',
 			update: '/api/tags/{id}',
@@ -238,14 +242,14 @@ export const api = {
 
 	content_tags: {
 		get: async (data = {}) => {
-			return await getArray('/api/content-tags/{id}')(data) // {id} here is content id, not tag id
+			return await getArray('/api/content-tags/article/{id}')(data) // {id} here is content id, not tag id
 		},
 
 		save: save({
-			create: '/api/content-tags/{id}', // {id} here is content id, not tag id
+			create: '/api/content-tags/article/{id}', // {id} here is content id, not tag id
 		}),
 
-		delete: remove('/api/content-tags/{id}'),
+		delete: remove('/api/content-tags/article/{id}/{tag_id}'),
 	},
 }
 
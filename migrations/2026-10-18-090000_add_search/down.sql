@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_articles_search_fi;
+DROP INDEX IF EXISTS idx_articles_search_en;
+DROP INDEX IF EXISTS idx_characters_search_fi;
+DROP INDEX IF EXISTS idx_characters_search_en;
+DROP INDEX IF EXISTS idx_tags_search_fi;
+DROP INDEX IF EXISTS idx_tags_search_en;
+
+ALTER TABLE articles DROP COLUMN search_fi, DROP COLUMN search_en;
+ALTER TABLE characters DROP COLUMN search_fi, DROP COLUMN search_en;
+ALTER TABLE tags DROP COLUMN search_fi, DROP COLUMN search_en;
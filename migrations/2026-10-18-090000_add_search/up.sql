@@ -0,0 +1,41 @@
+-- Your SQL goes here
+
+-- Full-text search vectors, one per text search configuration. Titles weigh
+-- the most, ingresses and descriptions next and article bodies the least.
+
+ALTER TABLE articles
+ADD COLUMN search_fi TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('finnish', coalesce(title, '')), 'A') ||
+  setweight(to_tsvector('finnish', coalesce(ingress, '')), 'B') ||
+  setweight(to_tsvector('finnish', coalesce(body, '')), 'C')
+) STORED,
+ADD COLUMN search_en TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+  setweight(to_tsvector('english', coalesce(ingress, '')), 'B') ||
+  setweight(to_tsvector('english', coalesce(body, '')), 'C')
+) STORED;
+
+ALTER TABLE characters
+ADD COLUMN search_fi TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('finnish', coalesce(name, '')), 'A') ||
+  setweight(to_tsvector('finnish', coalesce(description, '')), 'B')
+) STORED,
+ADD COLUMN search_en TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+  setweight(to_tsvector('english', coalesce(description, '')), 'B')
+) STORED;
+
+ALTER TABLE tags
+ADD COLUMN search_fi TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('finnish', coalesce(title, '')), 'A')
+) STORED,
+ADD COLUMN search_en TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('english', coalesce(title, '')), 'A')
+) STORED;
+
+CREATE INDEX idx_articles_search_fi ON articles USING GIN (search_fi);
+CREATE INDEX idx_articles_search_en ON articles USING GIN (search_en);
+CREATE INDEX idx_characters_search_fi ON characters USING GIN (search_fi);
+CREATE INDEX idx_characters_search_en ON characters USING GIN (search_en);
+CREATE INDEX idx_tags_search_fi ON tags USING GIN (search_fi);
+CREATE INDEX idx_tags_search_en ON tags USING GIN (search_en);
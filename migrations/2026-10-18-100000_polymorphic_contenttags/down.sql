@@ -0,0 +1,41 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW rich_contenttags;
+
+DELETE FROM contenttags WHERE content_type <> 'article';
+
+DROP INDEX IF EXISTS idx_contenttags_content;
+
+ALTER TABLE contenttags
+DROP CONSTRAINT uq_contenttags_tag,
+DROP CONSTRAINT fk_contenttags_users,
+DROP CONSTRAINT fk_contenttags_characters,
+DROP CONSTRAINT fk_contenttags_articles,
+DROP CONSTRAINT ck_contenttags_content_ref,
+DROP CONSTRAINT ck_contenttags_content_type;
+
+DROP TRIGGER hki_set_content_ref ON contenttags;
+DROP FUNCTION hki_set_content_ref();
+
+ALTER TABLE contenttags
+DROP COLUMN user_id,
+DROP COLUMN character_id,
+DROP COLUMN article_id,
+DROP COLUMN content_type;
+
+ALTER TABLE contenttags
+  ADD CONSTRAINT fk_articles_id
+    FOREIGN KEY (content_id)
+        REFERENCES articles(id);
+
+create view rich_contenttags as
+  select
+    row_number() OVER ()::integer AS idx,
+    c.id as contenttag_id,
+    c.tag_id as tag_id,
+    c.content_id as content_id,
+    t.title as tag_title
+  from
+    contenttags c,
+    tags t
+  where
+    c.tag_id = t.id
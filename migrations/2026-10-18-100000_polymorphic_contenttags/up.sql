@@ -0,0 +1,74 @@
+-- Your SQL goes here
+
+-- Content tags can point to articles, characters and users. content_type tells
+-- which table content_id refers to. The typed reference columns are maintained
+-- by a trigger and only exist to give every content type a real foreign key.
+
+DROP VIEW rich_contenttags;
+
+ALTER TABLE contenttags
+DROP CONSTRAINT fk_articles_id;
+
+DELETE FROM contenttags c
+WHERE NOT EXISTS (SELECT 1 FROM articles a WHERE a.id = c.content_id);
+
+DELETE FROM contenttags c
+USING contenttags d
+WHERE c.tag_id = d.tag_id
+  AND c.content_id = d.content_id
+  AND (c.created_at, c.id) > (d.created_at, d.id);
+
+ALTER TABLE contenttags
+ADD COLUMN content_type VARCHAR(20) NOT NULL DEFAULT 'article',
+ADD COLUMN article_id UUID NULL,
+ADD COLUMN character_id UUID NULL,
+ADD COLUMN user_id UUID NULL;
+
+CREATE OR REPLACE FUNCTION hki_set_content_ref() RETURNS trigger AS $$
+BEGIN
+    NEW.article_id := CASE WHEN NEW.content_type = 'article' THEN NEW.content_id END;
+    NEW.character_id := CASE WHEN NEW.content_type = 'character' THEN NEW.content_id END;
+    NEW.user_id := CASE WHEN NEW.content_type = 'user' THEN NEW.content_id END;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hki_set_content_ref BEFORE INSERT OR UPDATE ON contenttags
+    FOR EACH ROW EXECUTE PROCEDURE hki_set_content_ref();
+
+UPDATE contenttags SET article_id = content_id;
+
+ALTER TABLE contenttags
+ADD CONSTRAINT ck_contenttags_content_type
+    CHECK (content_type IN ('article', 'character', 'user')),
+ADD CONSTRAINT ck_contenttags_content_ref
+    CHECK (num_nonnulls(article_id, character_id, user_id) = 1),
+ADD CONSTRAINT fk_contenttags_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+ADD CONSTRAINT fk_contenttags_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE,
+ADD CONSTRAINT fk_contenttags_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE,
+ADD CONSTRAINT uq_contenttags_tag
+    UNIQUE (tag_id, content_type, content_id);
+
+CREATE INDEX idx_contenttags_content ON contenttags (content_type, content_id);
+
+create view rich_contenttags as
+  select
+    c.id as contenttag_id,
+    c.tag_id as tag_id,
+    c.content_type as content_type,
+    c.content_id as content_id,
+    t.title as tag_title
+  from
+    contenttags c,
+    tags t
+  where
+    c.tag_id = t.id
@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER hki_check_tag_title ON tags;
+DROP TRIGGER hki_check_tag_alias ON tag_aliases;
+DROP FUNCTION hki_check_tag_title();
+DROP FUNCTION hki_check_tag_alias();
+
+DROP TABLE tag_aliases;
+
+DROP INDEX IF EXISTS idx_tags_parent;
+ALTER TABLE tags
+DROP CONSTRAINT ck_tags_parent,
+DROP CONSTRAINT fk_tags_parent,
+DROP COLUMN parent_id;
+
+DROP INDEX IF EXISTS uq_tags_title;
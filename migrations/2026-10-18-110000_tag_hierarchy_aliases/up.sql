@@ -0,0 +1,104 @@
+-- Your SQL goes here
+
+-- Merge tags whose titles differ only by case into the oldest of them before
+-- making titles case-insensitively unique.
+CREATE TEMPORARY TABLE tag_duplicates AS
+SELECT t.id AS duplicate_id, k.id AS keeper_id
+FROM tags t
+JOIN LATERAL (
+  SELECT k.id FROM tags k
+  WHERE lower(k.title) = lower(t.title)
+  ORDER BY k.created_at, k.id
+  LIMIT 1
+) k ON k.id <> t.id;
+
+DELETE FROM contenttags c
+USING tag_duplicates d, contenttags k
+WHERE c.tag_id = d.duplicate_id
+  AND k.tag_id = d.keeper_id
+  AND k.content_type = c.content_type
+  AND k.content_id = c.content_id;
+
+DELETE FROM contenttags c
+USING tag_duplicates d, contenttags o
+WHERE c.tag_id = d.duplicate_id
+  AND o.tag_id IN (SELECT duplicate_id FROM tag_duplicates WHERE keeper_id = d.keeper_id)
+  AND o.content_type = c.content_type
+  AND o.content_id = c.content_id
+  AND o.id < c.id;
+
+UPDATE contenttags c
+SET tag_id = d.keeper_id
+FROM tag_duplicates d
+WHERE c.tag_id = d.duplicate_id;
+
+DELETE FROM tags t
+USING tag_duplicates d
+WHERE t.id = d.duplicate_id;
+
+DROP TABLE tag_duplicates;
+
+CREATE UNIQUE INDEX uq_tags_title ON tags (lower(title));
+
+ALTER TABLE tags
+ADD COLUMN parent_id UUID NULL,
+ADD CONSTRAINT fk_tags_parent
+    FOREIGN KEY (parent_id)
+        REFERENCES tags(id)
+    ON DELETE SET NULL,
+ADD CONSTRAINT ck_tags_parent
+    CHECK (parent_id <> id);
+
+CREATE INDEX idx_tags_parent ON tags (parent_id);
+
+CREATE TABLE tag_aliases (
+  id UUID NOT NULL PRIMARY KEY,
+  tag_id UUID NOT NULL,
+  alias VARCHAR(100) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT fk_tag_aliases_tags
+    FOREIGN KEY (tag_id)
+        REFERENCES tags(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('tag_aliases');
+
+CREATE UNIQUE INDEX uq_tag_aliases_alias ON tag_aliases (lower(alias));
+CREATE INDEX idx_tag_aliases_tag ON tag_aliases (tag_id);
+
+-- A title and an alias must never be the same word, otherwise resolving it
+-- would be ambiguous. Reported as unique violations like the indexes above.
+CREATE OR REPLACE FUNCTION hki_check_tag_title() RETURNS trigger AS $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM tag_aliases a WHERE lower(a.alias) = lower(NEW.title)) THEN
+        RAISE unique_violation USING
+            MESSAGE = format('Tag title "%s" is already an alias', NEW.title),
+            TABLE = 'tags',
+            CONSTRAINT = 'uq_tags_title';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION hki_check_tag_alias() RETURNS trigger AS $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM tags t WHERE lower(t.title) = lower(NEW.alias)) THEN
+        RAISE unique_violation USING
+            MESSAGE = format('Tag alias "%s" is already a tag title', NEW.alias),
+            TABLE = 'tag_aliases',
+            CONSTRAINT = 'uq_tag_aliases_alias';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hki_check_tag_title BEFORE INSERT OR UPDATE OF title ON tags
+    FOR EACH ROW EXECUTE PROCEDURE hki_check_tag_title();
+
+CREATE TRIGGER hki_check_tag_alias BEFORE INSERT OR UPDATE OF alias ON tag_aliases
+    FOR EACH ROW EXECUTE PROCEDURE hki_check_tag_alias();
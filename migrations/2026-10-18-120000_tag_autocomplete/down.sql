@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_tag_aliases_alias_trgm;
+DROP INDEX IF EXISTS idx_tags_title_trgm;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- Trigram indexes serve both prefix (LIKE 'abc%') and fuzzy (%) tag lookups.
+-- Usage counts and co-occurrence use the contenttags (tag_id, ...) unique index
+-- and idx_contenttags_content.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_tags_title_trgm ON tags USING GIN (lower(title) gin_trgm_ops);
+CREATE INDEX idx_tag_aliases_alias_trgm ON tag_aliases USING GIN (lower(alias) gin_trgm_ops);
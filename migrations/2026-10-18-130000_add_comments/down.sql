@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE comment_revisions;
+DROP TABLE comments;
+
+ALTER TABLE articles
+DROP COLUMN comments_locked;
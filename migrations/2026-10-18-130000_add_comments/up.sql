@@ -0,0 +1,64 @@
+-- Your SQL goes here
+
+ALTER TABLE articles
+ADD COLUMN comments_locked BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Comments are threaded one level deep: a reply always points to a top level
+-- comment. character_id is set when the user comments in-world as a character.
+CREATE TABLE comments (
+  id UUID NOT NULL PRIMARY KEY,
+  article_id UUID NOT NULL,
+  parent_id UUID NULL,
+  user_id UUID NOT NULL,
+  character_id UUID NULL,
+  body VARCHAR(10000) NOT NULL,
+  hidden BOOLEAN NOT NULL DEFAULT FALSE,
+  locked BOOLEAN NOT NULL DEFAULT FALSE,
+  deleted_at TIMESTAMP NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT fk_comments_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_comments_parent
+    FOREIGN KEY (parent_id)
+        REFERENCES comments(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_comments_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_comments_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE SET NULL
+);
+
+SELECT hki_manage_table('comments');
+
+CREATE INDEX idx_comments_article ON comments (article_id, created_at);
+CREATE INDEX idx_comments_parent ON comments (parent_id);
+
+-- Previous versions of edited comments, newest last
+CREATE TABLE comment_revisions (
+  id UUID NOT NULL PRIMARY KEY,
+  comment_id UUID NOT NULL,
+  body VARCHAR(10000) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT fk_comment_revisions_comments
+    FOREIGN KEY (comment_id)
+        REFERENCES comments(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('comment_revisions');
+
+CREATE INDEX idx_comment_revisions_comment ON comment_revisions (comment_id, created_at);
@@ -0,0 +1,60 @@
+-- Your SQL goes here
+
+-- A user may give each kind of reaction once as themselves and once as each of
+-- their characters. The set of reactions is fixed in the application.
+CREATE TABLE article_reactions (
+  id UUID NOT NULL PRIMARY KEY,
+  article_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  character_id UUID NULL,
+  reaction VARCHAR(20) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_article_reactions_reaction
+    CHECK (reaction IN ('like', 'love', 'laugh', 'wow', 'sad', 'angry')),
+  CONSTRAINT fk_article_reactions_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_article_reactions_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_article_reactions_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('article_reactions');
+
+CREATE UNIQUE INDEX uq_article_reactions_reaction ON article_reactions
+  (article_id, user_id, COALESCE(character_id, '00000000-0000-0000-0000-000000000000'), reaction);
+
+-- Bookmarked articles, always per user
+CREATE TABLE favorites (
+  id UUID NOT NULL PRIMARY KEY,
+  article_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_favorites_article UNIQUE (user_id, article_id),
+  CONSTRAINT fk_favorites_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_favorites_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('favorites');
+
+CREATE INDEX idx_favorites_article ON favorites (article_id);
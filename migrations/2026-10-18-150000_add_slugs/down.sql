@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER hki_delete_slug_history ON characters;
+DROP TRIGGER hki_delete_slug_history ON articles;
+DROP FUNCTION hki_delete_slug_history();
+DROP TABLE slug_history;
+
+ALTER TABLE characters
+DROP COLUMN slug;
+
+ALTER TABLE articles
+DROP COLUMN slug;
@@ -0,0 +1,76 @@
+-- Your SQL goes here
+
+-- Slugs are generated by the application from titles and names. Existing rows
+-- get a close approximation here; duplicates are told apart by their id.
+ALTER TABLE articles
+ADD COLUMN slug VARCHAR(100) NULL;
+
+ALTER TABLE characters
+ADD COLUMN slug VARCHAR(100) NULL;
+
+UPDATE articles
+SET slug = left(trim(both '-' from regexp_replace(
+  replace(replace(replace(replace(lower(title), 'ä', 'a'), 'ö', 'o'), 'å', 'a'), 'é', 'e'), '[^a-z0-9]+', '-', 'g')), 80);
+
+UPDATE characters
+SET slug = left(trim(both '-' from regexp_replace(
+  replace(replace(replace(replace(lower(name), 'ä', 'a'), 'ö', 'o'), 'å', 'a'), 'é', 'e'), '[^a-z0-9]+', '-', 'g')), 80);
+
+UPDATE articles SET slug = 'article' WHERE slug = '';
+UPDATE characters SET slug = 'character' WHERE slug = '';
+
+UPDATE articles a
+SET slug = a.slug || '-' || left(a.id::text, 8)
+FROM (
+  SELECT id, row_number() OVER (PARTITION BY slug ORDER BY created_at, id) AS n FROM articles
+) d
+WHERE a.id = d.id AND d.n > 1;
+
+UPDATE characters c
+SET slug = c.slug || '-' || left(c.id::text, 8)
+FROM (
+  SELECT id, row_number() OVER (PARTITION BY slug ORDER BY created_at, id) AS n FROM characters
+) d
+WHERE c.id = d.id AND d.n > 1;
+
+ALTER TABLE articles
+ALTER COLUMN slug SET NOT NULL,
+ADD CONSTRAINT uq_articles_slug UNIQUE (slug);
+
+ALTER TABLE characters
+ALTER COLUMN slug SET NOT NULL,
+ADD CONSTRAINT uq_characters_slug UNIQUE (slug);
+
+-- Earlier slugs of renamed content, so that old links can be redirected.
+-- content_type is 'article' or 'character' like in contenttags.
+CREATE TABLE slug_history (
+  id UUID NOT NULL PRIMARY KEY,
+  content_type VARCHAR(20) NOT NULL,
+  content_id UUID NOT NULL,
+  slug VARCHAR(100) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_slug_history_content_type CHECK (content_type IN ('article', 'character')),
+  CONSTRAINT uq_slug_history_slug UNIQUE (content_type, slug)
+);
+
+SELECT hki_manage_table('slug_history');
+
+CREATE INDEX idx_slug_history_content ON slug_history (content_type, content_id);
+
+-- History rows have no foreign key of their own, so they go with their content
+CREATE OR REPLACE FUNCTION hki_delete_slug_history() RETURNS trigger AS $$
+BEGIN
+    DELETE FROM slug_history WHERE content_type = TG_ARGV[0] AND content_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hki_delete_slug_history AFTER DELETE ON articles
+  FOR EACH ROW EXECUTE PROCEDURE hki_delete_slug_history('article');
+
+CREATE TRIGGER hki_delete_slug_history AFTER DELETE ON characters
+  FOR EACH ROW EXECUTE PROCEDURE hki_delete_slug_history('character');
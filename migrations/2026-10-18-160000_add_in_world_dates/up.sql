@@ -0,0 +1,8 @@
+-- Your SQL goes here
+
+-- When the article takes place in the campaign calendar. Unrelated to
+-- created_at, which is the real-world time the article was written.
+ALTER TABLE articles
+ADD COLUMN in_world_at TIMESTAMP NULL;
+
+CREATE INDEX idx_articles_in_world_at ON articles (in_world_at);
@@ -0,0 +1,41 @@
+-- Your SQL goes here
+
+-- Bylines of an article in attribution order. user_id is the owner of the
+-- character, kept here so that edit rights can be checked without a join and
+-- moved along when the character changes hands. articles.character_id and
+-- articles.user_id stay as the first byline.
+CREATE TABLE article_authors (
+  id UUID NOT NULL PRIMARY KEY,
+  article_id UUID NOT NULL,
+  character_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  position SMALLINT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_article_authors_character UNIQUE (article_id, character_id),
+  CONSTRAINT fk_article_authors_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_article_authors_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_article_authors_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('article_authors');
+
+CREATE INDEX idx_article_authors_character ON article_authors (character_id);
+CREATE INDEX idx_article_authors_user ON article_authors (user_id);
+
+INSERT INTO article_authors (id, article_id, character_id, user_id, position, updated_by)
+SELECT md5(random()::text || a.id::text)::uuid, a.id, a.character_id, c.user_id, 0, a.updated_by
+FROM articles a
+JOIN characters c ON c.id = a.character_id;
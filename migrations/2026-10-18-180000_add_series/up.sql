@@ -0,0 +1,50 @@
+-- Your SQL goes here
+
+-- A series collects articles of one long-running story. user_id is the user
+-- who started the series and may edit it.
+CREATE TABLE series (
+  id UUID NOT NULL PRIMARY KEY,
+  user_id UUID NOT NULL,
+  title VARCHAR(200) NOT NULL,
+  description VARCHAR(2000) NOT NULL DEFAULT '',
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT fk_series_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('series');
+
+CREATE INDEX idx_series_user ON series (user_id);
+
+-- Articles of a series in reading order. An article can belong to many series.
+CREATE TABLE series_articles (
+  id UUID NOT NULL PRIMARY KEY,
+  series_id UUID NOT NULL,
+  article_id UUID NOT NULL,
+  position SMALLINT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_series_articles_article UNIQUE (series_id, article_id),
+  CONSTRAINT uq_series_articles_position UNIQUE (series_id, position),
+  CONSTRAINT fk_series_articles_series
+    FOREIGN KEY (series_id)
+        REFERENCES series(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_series_articles_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('series_articles');
+
+CREATE INDEX idx_series_articles_article ON series_articles (article_id);
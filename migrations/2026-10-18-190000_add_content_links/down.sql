@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER hki_break_content_links ON characters;
+DROP TRIGGER hki_break_content_links ON articles;
+DROP FUNCTION hki_break_content_links();
+DROP TABLE content_links;
@@ -0,0 +1,44 @@
+-- Your SQL goes here
+
+-- [[links]] found in article bodies, stored when the article is saved. A link
+-- whose target was not found has no target_id and is reported to the authors;
+-- it gets resolved when content with target_slug is created or renamed.
+-- target_type is NULL for unresolved links that did not name a kind.
+CREATE TABLE content_links (
+  id UUID NOT NULL PRIMARY KEY,
+  article_id UUID NOT NULL,
+  link_text VARCHAR(200) NOT NULL,
+  target_type VARCHAR(20) NULL,
+  target_slug VARCHAR(100) NOT NULL,
+  target_id UUID NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_content_links_target_type CHECK (target_type IN ('article', 'character')),
+  CONSTRAINT uq_content_links_text UNIQUE (article_id, link_text),
+  CONSTRAINT fk_content_links_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('content_links');
+
+CREATE INDEX idx_content_links_target ON content_links (target_type, target_id);
+CREATE INDEX idx_content_links_broken ON content_links (target_slug) WHERE target_id IS NULL;
+
+-- Targets have no foreign key, links to deleted content become broken
+CREATE OR REPLACE FUNCTION hki_break_content_links() RETURNS trigger AS $$
+BEGIN
+    UPDATE content_links SET target_id = NULL WHERE target_type = TG_ARGV[0] AND target_id = OLD.id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hki_break_content_links AFTER DELETE ON articles
+  FOR EACH ROW EXECUTE PROCEDURE hki_break_content_links('article');
+
+CREATE TRIGGER hki_break_content_links AFTER DELETE ON characters
+  FOR EACH ROW EXECUTE PROCEDURE hki_break_content_links('character');
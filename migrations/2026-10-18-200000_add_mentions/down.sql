@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE notification_opt_outs;
+DROP TABLE notifications;
+DROP TABLE mentions;
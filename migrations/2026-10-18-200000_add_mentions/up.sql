@@ -0,0 +1,85 @@
+-- Your SQL goes here
+
+-- Characters @mentioned in article bodies, stored when the article is saved.
+-- user_id is the owner of the character, moved along when it changes hands.
+CREATE TABLE mentions (
+  id UUID NOT NULL PRIMARY KEY,
+  article_id UUID NOT NULL,
+  character_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_mentions_character UNIQUE (article_id, character_id),
+  CONSTRAINT fk_mentions_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_mentions_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_mentions_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('mentions');
+
+CREATE INDEX idx_mentions_character ON mentions (character_id);
+
+-- Things a user is told about. kind says what happened and which of
+-- article_id and character_id are set.
+CREATE TABLE notifications (
+  id UUID NOT NULL PRIMARY KEY,
+  user_id UUID NOT NULL,
+  kind VARCHAR(20) NOT NULL,
+  article_id UUID NULL,
+  character_id UUID NULL,
+  read_at TIMESTAMP NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_notifications_kind CHECK (kind IN ('mention')),
+  CONSTRAINT fk_notifications_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_notifications_articles
+    FOREIGN KEY (article_id)
+        REFERENCES articles(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_notifications_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('notifications');
+
+CREATE INDEX idx_notifications_user ON notifications (user_id, created_at);
+
+-- Kinds of notifications a user does not want. Everything is on by default.
+CREATE TABLE notification_opt_outs (
+  id UUID NOT NULL PRIMARY KEY,
+  user_id UUID NOT NULL,
+  kind VARCHAR(20) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_notification_opt_outs_kind CHECK (kind IN ('mention')),
+  CONSTRAINT uq_notification_opt_outs_kind UNIQUE (user_id, kind),
+  CONSTRAINT fk_notification_opt_outs_users
+    FOREIGN KEY (user_id)
+        REFERENCES users(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('notification_opt_outs');
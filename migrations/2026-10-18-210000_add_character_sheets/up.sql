@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+-- One sheet per character. The attributes, skills and sections are defined by
+-- the ruleset file, so data is JSON checked by the server against the ruleset
+-- and version stored beside it.
+CREATE TABLE character_sheets (
+  id UUID NOT NULL PRIMARY KEY,
+  character_id UUID NOT NULL,
+  ruleset VARCHAR(100) NOT NULL,
+  ruleset_version INTEGER NOT NULL,
+  data JSONB NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_character_sheets_character UNIQUE (character_id),
+  CONSTRAINT fk_character_sheets_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('character_sheets');
@@ -0,0 +1,45 @@
+-- Your SQL goes here
+
+-- The roll log of each campaign. Rows are only ever inserted: sequence counts
+-- the rolls of a campaign from 1 and hash covers the roll and the hash of the
+-- previous roll, so that a changed or removed roll breaks the chain. The seed
+-- lets the roll be repeated when the log is verified. The user and the
+-- character are not foreign keys, the log outlives them.
+CREATE TABLE rolls (
+  id UUID NOT NULL PRIMARY KEY,
+  campaign VARCHAR(100) NOT NULL,
+  sequence BIGINT NOT NULL,
+  user_id UUID NOT NULL,
+  character_id UUID NOT NULL,
+  character_name VARCHAR(100) NOT NULL,
+  expression VARCHAR(200) NOT NULL,
+  seed BIGINT NOT NULL,
+  result JSONB NOT NULL,
+  total BIGINT NOT NULL,
+  reason TEXT NOT NULL,
+  prev_hash VARCHAR(64) NOT NULL,
+  hash VARCHAR(64) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_rolls_sequence UNIQUE (campaign, sequence),
+  CONSTRAINT ck_rolls_sequence CHECK (sequence > 0)
+);
+
+CREATE INDEX idx_rolls_character ON rolls (character_id);
+
+SELECT hki_manage_table('rolls');
+
+CREATE OR REPLACE FUNCTION hki_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION '% is append-only', TG_TABLE_NAME;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hki_append_only BEFORE UPDATE OR DELETE ON rolls
+  FOR EACH ROW EXECUTE PROCEDURE hki_append_only();
+
+CREATE TRIGGER hki_append_only_truncate BEFORE TRUNCATE ON rolls
+  FOR EACH STATEMENT EXECUTE PROCEDURE hki_append_only();
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE experience;
+DROP FUNCTION hki_cascade_only();
@@ -0,0 +1,51 @@
+-- Your SQL goes here
+
+-- The experience ledger of each character. Entries are never changed: awards
+-- add experience, spends and advancements take it away, and the current
+-- experience is their sum. user_id is who made the entry, the GM for awards.
+-- An advancement records the sheet value it raised.
+CREATE TABLE experience (
+  id UUID NOT NULL PRIMARY KEY,
+  character_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  kind VARCHAR(20) NOT NULL,
+  amount INTEGER NOT NULL,
+  reason TEXT NOT NULL,
+  advanced_key VARCHAR(100),
+  advanced_from INTEGER,
+  advanced_to INTEGER,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_experience_kind CHECK (
+    (kind = 'award' AND amount > 0)
+    OR (kind = 'spend' AND amount < 0)
+    OR (kind = 'advancement' AND amount <= 0 AND advanced_key IS NOT NULL)
+  ),
+  CONSTRAINT fk_experience_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX idx_experience_character ON experience (character_id, created_at);
+
+SELECT hki_manage_table('experience');
+
+-- Ledger rows only go away with the row they belong to
+CREATE OR REPLACE FUNCTION hki_cascade_only() RETURNS trigger AS $$
+BEGIN
+    IF pg_trigger_depth() < 2 THEN
+        RAISE EXCEPTION '% rows are only deleted with their owner', TG_TABLE_NAME;
+    END IF;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hki_append_only BEFORE UPDATE ON experience
+  FOR EACH ROW EXECUTE PROCEDURE hki_append_only();
+
+CREATE TRIGGER hki_cascade_only BEFORE DELETE ON experience
+  FOR EACH ROW EXECUTE PROCEDURE hki_cascade_only();
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE item_transfers;
+DROP TABLE inventory_items;
+DROP TABLE items;
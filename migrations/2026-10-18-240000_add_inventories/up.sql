@@ -0,0 +1,73 @@
+-- Your SQL goes here
+
+-- The item catalog kept by the GMs
+CREATE TABLE items (
+  id UUID NOT NULL PRIMARY KEY,
+  name VARCHAR(100) NOT NULL,
+  category VARCHAR(20) NOT NULL,
+  description TEXT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_items_name UNIQUE (name),
+  CONSTRAINT ck_items_category CHECK (category IN ('gear', 'cyberware', 'weapon'))
+);
+
+SELECT hki_manage_table('items');
+
+-- What each character carries: one row per catalog item, with the quantity,
+-- whether it is equipped and the player's own notes. Catalog items cannot be
+-- removed while someone carries them.
+CREATE TABLE inventory_items (
+  id UUID NOT NULL PRIMARY KEY,
+  character_id UUID NOT NULL,
+  item_id UUID NOT NULL,
+  quantity INTEGER NOT NULL,
+  equipped BOOLEAN NOT NULL,
+  notes TEXT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_inventory_items_item UNIQUE (character_id, item_id),
+  CONSTRAINT ck_inventory_items_quantity CHECK (quantity > 0),
+  CONSTRAINT fk_inventory_items_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_inventory_items_items
+    FOREIGN KEY (item_id)
+        REFERENCES items(id)
+);
+
+SELECT hki_manage_table('inventory_items');
+
+-- The log of items given from one character to another. Like the roll log it
+-- is only appended to and keeps no foreign keys, so that it outlives the
+-- characters and items.
+CREATE TABLE item_transfers (
+  id UUID NOT NULL PRIMARY KEY,
+  item_id UUID NOT NULL,
+  item_name VARCHAR(100) NOT NULL,
+  from_character_id UUID NOT NULL,
+  to_character_id UUID NOT NULL,
+  quantity INTEGER NOT NULL,
+  note TEXT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_item_transfers_quantity CHECK (quantity > 0)
+);
+
+CREATE INDEX idx_item_transfers_from ON item_transfers (from_character_id);
+CREATE INDEX idx_item_transfers_to ON item_transfers (to_character_id);
+
+SELECT hki_manage_table('item_transfers');
+
+CREATE TRIGGER hki_append_only BEFORE UPDATE OR DELETE ON item_transfers
+  FOR EACH ROW EXECUTE PROCEDURE hki_append_only();
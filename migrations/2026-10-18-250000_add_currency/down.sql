@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE currency_entries;
+DROP TABLE currency_transactions;
+DROP TABLE currency_accounts;
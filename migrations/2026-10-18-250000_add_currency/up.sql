@@ -0,0 +1,91 @@
+-- Your SQL goes here
+
+-- Currency accounts: one per character, opened on its first transaction, and
+-- the treasury that GM adjustments are paid from and into. credit_limit is
+-- how far below zero a GM lets the balance go. character_id is not a foreign
+-- key, the ledger outlives the characters; name is the character's name when
+-- the account was opened.
+CREATE TABLE currency_accounts (
+  id UUID NOT NULL PRIMARY KEY,
+  kind VARCHAR(20) NOT NULL,
+  character_id UUID,
+  name VARCHAR(100) NOT NULL,
+  credit_limit BIGINT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_currency_accounts_character UNIQUE (character_id),
+  CONSTRAINT ck_currency_accounts_kind CHECK (
+    (kind = 'character' AND character_id IS NOT NULL)
+    OR (kind = 'treasury' AND character_id IS NULL)
+  ),
+  CONSTRAINT ck_currency_accounts_credit_limit CHECK (credit_limit >= 0)
+);
+
+CREATE UNIQUE INDEX uq_currency_accounts_treasury ON currency_accounts (kind) WHERE kind = 'treasury';
+
+SELECT hki_manage_table('currency_accounts');
+
+-- Every transaction moves amount from the payer to the payee and is booked as
+-- two entries that sum to zero; balances are the sums of the entries. Both
+-- are only ever appended to. user_id is who made the transaction.
+CREATE TABLE currency_transactions (
+  id UUID NOT NULL PRIMARY KEY,
+  kind VARCHAR(20) NOT NULL,
+  payer_account_id UUID NOT NULL,
+  payee_account_id UUID NOT NULL,
+  amount BIGINT NOT NULL,
+  memo TEXT NOT NULL,
+  in_world_at TIMESTAMP,
+  user_id UUID NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_currency_transactions_kind CHECK (kind IN ('payment', 'adjustment')),
+  CONSTRAINT ck_currency_transactions_amount CHECK (amount > 0),
+  CONSTRAINT ck_currency_transactions_accounts CHECK (payer_account_id <> payee_account_id),
+  CONSTRAINT fk_currency_transactions_payer
+    FOREIGN KEY (payer_account_id)
+        REFERENCES currency_accounts(id),
+  CONSTRAINT fk_currency_transactions_payee
+    FOREIGN KEY (payee_account_id)
+        REFERENCES currency_accounts(id)
+);
+
+CREATE INDEX idx_currency_transactions_payer ON currency_transactions (payer_account_id);
+CREATE INDEX idx_currency_transactions_payee ON currency_transactions (payee_account_id);
+
+SELECT hki_manage_table('currency_transactions');
+
+CREATE TABLE currency_entries (
+  id UUID NOT NULL PRIMARY KEY,
+  transaction_id UUID NOT NULL,
+  account_id UUID NOT NULL,
+  amount BIGINT NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_currency_entries_account UNIQUE (transaction_id, account_id),
+  CONSTRAINT fk_currency_entries_transactions
+    FOREIGN KEY (transaction_id)
+        REFERENCES currency_transactions(id),
+  CONSTRAINT fk_currency_entries_accounts
+    FOREIGN KEY (account_id)
+        REFERENCES currency_accounts(id)
+);
+
+CREATE INDEX idx_currency_entries_account ON currency_entries (account_id);
+
+SELECT hki_manage_table('currency_entries');
+
+CREATE TRIGGER hki_append_only BEFORE UPDATE OR DELETE ON currency_transactions
+  FOR EACH ROW EXECUTE PROCEDURE hki_append_only();
+
+CREATE TRIGGER hki_append_only BEFORE UPDATE OR DELETE ON currency_entries
+  FOR EACH ROW EXECUTE PROCEDURE hki_append_only();
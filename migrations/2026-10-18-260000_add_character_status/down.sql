@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_characters_status;
+
+ALTER TABLE characters
+DROP COLUMN died_at,
+DROP COLUMN status;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+-- Where the character is in its life. Retired characters are out of play but
+-- may return, deceased ones are kept read-only for the record and npc
+-- characters are played by a GM, who then owns them. died_at is the in-world
+-- date of death.
+ALTER TABLE characters
+ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'active',
+ADD COLUMN died_at TIMESTAMP NULL,
+ADD CONSTRAINT ck_characters_status CHECK (status IN ('active', 'retired', 'deceased', 'npc')),
+ADD CONSTRAINT ck_characters_died_at CHECK ((status = 'deceased') = (died_at IS NOT NULL));
+
+CREATE INDEX idx_characters_status ON characters (user_id, status);
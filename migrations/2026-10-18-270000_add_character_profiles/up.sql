@@ -0,0 +1,39 @@
+-- Your SQL goes here
+
+-- The public persona of a character beside its private record. The player
+-- writes the portrait, bio and affiliations, GMs write gm_notes. Each field is
+-- shown by its visibility: public to anyone, party to signed in players, owner
+-- to the character's owner and GMs, gm to GMs. A character without a row has
+-- the defaults of the server.
+CREATE TABLE character_profiles (
+  id UUID NOT NULL PRIMARY KEY,
+  character_id UUID NOT NULL,
+  portrait_url VARCHAR(500) NOT NULL,
+  bio VARCHAR(10000) NOT NULL,
+  affiliations TEXT[] NOT NULL,
+  gm_notes VARCHAR(10000) NOT NULL,
+  portrait_visibility VARCHAR(20) NOT NULL,
+  bio_visibility VARCHAR(20) NOT NULL,
+  affiliations_visibility VARCHAR(20) NOT NULL,
+  sheet_visibility VARCHAR(20) NOT NULL,
+  gm_notes_visibility VARCHAR(20) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT uq_character_profiles_character UNIQUE (character_id),
+  CONSTRAINT ck_character_profiles_visibility CHECK (
+    portrait_visibility IN ('public', 'party', 'owner', 'gm')
+    AND bio_visibility IN ('public', 'party', 'owner', 'gm')
+    AND affiliations_visibility IN ('public', 'party', 'owner', 'gm')
+    AND sheet_visibility IN ('public', 'party', 'owner', 'gm')
+    AND gm_notes_visibility IN ('public', 'party', 'owner', 'gm')
+  ),
+  CONSTRAINT fk_character_profiles_characters
+    FOREIGN KEY (character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('character_profiles');
@@ -0,0 +1,41 @@
+-- Your SQL goes here
+
+-- Ties between characters, written by the owner of from_character_id or a GM.
+-- A directed relationship is how the first character sees the second; an
+-- undirected one holds both ways and is stored once for the pair. visibility
+-- takes the values of the profile fields, owner meaning the owner of
+-- from_character_id, so a note can be kept from the character it is about.
+CREATE TABLE relationships (
+  id UUID NOT NULL PRIMARY KEY,
+  from_character_id UUID NOT NULL,
+  to_character_id UUID NOT NULL,
+  kind VARCHAR(20) NOT NULL,
+  directed BOOLEAN NOT NULL,
+  notes VARCHAR(10000) NOT NULL,
+  visibility VARCHAR(20) NOT NULL,
+  created_by VARCHAR(100) NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_by VARCHAR(100) NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  updated_count SMALLINT NOT NULL,
+  CONSTRAINT ck_relationships_kind CHECK (kind IN ('ally', 'rival', 'contact', 'family')),
+  CONSTRAINT ck_relationships_visibility CHECK (visibility IN ('public', 'party', 'owner', 'gm')),
+  CONSTRAINT ck_relationships_self CHECK (from_character_id <> to_character_id),
+  CONSTRAINT fk_relationships_from
+    FOREIGN KEY (from_character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE,
+  CONSTRAINT fk_relationships_to
+    FOREIGN KEY (to_character_id)
+        REFERENCES characters(id)
+    ON DELETE CASCADE
+);
+
+SELECT hki_manage_table('relationships');
+
+CREATE UNIQUE INDEX uq_relationships_directed ON relationships (from_character_id, to_character_id, kind) WHERE directed;
+CREATE UNIQUE INDEX uq_relationships_undirected
+  ON relationships (LEAST(from_character_id, to_character_id), GREATEST(from_character_id, to_character_id), kind)
+  WHERE NOT directed;
+CREATE INDEX idx_relationships_from ON relationships (from_character_id);
+CREATE INDEX idx_relationships_to ON relationships (to_character_id);
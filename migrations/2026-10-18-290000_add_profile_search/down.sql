@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_character_profiles_search_fi;
+DROP INDEX IF EXISTS idx_character_profiles_search_en;
+
+ALTER TABLE character_profiles DROP COLUMN search_fi, DROP COLUMN search_en;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+-- Full-text search vectors for profile bios, so that characters can be found by
+-- their bios through an index like by their names and descriptions.
+
+ALTER TABLE character_profiles
+ADD COLUMN search_fi TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('finnish', coalesce(bio, '')), 'B')
+) STORED,
+ADD COLUMN search_en TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('english', coalesce(bio, '')), 'B')
+) STORED;
+
+CREATE INDEX idx_character_profiles_search_fi ON character_profiles USING GIN (search_fi);
+CREATE INDEX idx_character_profiles_search_en ON character_profiles USING GIN (search_en);
@@ -0,0 +1,140 @@
+// The campaign calendar. In-world dates are stored as plain timestamps; the
+// calendar tells how far the in-world clock runs ahead of the real one, which
+// dates the campaign allows and what months and weekdays are called in it.
+// Loaded from the JSON file named by CAMPAIGN_CALENDAR, HKI2050 by default.
+use crate::errors::ServiceError;
+use chrono::{Datelike, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+lazy_static::lazy_static! {
+pub static ref CALENDAR: CampaignCalendar = match std::env::var("CAMPAIGN_CALENDAR") {
+	Ok(path) => CampaignCalendar::load(&path).unwrap_or_else(|err| panic!("Invalid CAMPAIGN_CALENDAR {}: {}", path, err)),
+	Err(_) => CampaignCalendar::default(),
+};
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignCalendar {
+	pub name: String,
+	// Years added to the real date to get the in-world date of new articles.
+	// Without it articles have no in-world date until one is given.
+	pub year_offset: Option<i32>,
+	pub earliest: Option<NaiveDateTime>,
+	pub latest: Option<NaiveDateTime>,
+	// January first
+	pub month_names: Vec<String>,
+	// Monday first
+	pub weekday_names: Vec<String>,
+}
+
+impl Default for CampaignCalendar {
+	fn default() -> Self {
+		let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+		CampaignCalendar {
+			name: String::from("HKI2050"),
+			year_offset: Some(24),
+			earliest: None,
+			latest: None,
+			month_names: names(&[
+				"tammikuu",
+				"helmikuu",
+				"maaliskuu",
+				"huhtikuu",
+				"toukokuu",
+				"kesäkuu",
+				"heinäkuu",
+				"elokuu",
+				"syyskuu",
+				"lokakuu",
+				"marraskuu",
+				"joulukuu",
+			]),
+			weekday_names: names(&[
+				"maanantai",
+				"tiistai",
+				"keskiviikko",
+				"torstai",
+				"perjantai",
+				"lauantai",
+				"sunnuntai",
+			]),
+		}
+	}
+}
+
+impl CampaignCalendar {
+	pub fn load(path: &str) -> Result<CampaignCalendar, String> {
+		let json = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+		let calendar: CampaignCalendar = serde_json::from_str(&json).map_err(|err| err.to_string())?;
+		if calendar.month_names.len() != 12 || calendar.weekday_names.len() != 7 {
+			return Err(String::from("a calendar needs 12 month names and 7 weekday names"));
+		}
+		Ok(calendar)
+	}
+
+	// Leap days fall back to February 28th in years without one
+	pub fn default_in_world_at(&self, real: NaiveDateTime) -> Option<NaiveDateTime> {
+		let year = real.year() + self.year_offset?;
+		real.with_year(year).or_else(|| real.with_day(28).and_then(|date| date.with_year(year)))
+	}
+
+	pub fn check(&self, at: NaiveDateTime) -> Result<(), ServiceError> {
+		if let Some(earliest) = self.earliest {
+			if at < earliest {
+				return Err(ServiceError::BadRequest(format!(
+					"In-world date is before the campaign calendar starts at {}",
+					earliest
+				)));
+			}
+		}
+		if let Some(latest) = self.latest {
+			if at > latest {
+				return Err(ServiceError::BadRequest(format!(
+					"In-world date is after the campaign calendar ends at {}",
+					latest
+				)));
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+
+	fn at(year: i32, month: u32, day: u32) -> NaiveDateTime {
+		NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(18, 0, 0).unwrap()
+	}
+
+	#[test]
+	fn default_date_runs_ahead_of_real_time() {
+		let calendar = CampaignCalendar::default();
+		assert_eq!(calendar.default_in_world_at(at(2026, 10, 18)), Some(at(2050, 10, 18)));
+
+		let calendar = CampaignCalendar {
+			year_offset: Some(1),
+			..Default::default()
+		};
+		assert_eq!(calendar.default_in_world_at(at(2024, 2, 29)), Some(at(2025, 2, 28)));
+
+		let calendar = CampaignCalendar {
+			year_offset: None,
+			..Default::default()
+		};
+		assert_eq!(calendar.default_in_world_at(at(2026, 10, 18)), None);
+	}
+
+	#[test]
+	fn check_enforces_campaign_bounds() {
+		let calendar = CampaignCalendar {
+			earliest: Some(at(2050, 1, 1)),
+			latest: Some(at(2050, 12, 31)),
+			..Default::default()
+		};
+		assert!(calendar.check(at(2050, 6, 1)).is_ok());
+		assert!(calendar.check(at(2049, 12, 31)).is_err());
+		assert!(calendar.check(at(2051, 1, 1)).is_err());
+	}
+}
@@ -0,0 +1,437 @@
+// Dice expressions such as 3d6+2, d20, 4d6kh3, 2d10! and 6d10>=7. Terms are
+// added or subtracted; a dice term is [count]d<sides|%> followed by, in this
+// order, ! to explode dice that roll their maximum, khN or klN to keep the N
+// highest or lowest dice and >=T or >T to count dice reaching the target
+// instead of summing them. Rolling takes the random generator as a parameter,
+// so that a roll can be repeated from its seed. Logged rolls use ChaCha8 and
+// draw dice from its raw output, which stay the same across rand versions and
+// platforms, so that old rolls still check out after an upgrade.
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use std::fmt;
+
+// Dice rolled by one expression, explosions included
+pub const MAX_DICE: usize = 200;
+pub const MAX_SIDES: i64 = 1000;
+// Keeps the sum of an expression far from overflowing
+pub const MAX_CONSTANT: i64 = 1_000_000;
+pub const MAX_LENGTH: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceExpression {
+	// Terms with their sign, 1 or -1
+	pub terms: Vec<(i64, Term)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+	Constant(i64),
+	Dice(DiceTerm),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceTerm {
+	pub count: usize,
+	pub sides: i64,
+	pub explode: bool,
+	pub keep: Option<Keep>,
+	// Dice rolling at least this much are successes
+	pub target: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keep {
+	Highest(usize),
+	Lowest(usize),
+}
+
+#[derive(Debug, Serialize)]
+pub struct RollResult {
+	pub expression: String,
+	pub total: i64,
+	// Sum of the successes of the terms with a target, if any have one
+	pub successes: Option<i64>,
+	pub terms: Vec<TermResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TermResult {
+	pub notation: String,
+	pub sign: i64,
+	pub dice: Vec<DieResult>,
+	pub value: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DieResult {
+	pub value: i64,
+	pub kept: bool,
+	// The die rolled its maximum and the next die was rolled because of it
+	pub exploded: bool,
+}
+
+impl fmt::Display for DiceTerm {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}d{}", self.count, self.sides)?;
+		if self.explode {
+			write!(f, "!")?;
+		}
+		match self.keep {
+			Some(Keep::Highest(n)) => write!(f, "kh{}", n)?,
+			Some(Keep::Lowest(n)) => write!(f, "kl{}", n)?,
+			None => {}
+		}
+		if let Some(target) = self.target {
+			write!(f, ">={}", target)?;
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for Term {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Term::Constant(number) => write!(f, "{}", number),
+			Term::Dice(dice) => write!(f, "{}", dice),
+		}
+	}
+}
+
+impl fmt::Display for DiceExpression {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (i, (sign, term)) in self.terms.iter().enumerate() {
+			match (i, sign) {
+				(0, -1) => write!(f, "-")?,
+				(0, _) => {}
+				(_, -1) => write!(f, "-")?,
+				_ => write!(f, "+")?,
+			}
+			write!(f, "{}", term)?;
+		}
+		Ok(())
+	}
+}
+
+struct Parser<'a> {
+	chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+	fn eat(&mut self, c: char) -> bool {
+		if self.chars.peek() == Some(&c) {
+			self.chars.next();
+			return true;
+		}
+		false
+	}
+
+	fn number(&mut self) -> Result<Option<i64>, String> {
+		let mut digits = String::new();
+		while let Some(&d) = self.chars.peek().filter(|d| d.is_ascii_digit()) {
+			digits.push(d);
+			self.chars.next();
+		}
+		if digits.is_empty() {
+			return Ok(None);
+		}
+		digits.parse().map(Some).map_err(|_| format!("{} is too large", digits))
+	}
+
+	fn term(&mut self) -> Result<Term, String> {
+		let count = self.number()?;
+		if !self.eat('d') {
+			let constant = count.ok_or_else(|| String::from("expected a number or dice"))?;
+			if constant > MAX_CONSTANT {
+				return Err(format!("numbers are at most {}", MAX_CONSTANT));
+			}
+			return Ok(Term::Constant(constant));
+		}
+
+		let count = count.unwrap_or(1);
+		let sides = if self.eat('%') {
+			100
+		} else {
+			self.number()?.ok_or_else(|| String::from("expected the number of sides after d"))?
+		};
+		if count < 1 || count > MAX_DICE as i64 {
+			return Err(format!("roll 1 to {} dice at a time", MAX_DICE));
+		}
+		if !(1..=MAX_SIDES).contains(&sides) {
+			return Err(format!("dice have 1 to {} sides", MAX_SIDES));
+		}
+		let count = count as usize;
+
+		let explode = self.eat('!');
+		if explode && sides == 1 {
+			return Err(String::from("a one-sided die cannot explode"));
+		}
+
+		let keep = if self.eat('k') {
+			let highest = if self.eat('h') {
+				true
+			} else if self.eat('l') {
+				false
+			} else {
+				return Err(String::from("expected kh or kl"));
+			};
+			let n = self.number()?.ok_or_else(|| String::from("expected the number of dice to keep"))?;
+			if n < 1 || n > count as i64 {
+				return Err(format!("keep 1 to {} dice", count));
+			}
+			Some(if highest { Keep::Highest(n as usize) } else { Keep::Lowest(n as usize) })
+		} else {
+			None
+		};
+
+		let target = if self.eat('>') {
+			let at_least = self.eat('=');
+			let target = self.number()?.ok_or_else(|| String::from("expected a target number"))?;
+			if target > MAX_SIDES {
+				return Err(format!("targets are at most {}", MAX_SIDES));
+			}
+			Some(if at_least { target } else { target + 1 })
+		} else {
+			None
+		};
+
+		Ok(Term::Dice(DiceTerm {
+			count,
+			sides,
+			explode,
+			keep,
+			target,
+		}))
+	}
+}
+
+pub fn parse(text: &str) -> Result<DiceExpression, String> {
+	if text.len() > MAX_LENGTH {
+		return Err(format!("expressions are at most {} characters", MAX_LENGTH));
+	}
+	let normalized: String = text.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+	let mut parser = Parser {
+		chars: normalized.chars().peekable(),
+	};
+
+	let mut terms = Vec::new();
+	let mut sign = if parser.eat('-') { -1 } else { 1 };
+	loop {
+		terms.push((sign, parser.term()?));
+		sign = if parser.eat('+') {
+			1
+		} else if parser.eat('-') {
+			-1
+		} else {
+			break;
+		};
+	}
+	if let Some(c) = parser.chars.next() {
+		return Err(format!("unexpected {}", c));
+	}
+
+	let dice: usize = terms
+		.iter()
+		.map(|(_, term)| match term {
+			Term::Dice(dice) => dice.count,
+			Term::Constant(_) => 0,
+		})
+		.sum();
+	if dice > MAX_DICE {
+		return Err(format!("roll 1 to {} dice at a time", MAX_DICE));
+	}
+
+	Ok(DiceExpression { terms })
+}
+
+// A die from 1 to sides. Draws that would favour low values are thrown away.
+fn die<R: RngCore>(rng: &mut R, sides: i64) -> i64 {
+	let sides = sides as u64;
+	let biased = sides.wrapping_neg() % sides;
+	loop {
+		let draw = rng.next_u64();
+		if draw >= biased {
+			return (draw % sides) as i64 + 1;
+		}
+	}
+}
+
+impl DiceTerm {
+	// Explosions stop when the expression has rolled MAX_DICE dice in total
+	fn roll<R: RngCore>(&self, rng: &mut R, rolled: &mut usize) -> (Vec<DieResult>, i64) {
+		let mut dice: Vec<DieResult> = Vec::with_capacity(self.count);
+		let mut pending = self.count;
+		while pending > 0 {
+			pending -= 1;
+			*rolled += 1;
+			let value = die(rng, self.sides);
+			let exploded = self.explode && value == self.sides && *rolled < MAX_DICE;
+			if exploded {
+				pending += 1;
+			}
+			dice.push(DieResult {
+				value,
+				kept: true,
+				exploded,
+			});
+		}
+
+		if let Some(keep) = self.keep {
+			let mut order: Vec<usize> = (0..dice.len()).collect();
+			order.sort_by_key(|i| dice[*i].value);
+			let dropped = match keep {
+				Keep::Highest(n) => &order[..dice.len().saturating_sub(n)],
+				Keep::Lowest(n) => &order[n.min(dice.len())..],
+			};
+			for i in dropped {
+				dice[*i].kept = false;
+			}
+		}
+
+		let kept = dice.iter().filter(|die| die.kept);
+		let value = match self.target {
+			Some(target) => kept.filter(|die| die.value >= target).count() as i64,
+			None => kept.map(|die| die.value).sum(),
+		};
+		(dice, value)
+	}
+}
+
+impl DiceExpression {
+	pub fn roll<R: RngCore>(&self, rng: &mut R) -> RollResult {
+		let mut rolled = 0;
+		let mut successes: Option<i64> = None;
+		let terms: Vec<TermResult> = self
+			.terms
+			.iter()
+			.map(|(sign, term)| {
+				let (dice, value) = match term {
+					Term::Constant(number) => (Vec::new(), *number),
+					Term::Dice(dice) => {
+						let (results, value) = dice.roll(rng, &mut rolled);
+						if dice.target.is_some() {
+							successes = Some(successes.unwrap_or(0) + sign * value);
+						}
+						(results, value)
+					}
+				};
+				TermResult {
+					notation: term.to_string(),
+					sign: *sign,
+					dice,
+					value,
+				}
+			})
+			.collect();
+
+		RollResult {
+			expression: self.to_string(),
+			total: terms.iter().map(|term| term.sign * term.value).sum(),
+			successes,
+			terms,
+		}
+	}
+
+	// The roll logged with this seed, the same every time
+	pub fn roll_seeded(&self, seed: i64) -> RollResult {
+		let mut key = [0u8; 32];
+		key[..8].copy_from_slice(&seed.to_le_bytes());
+		self.roll(&mut ChaCha8Rng::from_seed(key))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn dice(result: &RollResult, term: usize) -> Vec<i64> {
+		result.terms[term].dice.iter().map(|die| die.value).collect()
+	}
+
+	#[test]
+	fn parse_normalizes_notation() {
+		assert_eq!(parse("3d6 + 2").unwrap().to_string(), "3d6+2");
+		assert_eq!(parse("D20-1").unwrap().to_string(), "1d20-1");
+		assert_eq!(parse("4d6kh3").unwrap().to_string(), "4d6kh3");
+		assert_eq!(parse("2d%!kl1").unwrap().to_string(), "2d100!kl1");
+		assert_eq!(parse("6d10>7").unwrap().to_string(), "6d10>=8");
+		assert_eq!(parse("-1d4+d8").unwrap().to_string(), "-1d4+1d8");
+	}
+
+	#[test]
+	fn parse_rejects_bad_expressions() {
+		for text in &[
+			"",
+			"3d",
+			"d0",
+			"3d6+",
+			"4d6kh5",
+			"4d6k3",
+			"1d1!",
+			"300d6",
+			"150d6+150d6",
+			"3d6*2",
+			"2d6>",
+			"1d6>9223372036854775807",
+			"9223372036854775807+1",
+			"1000001",
+		] {
+			assert!(parse(text).is_err(), "{} should not parse", text);
+		}
+	}
+
+	#[test]
+	fn rolls_repeat_with_the_same_seed() {
+		let expression = parse("10d6!kh3+2").unwrap();
+		let first = expression.roll_seeded(2050);
+		let second = expression.roll_seeded(2050);
+		assert_eq!(first.total, second.total);
+		assert_eq!(dice(&first, 0), dice(&second, 0));
+	}
+
+	// Logged rolls are checked against their seeds, so these must never change
+	#[test]
+	fn seeded_rolls_stay_the_same() {
+		let result = parse("4d6+1d20+1d100").unwrap().roll_seeded(2050);
+		assert_eq!((dice(&result, 0), dice(&result, 1), dice(&result, 2)), (vec![2, 1, 3, 1], vec![8], vec![13]));
+		assert_eq!(result.total, 28);
+		assert_eq!(dice(&parse("3d6").unwrap().roll_seeded(-1), 0), vec![4, 5, 1]);
+	}
+
+	#[test]
+	fn keep_highest_keeps_the_highest_dice() {
+		let expression = parse("6d6kh2").unwrap();
+		for seed in 0..50 {
+			let result = expression.roll_seeded(seed);
+			let mut values = dice(&result, 0);
+			values.sort_unstable();
+			assert_eq!(result.total, values[4] + values[5]);
+			assert_eq!(result.terms[0].dice.iter().filter(|die| die.kept).count(), 2);
+		}
+	}
+
+	#[test]
+	fn exploding_dice_roll_again_on_the_maximum() {
+		let expression = parse("20d4!").unwrap();
+		for seed in 0..20 {
+			let result = expression.roll_seeded(seed);
+			let term = &result.terms[0];
+			let exploded = term.dice.iter().filter(|die| die.exploded).count();
+			assert_eq!(term.dice.len(), 20 + exploded);
+			assert!(term.dice.iter().all(|die| die.exploded == (die.value == 4)));
+			assert_eq!(result.total, term.dice.iter().map(|die| die.value).sum::<i64>());
+		}
+	}
+
+	#[test]
+	fn targets_count_successes() {
+		let expression = parse("8d10>=7+1").unwrap();
+		for seed in 0..20 {
+			let result = expression.roll_seeded(seed);
+			let hits = dice(&result, 0).iter().filter(|value| **value >= 7).count() as i64;
+			assert_eq!(result.successes, Some(hits));
+			assert_eq!(result.total, hits + 1);
+		}
+		assert_eq!(parse("3d6").unwrap().roll_seeded(1).successes, None);
+	}
+}
@@ -31,6 +31,12 @@ pub enum ForbiddenType {
 
 	#[display(fmt = "Foreign key violated")]
 	ForeignKeyViolation,
+
+	#[display(fmt = "Still in use")]
+	InUse,
+
+	#[display(fmt = "Locked")]
+	Locked,
 }
 
 #[derive(Debug, Display)]
@@ -0,0 +1,221 @@
+// Atom and RSS rendering for the article feeds. The feeds are small, so the XML
+// is written by hand instead of pulling in an XML library.
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
+lazy_static::lazy_static! {
+pub static ref PUBLIC_URL: String = std::env::var("PUBLIC_URL").unwrap_or_else(|_| "localhost:8086".to_string());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedFormat {
+	Atom,
+	Rss,
+}
+
+impl FeedFormat {
+	pub fn from_extension(extension: &str) -> Option<FeedFormat> {
+		match extension {
+			"atom" => Some(FeedFormat::Atom),
+			"rss" => Some(FeedFormat::Rss),
+			_ => None,
+		}
+	}
+
+	pub fn content_type(&self) -> &'static str {
+		match self {
+			FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+			FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+		}
+	}
+}
+
+#[derive(Debug, Hash)]
+pub struct FeedEntry {
+	pub id: uuid::Uuid,
+	pub title: String,
+	pub authors: Vec<String>,
+	pub summary: String,
+	pub content: String,
+	pub link: String,
+	pub published: NaiveDateTime,
+	pub updated: NaiveDateTime,
+}
+
+// Timestamps are stored without a zone and are treated as UTC
+#[derive(Debug, Hash)]
+pub struct Feed {
+	pub title: String,
+	pub self_link: String,
+	pub link: String,
+	pub updated: NaiveDateTime,
+	pub entries: Vec<FeedEntry>,
+}
+
+impl Feed {
+	// Changes whenever anything rendered into the feed changes
+	pub fn etag(&self) -> String {
+		let mut hasher = DefaultHasher::new();
+		self.hash(&mut hasher);
+		format!("\"{:016x}\"", hasher.finish())
+	}
+
+	pub fn render(&self, format: FeedFormat) -> String {
+		match format {
+			FeedFormat::Atom => self.render_atom(),
+			FeedFormat::Rss => self.render_rss(),
+		}
+	}
+
+	fn render_atom(&self) -> String {
+		let mut xml = String::new();
+		xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+		let _ = writeln!(xml, "  <id>{}</id>", escape(&self.self_link));
+		let _ = writeln!(xml, "  <title>{}</title>", escape(&self.title));
+		let _ = writeln!(xml, "  <updated>{}</updated>", rfc3339(&self.updated));
+		let _ = writeln!(xml, "  <link rel=\"self\" href=\"{}\"/>", escape(&self.self_link));
+		let _ = writeln!(xml, "  <link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>", escape(&self.link));
+		for entry in &self.entries {
+			xml.push_str("  <entry>\n");
+			let _ = writeln!(xml, "    <id>urn:uuid:{}</id>", entry.id);
+			let _ = writeln!(xml, "    <title>{}</title>", escape(&entry.title));
+			for author in &entry.authors {
+				let _ = writeln!(xml, "    <author><name>{}</name></author>", escape(author));
+			}
+			let _ = writeln!(xml, "    <link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>", escape(&entry.link));
+			let _ = writeln!(xml, "    <published>{}</published>", rfc3339(&entry.published));
+			let _ = writeln!(xml, "    <updated>{}</updated>", rfc3339(&entry.updated));
+			let _ = writeln!(xml, "    <summary type=\"text\">{}</summary>", escape(&entry.summary));
+			let _ = writeln!(xml, "    <content type=\"text\">{}</content>", escape(&entry.content));
+			xml.push_str("  </entry>\n");
+		}
+		xml.push_str("</feed>\n");
+		xml
+	}
+
+	fn render_rss(&self) -> String {
+		let mut xml = String::new();
+		xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		xml.push_str(
+			"<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+		);
+		xml.push_str("  <channel>\n");
+		let _ = writeln!(xml, "    <title>{}</title>", escape(&self.title));
+		let _ = writeln!(xml, "    <link>{}</link>", escape(&self.link));
+		let _ = writeln!(xml, "    <description>{}</description>", escape(&self.title));
+		let _ = writeln!(xml, "    <lastBuildDate>{}</lastBuildDate>", rfc2822(&self.updated));
+		let _ = writeln!(
+			xml,
+			"    <atom:link rel=\"self\" type=\"application/rss+xml\" href=\"{}\"/>",
+			escape(&self.self_link)
+		);
+		for entry in &self.entries {
+			xml.push_str("    <item>\n");
+			let _ = writeln!(xml, "      <guid isPermaLink=\"false\">urn:uuid:{}</guid>", entry.id);
+			let _ = writeln!(xml, "      <title>{}</title>", escape(&entry.title));
+			for author in &entry.authors {
+				let _ = writeln!(xml, "      <dc:creator>{}</dc:creator>", escape(author));
+			}
+			let _ = writeln!(xml, "      <link>{}</link>", escape(&entry.link));
+			let _ = writeln!(xml, "      <pubDate>{}</pubDate>", rfc2822(&entry.published));
+			let _ = writeln!(xml, "      <description>{}</description>", escape(&entry.summary));
+			xml.push_str("    </item>\n");
+		}
+		xml.push_str("  </channel>\n");
+		xml.push_str("</rss>\n");
+		xml
+	}
+}
+
+// Also drops characters that are not allowed in XML 1.0 at all
+pub fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			'\t' | '\n' | '\r' => escaped.push(c),
+			c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+// Timestamps are stored in the server's local time, feeds and HTTP dates are in UTC
+pub fn utc(time: &NaiveDateTime) -> DateTime<Utc> {
+	Local
+		.from_local_datetime(time)
+		.earliest()
+		.map_or_else(|| Utc.from_utc_datetime(time), |local| local.with_timezone(&Utc))
+}
+
+pub fn rfc3339(time: &NaiveDateTime) -> String {
+	utc(time).format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+// Also the HTTP date format used in Last-Modified
+pub fn rfc2822(time: &NaiveDateTime) -> String {
+	utc(time).format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn feed() -> Feed {
+		let time = Utc.with_ymd_and_hms(2050, 1, 31, 12, 30, 0).unwrap().with_timezone(&Local).naive_local();
+		Feed {
+			title: String::from("HKIbook: Kallio & <Kamppi>"),
+			self_link: String::from("http://hki2050.com/feeds/articles.atom?x=1&y=2"),
+			link: String::from("http://hki2050.com/app/hkibook"),
+			updated: time,
+			entries: vec![FeedEntry {
+				id: uuid::Uuid::nil(),
+				title: String::from("\"Palo\" \u{1}Kalliossa"),
+				authors: vec![String::from("Matti"), String::from("Maija")],
+				summary: String::from("a < b"),
+				content: String::from("body"),
+				link: String::from("http://hki2050.com/app/hkibook/1"),
+				published: time,
+				updated: time,
+			}],
+		}
+	}
+
+	#[test]
+	fn escapes_markup_and_control_characters() {
+		assert_eq!(escape("<a href='x'>\"&\"</a>\u{0}"), "&lt;a href=&apos;x&apos;&gt;&quot;&amp;&quot;&lt;/a&gt;");
+	}
+
+	#[test]
+	fn renders_escaped_atom() {
+		let xml = feed().render(FeedFormat::Atom);
+		assert!(xml.contains("<title>HKIbook: Kallio &amp; &lt;Kamppi&gt;</title>"));
+		assert!(xml.contains("<updated>2050-01-31T12:30:00Z</updated>"));
+		assert!(xml.contains("href=\"http://hki2050.com/feeds/articles.atom?x=1&amp;y=2\""));
+		assert!(xml.contains("<title>&quot;Palo&quot; Kalliossa</title>"));
+		assert!(xml.contains("<author><name>Matti</name></author>\n    <author><name>Maija</name></author>"));
+	}
+
+	#[test]
+	fn renders_rss_dates() {
+		let xml = feed().render(FeedFormat::Rss);
+		assert!(xml.contains("<lastBuildDate>Mon, 31 Jan 2050 12:30:00 GMT</lastBuildDate>"));
+		assert!(xml.contains("<description>a &lt; b</description>"));
+	}
+
+	#[test]
+	fn etag_follows_content() {
+		let mut changed = feed();
+		changed.entries[0].title.push('!');
+		assert_eq!(feed().etag(), feed().etag());
+		assert_ne!(feed().etag(), changed.etag());
+	}
+}
@@ -0,0 +1,203 @@
+// GraphML and DOT rendering for the character relationship graph. Written by
+// hand like the feeds; both formats are plain text.
+use crate::feeds::escape;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+	GraphMl,
+	Dot,
+}
+
+impl GraphFormat {
+	pub fn from_extension(extension: &str) -> Option<GraphFormat> {
+		match extension {
+			"graphml" => Some(GraphFormat::GraphMl),
+			"dot" | "gv" => Some(GraphFormat::Dot),
+			_ => None,
+		}
+	}
+
+	pub fn content_type(&self) -> &'static str {
+		match self {
+			GraphFormat::GraphMl => "application/graphml+xml; charset=utf-8",
+			GraphFormat::Dot => "text/vnd.graphviz; charset=utf-8",
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct GraphNode {
+	pub id: uuid::Uuid,
+	pub name: String,
+	pub status: String,
+}
+
+#[derive(Debug)]
+pub struct GraphEdge {
+	pub id: uuid::Uuid,
+	pub source: uuid::Uuid,
+	pub target: uuid::Uuid,
+	pub kind: String,
+	pub directed: bool,
+	pub notes: String,
+}
+
+#[derive(Debug)]
+pub struct Graph {
+	pub nodes: Vec<GraphNode>,
+	pub edges: Vec<GraphEdge>,
+}
+
+impl Graph {
+	pub fn render(&self, format: GraphFormat) -> String {
+		match format {
+			GraphFormat::GraphMl => self.render_graphml(),
+			GraphFormat::Dot => self.render_dot(),
+		}
+	}
+
+	// Edges are undirected unless they say otherwise
+	fn render_graphml(&self) -> String {
+		let mut xml = String::new();
+		xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+		xml.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+		xml.push_str("  <key id=\"status\" for=\"node\" attr.name=\"status\" attr.type=\"string\"/>\n");
+		xml.push_str("  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+		xml.push_str("  <key id=\"notes\" for=\"edge\" attr.name=\"notes\" attr.type=\"string\"/>\n");
+		xml.push_str("  <graph id=\"relationships\" edgedefault=\"undirected\">\n");
+		for node in &self.nodes {
+			let _ = writeln!(xml, "    <node id=\"{}\">", node.id);
+			let _ = writeln!(xml, "      <data key=\"name\">{}</data>", escape(&node.name));
+			let _ = writeln!(xml, "      <data key=\"status\">{}</data>", escape(&node.status));
+			xml.push_str("    </node>\n");
+		}
+		for edge in &self.edges {
+			let _ = writeln!(
+				xml,
+				"    <edge id=\"{}\" source=\"{}\" target=\"{}\" directed=\"{}\">",
+				edge.id, edge.source, edge.target, edge.directed
+			);
+			let _ = writeln!(xml, "      <data key=\"kind\">{}</data>", escape(&edge.kind));
+			let _ = writeln!(xml, "      <data key=\"notes\">{}</data>", escape(&edge.notes));
+			xml.push_str("    </edge>\n");
+		}
+		xml.push_str("  </graph>\n");
+		xml.push_str("</graphml>\n");
+		xml
+	}
+
+	// DOT has no mixed graphs, so undirected edges are drawn without arrowheads
+	fn render_dot(&self) -> String {
+		let mut dot = String::new();
+		dot.push_str("digraph relationships {\n");
+		for node in &self.nodes {
+			let _ = writeln!(
+				dot,
+				"  \"{}\" [label={}, status={}];",
+				node.id,
+				quote(&node.name),
+				quote(&node.status)
+			);
+		}
+		for edge in &self.edges {
+			let _ = writeln!(
+				dot,
+				"  \"{}\" -> \"{}\" [id=\"{}\", label={}, tooltip={}{}];",
+				edge.source,
+				edge.target,
+				edge.id,
+				quote(&edge.kind),
+				quote(&edge.notes),
+				if edge.directed { "" } else { ", dir=none" }
+			);
+		}
+		dot.push_str("}\n");
+		dot
+	}
+}
+
+// A DOT double-quoted string
+pub fn quote(text: &str) -> String {
+	let mut quoted = String::with_capacity(text.len() + 2);
+	quoted.push('"');
+	for c in text.chars() {
+		match c {
+			'"' => quoted.push_str("\\\""),
+			'\\' => quoted.push_str("\\\\"),
+			'\n' => quoted.push_str("\\n"),
+			'\r' => {}
+			c => quoted.push(c),
+		}
+	}
+	quoted.push('"');
+	quoted
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn graph() -> Graph {
+		let a = uuid::Uuid::parse_str("00000000-0000-0000-0000-00000000000a").unwrap();
+		let b = uuid::Uuid::parse_str("00000000-0000-0000-0000-00000000000b").unwrap();
+		Graph {
+			nodes: vec![
+				GraphNode {
+					id: a,
+					name: "Kalle \"K\" <Kärkkäinen>".to_string(),
+					status: "active".to_string(),
+				},
+				GraphNode {
+					id: b,
+					name: "Aino".to_string(),
+					status: "deceased".to_string(),
+				},
+			],
+			edges: vec![
+				GraphEdge {
+					id: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000001").unwrap(),
+					source: a,
+					target: b,
+					kind: "family".to_string(),
+					directed: false,
+					notes: "Siblings\nsince birth".to_string(),
+				},
+				GraphEdge {
+					id: uuid::Uuid::parse_str("00000000-0000-0000-0000-000000000002").unwrap(),
+					source: b,
+					target: a,
+					kind: "contact".to_string(),
+					directed: true,
+					notes: String::new(),
+				},
+			],
+		}
+	}
+
+	#[test]
+	fn reads_extensions() {
+		assert_eq!(GraphFormat::from_extension("graphml"), Some(GraphFormat::GraphMl));
+		assert_eq!(GraphFormat::from_extension("gv"), Some(GraphFormat::Dot));
+		assert_eq!(GraphFormat::from_extension("json"), None);
+	}
+
+	#[test]
+	fn renders_escaped_graphml() {
+		let xml = graph().render(GraphFormat::GraphMl);
+		assert!(xml.contains("<data key=\"name\">Kalle &quot;K&quot; &lt;Kärkkäinen&gt;</data>"));
+		assert!(xml.contains(
+			"source=\"00000000-0000-0000-0000-00000000000a\" target=\"00000000-0000-0000-0000-00000000000b\" directed=\"false\""
+		));
+		assert!(xml.contains("directed=\"true\""));
+	}
+
+	#[test]
+	fn renders_dot_with_undirected_edges() {
+		let dot = graph().render(GraphFormat::Dot);
+		assert!(dot.contains("[label=\"Kalle \\\"K\\\" <Kärkkäinen>\", status=\"active\"]"));
+		assert!(dot.contains("label=\"family\", tooltip=\"Siblings\\nsince birth\", dir=none];"));
+		assert!(dot.contains("label=\"contact\", tooltip=\"\"];"));
+	}
+}
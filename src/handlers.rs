@@ -5,4 +5,18 @@ pub mod register_handler;
 pub mod users_handler;
 pub mod character_handler;
 pub mod article_handler;
-pub mod tag_handler;
\ No newline at end of file
+pub mod tag_handler;
+pub mod search_handler;
+pub mod comment_handler;
+pub mod reaction_handler;
+pub mod feed_handler;
+pub mod series_handler;
+pub mod link_handler;
+pub mod notification_handler;
+pub mod sheet_handler;
+pub mod roll_handler;
+pub mod experience_handler;
+pub mod item_handler;
+pub mod currency_handler;
+pub mod profile_handler;
+pub mod relationship_handler;
\ No newline at end of file
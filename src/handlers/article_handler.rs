@@ -1,27 +1,34 @@
+use crate::calendar::CALENDAR;
 use crate::errors::ServiceError;
+use crate::models::articles::ArticleChanges;
+use crate::models::listing::ListParams;
+use crate::models::slugs::Resolved;
 use crate::models::users::{LoggedUser, Pool};
 use crate::storage::*;
 use crate::handlers::*;
-use actix_web::{error::BlockingError, web, HttpResponse};
+use crate::handlers::character_handler::check_character_owner;
+use actix_web::{error::BlockingError, http::header, web, HttpRequest, HttpResponse};
 use log::trace;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug)]
 pub struct ArticleData {
-	pub user_id: uuid::Uuid,
 	pub title: String,
 	pub ingress: String,
 	pub body: String,
 	pub character_id: uuid::Uuid,
+	// Defaults to the campaign calendar's current date on create, kept on update
+	pub in_world_at: Option<chrono::NaiveDateTime>,
 }
 
+// Characters in attribution order, the first one is the lead author
 #[derive(Deserialize, Debug)]
-pub struct ArticleDeleteData {
-	pub id: uuid::Uuid,
-	pub character_id: uuid::Uuid,
-	pub user_id: uuid::Uuid,
+pub struct ArticleAuthorsData {
+	pub character_ids: Vec<uuid::Uuid>,
 }
 
+// The article goes to the owner of its lead character, which has to be the
+// caller's own unless they are a GM
 pub async fn add_article(
 	uuid_path: web::Path<String>,
 	article_data: web::Json<ArticleData>,
@@ -40,34 +47,52 @@ pub async fn add_article(
 		return Err(ServiceError::AdminRequired);
 	}
 
+	let in_world_at = article_data
+		.in_world_at
+		.or_else(|| CALENDAR.default_in_world_at(chrono::Local::now().naive_local()));
+	if let Some(at) = in_world_at {
+		CALENDAR.check(at)?;
+	}
+
 	let res = web::block(move || {
-		articles_storage::create_article(
-			id, 
-			article_data.title.clone(), 
-			article_data.ingress.clone(), 
-			article_data.body.clone(), 
-			article_data.character_id.clone(),
-			logged_user.email,
-			&pool,
-		)
+		let character = check_character_owner(article_data.character_id, &logged_user, &pool)?;
+		character.check_alive()?;
+		let new_article = ArticleChanges {
+			character_id: character.id,
+			title: article_data.title.clone(),
+			ingress: article_data.ingress.clone(),
+			body: article_data.body.clone(),
+			in_world_at,
+			updated_by: logged_user.email,
+		};
+		articles_storage::create_article(character.user_id, new_article, &pool)
+			.and_then(|article| links_storage::with_broken_links(article, &pool))
+			.map_err(ServiceError::from)
 	})
 	.await;
 	match res {
 		Ok(article) => Ok(HttpResponse::Ok().json(&article)),
 		Err(err) => match err {
-			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Error(service_error) => Err(service_error),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
 		},
 	}
 }
 pub async fn get_articles(
+	req: HttpRequest,
+	web::Query(params): web::Query<ListParams>,
 	pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!("Getting articles");
+	trace!("Getting articles: params = {:#?}", &params);
 
-	let res = web::block(move || articles_storage::query_articles(&pool)).await;
+	let res = web::block(move || {
+		articles_storage::query_articles(&params, &pool)
+			.and_then(|page| reactions_storage::page_with_reactions(page, &pool))
+			.map(|page| (page, params))
+	})
+	.await;
 	match res {
-		Ok(articles) => Ok(HttpResponse::Ok().json(&articles)),
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -75,6 +100,7 @@ pub async fn get_articles(
 	}
 }
 
+// id is the article UUID or slug; earlier slugs redirect to the current one
 pub async fn get_by_uuid(
 	id: web::Path<String>,
 	pool: web::Data<Pool>,
@@ -86,16 +112,20 @@ pub async fn get_by_uuid(
 		&logged_user
 	);
 
-	let user_id = logged_user.id;
-	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
-
-	if logged_user.isadmin == false && logged_user.id != user_id {
-		return Err(ServiceError::AdminRequired);
-	}
+	let key = id.into_inner();
 
-	let res = web::block(move || articles_storage::query_articles_by_article_uuid(article_id, &pool)).await;
+	let res = web::block(move || match articles_storage::resolve_article(key, &pool)? {
+		Resolved::Current(article) => reactions_storage::with_reactions(vec![article], &pool)
+			.and_then(|items| series_storage::with_series(items, &pool))
+			.map(Resolved::Current),
+		Resolved::Renamed(slug) => Ok(Resolved::Renamed(slug)),
+	})
+	.await;
 	match res {
-		Ok(article) => Ok(HttpResponse::Ok().json(&article)),
+		Ok(Resolved::Current(article)) => Ok(HttpResponse::Ok().json(&article)),
+		Ok(Resolved::Renamed(slug)) => Ok(HttpResponse::MovedPermanently()
+			.header(header::LOCATION, format!("/api/articles/{}", slug))
+			.finish()),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -104,24 +134,34 @@ pub async fn get_by_uuid(
 }
 
 pub async fn get_by_user_uuid(
-	article_data: web::Path<String>,
+	req: HttpRequest,
+	uuid_path: web::Path<String>,
+	web::Query(mut params): web::Query<ListParams>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
 	trace!(
-		"Getting user articles: logged_user = {:#?}",
+		"Getting user articles: params = {:#?} logged_user = {:#?}",
+		&params,
 		&logged_user
 	);
 
-	let user_id = logged_user.id;
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
 
 	if logged_user.isadmin == false && logged_user.id != user_id {
 		return Err(ServiceError::AdminRequired);
 	}
 
-	let res = web::block(move || articles_storage::query_articles_by_user_uuid(user_id, &pool)).await;
+	params.user_id = Some(user_id);
+
+	let res = web::block(move || {
+		articles_storage::query_articles(&params, &pool)
+			.and_then(|page| reactions_storage::page_with_reactions(page, &pool))
+			.map(|page| (page, params))
+	})
+	.await;
 	match res {
-		Ok(article) => Ok(HttpResponse::Ok().json(&article)),
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -129,24 +169,21 @@ pub async fn get_by_user_uuid(
 	}
 }
 
+// Any co-author may delete the article
 pub async fn delete_article(
 	id: web::Path<String>,
-	payload: web::Json<ArticleDeleteData>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!(
-		"Delete article: article_data = {:#?} logged_user = {:#?}",
-		&payload,
-		&logged_user
-	);
+	trace!("Delete article: id = {:#?} logged_user = {:#?}", &id, &logged_user);
 
 	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
-	if logged_user.isadmin == false && logged_user.id != payload.user_id {
-		return Err(ServiceError::AdminRequired);
-	}
 
-	let res = web::block(move || articles_storage::delete_article(article_id, &pool)).await;
+	let res = web::block(move || {
+		check_author(article_id, &logged_user, &pool)?;
+		articles_storage::delete_article(article_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
 	match res {
 		Ok(user) => Ok(HttpResponse::Ok().json(&user)),
 		Err(err) => match err {
@@ -171,24 +208,65 @@ pub async fn update_article(
 
 	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
 
-	if logged_user.isadmin == false && logged_user.id != payload.user_id {
-		return Err(ServiceError::AdminRequired);
+	if let Some(at) = payload.in_world_at {
+		CALENDAR.check(at)?;
 	}
 
 	let res = web::block(move || {
-		articles_storage::update_article(
-			article_id,
-			payload.title.clone(),
-			payload.ingress.clone(),
-			payload.body.clone(),
-			payload.character_id,
-			logged_user.email,
-			&pool,
-		)
+		check_author(article_id, &logged_user, &pool)?;
+
+		let authors = articles_storage::query_article_authors(article_id, &pool)?;
+		if !authors.iter().any(|author| author.character_id == payload.character_id) {
+			return Err(ServiceError::BadRequest(
+				"The character is not an author of the article, add it to the authors first".into(),
+			));
+		}
+
+		let changes = ArticleChanges {
+			character_id: payload.character_id,
+			title: payload.title.clone(),
+			ingress: payload.ingress.clone(),
+			body: payload.body.clone(),
+			in_world_at: payload.in_world_at,
+			updated_by: logged_user.email,
+		};
+		articles_storage::update_article(article_id, changes, &pool)
+			.and_then(|article| links_storage::with_broken_links(article, &pool))
+			.map_err(ServiceError::from)
 	})
 	.await;
 	match res {
 		Ok(userreservation) => Ok(HttpResponse::Ok().json(&userreservation)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Every co-author may edit the article
+fn check_author(article_id: uuid::Uuid, logged_user: &LoggedUser, pool: &web::Data<Pool>) -> Result<(), ServiceError> {
+	if logged_user.isadmin {
+		return Ok(());
+	}
+	if articles_storage::is_article_author(article_id, logged_user.id, pool)? {
+		return Ok(());
+	}
+	Err(ServiceError::AdminRequired)
+}
+
+pub async fn get_authors(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting article authors: id = {:#?}", &id);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		articles_storage::get_article(article_id, &pool)?;
+		articles_storage::query_bylines(vec![article_id], &pool)
+	})
+	.await;
+	match res {
+		Ok(bylines) => Ok(HttpResponse::Ok().json(&bylines)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -196,6 +274,58 @@ pub async fn update_article(
 	}
 }
 
+pub async fn set_authors(
+	id: web::Path<String>,
+	payload: web::Json<ArticleAuthorsData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Setting article authors: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let character_ids = payload.into_inner().character_ids;
+
+	if character_ids.is_empty() {
+		return Err(ServiceError::BadRequest("An article needs at least one author".into()));
+	}
+	if character_ids.iter().enumerate().any(|(i, character_id)| character_ids[..i].contains(character_id)) {
+		return Err(ServiceError::BadRequest("A character can be an author only once".into()));
+	}
+
+	let res = web::block(move || {
+		check_author(article_id, &logged_user, &pool)?;
+
+		// Players only add their own characters; those already on the article stay.
+		// Deceased authors keep their bylines but take on no new articles.
+		let authors = articles_storage::query_article_authors(article_id, &pool)?;
+		for character_id in &character_ids {
+			if !authors.iter().any(|author| author.character_id == *character_id) {
+				check_character_owner(*character_id, &logged_user, &pool)?.check_alive()?;
+			}
+		}
+		articles_storage::set_article_authors(article_id, character_ids, logged_user.email, &pool)?;
+		articles_storage::query_bylines(vec![article_id], &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(bylines) => Ok(HttpResponse::Ok().json(&bylines)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// The campaign calendar, for showing and picking in-world dates
+pub async fn get_calendar() -> Result<HttpResponse, ServiceError> {
+	Ok(HttpResponse::Ok().json(&*CALENDAR))
+}
+
 /*
 pub fn delete_article(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
 	let conn: &PgConnection = &pool.get().unwrap();
@@ -1,7 +1,12 @@
+use crate::calendar::CALENDAR;
 use crate::errors::ServiceError;
+use crate::models::characters::{Character, CharacterStatus};
+use crate::models::items::InventoryItemChanges;
+use crate::models::listing::ListParams;
+use crate::models::slugs::Resolved;
 use crate::models::users::{LoggedUser, Pool};
 use crate::storage::*;
-use actix_web::{error::BlockingError, web, HttpResponse};
+use actix_web::{error::BlockingError, http::header, web, HttpRequest, HttpResponse};
 use log::trace;
 use serde::{Deserialize, Serialize};
 
@@ -9,13 +14,79 @@ use serde::{Deserialize, Serialize};
 pub struct CharacterData {
 	pub name: String,
 	pub description: String,
-	pub user_id: uuid::Uuid,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct CharacterDeleteData {
-	pub id: uuid::Uuid,
-	pub user_id: uuid::Uuid,
+pub struct StatusQuery {
+	pub status: Option<CharacterStatus>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StatusData {
+	pub status: CharacterStatus,
+	// In-world date of death, defaults to the campaign calendar's current date
+	pub died_at: Option<chrono::NaiveDateTime>,
+	// Who the character goes to, for GMs handing characters over
+	pub user_id: Option<uuid::Uuid>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InventoryData {
+	pub item_id: uuid::Uuid,
+	pub quantity: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InventoryItemData {
+	// Kept when left out. Only GMs may raise it, players may use items up.
+	pub quantity: Option<i32>,
+	pub equipped: bool,
+	pub notes: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransferData {
+	pub to_character_id: uuid::Uuid,
+	pub item_id: uuid::Uuid,
+	pub quantity: i32,
+	pub note: Option<String>,
+}
+
+// Only the owner and GMs get at a character's own records: its sheet, experience,
+// inventory, account, profile and relationships
+pub fn check_character_owner(
+	character_id: uuid::Uuid,
+	logged_user: &LoggedUser,
+	pool: &web::Data<Pool>,
+) -> Result<Character, ServiceError> {
+	let character = characters_storage::get_character(character_id, pool)?;
+	if logged_user.isadmin == false && logged_user.id != character.user_id {
+		return Err(ServiceError::AdminRequired);
+	}
+	Ok(character)
+}
+
+// Players retire their characters, bring them back and write them dead. Making
+// and giving back NPCs and raising the dead is up to GMs.
+fn check_transition(from: &str, to: CharacterStatus, logged_user: &LoggedUser) -> Result<(), ServiceError> {
+	if from == to.as_str() {
+		return Err(ServiceError::BadRequest(format!("The character is already {}", from)));
+	}
+	if logged_user.isadmin {
+		return Ok(());
+	}
+	let played = from == CharacterStatus::Active.as_str() || from == CharacterStatus::Retired.as_str();
+	if played && to != CharacterStatus::Npc {
+		return Ok(());
+	}
+	Err(ServiceError::AdminRequired)
+}
+
+fn check_quantity(quantity: i32) -> Result<i32, ServiceError> {
+	if quantity < 1 {
+		return Err(ServiceError::BadRequest("Quantities start from 1".into()));
+	}
+	Ok(quantity)
 }
 
 pub async fn add_character(
@@ -55,26 +126,31 @@ pub async fn add_character(
 	}
 }
 
+// character_data is the character UUID or slug; earlier slugs redirect to the current one
 pub async fn get_by_character_uuid(
 	character_data: web::Path<String>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
 	trace!(
-		"Adding a favorite project: character_data = {:#?} logged_user = {:#?}",
+		"Getting character: character_data = {:#?} logged_user = {:#?}",
 		&character_data,
 		&logged_user
 	);
 
-	let user_id = logged_user.id;
-
-	if logged_user.isadmin == false && logged_user.id != user_id {
-		return Err(ServiceError::AdminRequired);
-	}
+	let key = character_data.into_inner();
 
-	let res = web::block(move || characters_storage::query_characters_by_character_uuid(user_id, &pool)).await;
+	let res = web::block(move || characters_storage::resolve_character(key, &pool)).await;
 	match res {
-		Ok(project) => Ok(HttpResponse::Ok().json(&project)),
+		Ok(Resolved::Current(character)) => {
+			if logged_user.isadmin == false && logged_user.id != character.user_id {
+				return Err(ServiceError::AdminRequired);
+			}
+			Ok(HttpResponse::Ok().json(vec![character]))
+		}
+		Ok(Resolved::Renamed(slug)) => Ok(HttpResponse::MovedPermanently()
+			.header(header::LOCATION, format!("/api/characters/{}", slug))
+			.finish()),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -83,24 +159,32 @@ pub async fn get_by_character_uuid(
 }
 
 pub async fn get_by_user_uuid(
-	character_data: web::Path<String>,
+	req: HttpRequest,
+	uuid_path: web::Path<String>,
+	web::Query(query): web::Query<StatusQuery>,
+	web::Query(params): web::Query<ListParams>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
 	trace!(
-		"Getting user characters: logged_user = {:#?}",
+		"Getting user characters: query = {:#?} params = {:#?} logged_user = {:#?}",
+		&query,
+		&params,
 		&logged_user
 	);
 
-	let user_id = logged_user.id;
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
 
 	if logged_user.isadmin == false && logged_user.id != user_id {
 		return Err(ServiceError::AdminRequired);
 	}
 
-	let res = web::block(move || characters_storage::query_characters_by_user_uuid(user_id, &pool)).await;
+	let res = web::block(move || {
+		characters_storage::query_characters_by_user_uuid(user_id, query.status, &params, &pool).map(|page| (page, params))
+	})
+	.await;
 	match res {
-		Ok(project) => Ok(HttpResponse::Ok().json(&project)),
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -110,26 +194,22 @@ pub async fn get_by_user_uuid(
 
 pub async fn delete_character(
 	id: web::Path<String>,
-	payload: web::Json<CharacterDeleteData>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!(
-		"Delete a favorite project: character_data = {:#?} logged_user = {:#?}",
-		&payload,
-		&logged_user
-	);
+	trace!("Delete character: id = {:#?} logged_user = {:#?}", &id, &logged_user);
 
 	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
-	if logged_user.isadmin == false && logged_user.id != payload.user_id {
-		return Err(ServiceError::AdminRequired);
-	}
 
-	let res = web::block(move || characters_storage::delete_character(character_id, &pool)).await;
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		characters_storage::delete_character(character_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
 	match res {
 		Ok(user) => Ok(HttpResponse::Ok().json(&user)),
 		Err(err) => match err {
-			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Error(service_error) => Err(service_error),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
 		},
 	}
@@ -150,11 +230,8 @@ pub async fn update_character(
 
 	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
 
-	if logged_user.isadmin == false && logged_user.id != payload.user_id {
-		return Err(ServiceError::AdminRequired);
-	}
-
 	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
 		characters_storage::update_character(
 			character_id,
 			payload.name.clone(),
@@ -162,13 +239,281 @@ pub async fn update_character(
 			logged_user.email,
 			&pool,
 		)
+		.map_err(ServiceError::from)
 	})
 	.await;
 	match res {
 		Ok(userreservation) => Ok(HttpResponse::Ok().json(&userreservation)),
 		Err(err) => match err {
-			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Moves the character along its life: retired, deceased on an in-world date or
+// an NPC owned by the GM making the change. Deceased characters are read-only
+// until a GM brings them back.
+pub async fn update_status(
+	id: web::Path<String>,
+	payload: web::Json<StatusData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating character status: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	if payload.user_id.is_some() && logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let died_at = match payload.status {
+		CharacterStatus::Deceased => {
+			let died_at = payload
+				.died_at
+				.or_else(|| CALENDAR.default_in_world_at(chrono::Local::now().naive_local()))
+				.ok_or_else(|| ServiceError::BadRequest("Deceased characters need an in-world date of death".into()))?;
+			CALENDAR.check(died_at)?;
+			Some(died_at)
+		}
+		_ if payload.died_at.is_some() => {
+			return Err(ServiceError::BadRequest("Only deceased characters have a date of death".into()));
+		}
+		_ => None,
+	};
+
+	let res = web::block(move || {
+		let character = check_character_owner(character_id, &logged_user, &pool)?;
+		check_transition(&character.status, payload.status, &logged_user)?;
+		let user_id = match (payload.user_id, payload.status) {
+			(Some(user_id), _) => user_id,
+			(None, CharacterStatus::Npc) => logged_user.id,
+			(None, _) => character.user_id,
+		};
+		characters_storage::update_status(
+			character_id,
+			character.status,
+			payload.status,
+			died_at,
+			user_id,
+			logged_user.email,
+			&pool,
+		)
+		.map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(character) => Ok(HttpResponse::Ok().json(&character)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_inventory(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting inventory: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?;
+		items_storage::query_inventory(character_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(inventory) => Ok(HttpResponse::Ok().json(&inventory)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// GMs hand out catalog items, on top of those already carried. Players get
+// theirs from each other by logged transfers.
+pub async fn add_inventory_item(
+	id: web::Path<String>,
+	payload: web::Json<InventoryData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding to inventory: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let quantity = check_quantity(payload.quantity.unwrap_or(1))?;
+
+	let res = web::block(move || {
+		characters_storage::get_character(character_id, &pool)?.check_alive()?;
+		items_storage::add_inventory_item(character_id, payload.item_id, quantity, logged_user.email, &pool)
+			.map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(inventory_item) => Ok(HttpResponse::Ok().json(&inventory_item)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_inventory_item(
+	path: web::Path<(String, String)>,
+	payload: web::Json<InventoryItemData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating inventory: path = {:#?} payload = {:#?} logged_user = {:#?}",
+		&path,
+		&payload,
+		&logged_user
+	);
+
+	let (character_id, inventory_item_id) = path.into_inner();
+	let character_id = uuid::Uuid::parse_str(&character_id)?;
+	let inventory_item_id = uuid::Uuid::parse_str(&inventory_item_id)?;
+	let payload = payload.into_inner();
+	let quantity = payload.quantity.map(check_quantity).transpose()?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		let changes = InventoryItemChanges {
+			quantity,
+			equipped: payload.equipped,
+			notes: payload.notes.trim().to_string(),
+			updated_by: logged_user.email,
+		};
+		items_storage::update_inventory_item(character_id, inventory_item_id, changes, logged_user.isadmin, &pool)?
+			.ok_or(ServiceError::AdminRequired)
+	})
+	.await;
+	match res {
+		Ok(inventory_item) => Ok(HttpResponse::Ok().json(&inventory_item)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_inventory_item(
+	path: web::Path<(String, String)>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Removing from inventory: path = {:#?} logged_user = {:#?}", &path, &logged_user);
+
+	let (character_id, inventory_item_id) = path.into_inner();
+	let character_id = uuid::Uuid::parse_str(&character_id)?;
+	let inventory_item_id = uuid::Uuid::parse_str(&inventory_item_id)?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		items_storage::delete_inventory_item(character_id, inventory_item_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(()) => Ok(HttpResponse::Ok().finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Transfers to and from the character
+pub async fn get_transfers(
+	req: HttpRequest,
+	id: web::Path<String>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting item transfers: id = {:#?} params = {:#?} logged_user = {:#?}",
+		&id,
+		&params,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?;
+		let page = items_storage::query_transfers(character_id, &params, &pool)?;
+		Ok((page, params))
+	})
+	.await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Gives items of the character to another character. The giving character's
+// owner or an admin makes the transfer.
+pub async fn transfer_items(
+	id: web::Path<String>,
+	payload: web::Json<TransferData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Transferring items: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	let quantity = check_quantity(payload.quantity)?;
+	if payload.to_character_id == character_id {
+		return Err(ServiceError::BadRequest("Items are transferred to another character".into()));
+	}
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		characters_storage::get_character(payload.to_character_id, &pool)?.check_alive()?;
+		items_storage::transfer_items(
+			character_id,
+			payload.to_character_id,
+			payload.item_id,
+			quantity,
+			payload.note.unwrap_or_default().trim().to_string(),
+			logged_user.email,
+			&pool,
+		)?
+		.ok_or_else(|| ServiceError::BadRequest("The character does not carry that many of the item".into()))
+	})
+	.await;
+	match res {
+		Ok(transfer) => Ok(HttpResponse::Ok().json(&transfer)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
 		},
 	}
-}
\ No newline at end of file
+}
@@ -0,0 +1,374 @@
+use crate::errors::{ForbiddenStruct, ForbiddenType, ServiceError};
+use crate::models::comments::{Comment, CommentThread};
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+const MAX_BODY_LENGTH: usize = 10000;
+
+#[derive(Deserialize, Debug)]
+pub struct CommentData {
+	pub body: String,
+	pub parent_id: Option<uuid::Uuid>,
+	pub character_id: Option<uuid::Uuid>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommentUpdateData {
+	pub body: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommentModerationData {
+	pub hidden: Option<bool>,
+	pub locked: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommentLockData {
+	pub locked: bool,
+}
+
+fn locked(description: &str) -> ServiceError {
+	ServiceError::Forbidden(ForbiddenStruct {
+		error_type: ForbiddenType::Locked,
+		description: Some(String::from(description)),
+		details: None,
+	})
+}
+
+fn check_body(body: &str) -> Result<String, ServiceError> {
+	let body = body.trim();
+	if body.is_empty() {
+		return Err(ServiceError::BadRequest("Comment is empty".into()));
+	}
+	if body.chars().count() > MAX_BODY_LENGTH {
+		return Err(ServiceError::BadRequest(format!(
+			"Comment is longer than {} characters",
+			MAX_BODY_LENGTH
+		)));
+	}
+	Ok(body.to_string())
+}
+
+// Locked articles and threads only take comments from admins (GMs). A thread is
+// a top level comment with its replies; hiding the top level comment closes it too.
+fn check_open(
+	article_id: uuid::Uuid,
+	thread: Option<&Comment>,
+	logged_user: &LoggedUser,
+	pool: &web::Data<Pool>,
+) -> Result<(), ServiceError> {
+	if logged_user.isadmin {
+		return Ok(());
+	}
+
+	let article = articles_storage::get_article(article_id, pool)?;
+	if article.comments_locked {
+		return Err(locked("Comments on this article are locked"));
+	}
+	if let Some(thread) = thread {
+		if thread.locked || thread.hidden {
+			return Err(locked("This comment thread is locked"));
+		}
+	}
+	Ok(())
+}
+
+// The top level comment of the thread the given comment belongs to
+fn thread_of(comment: &Comment, pool: &web::Data<Pool>) -> Result<Option<Comment>, ServiceError> {
+	match comment.parent_id {
+		Some(parent_id) => Ok(Some(comments_storage::get_comment(parent_id, pool)?)),
+		None => Ok(None),
+	}
+}
+
+// Text of deleted comments is never shown, hidden ones only to their author and admins
+fn can_read(comment: &Comment, logged_user: &Option<LoggedUser>) -> bool {
+	if comment.deleted_at.is_some() {
+		return false;
+	}
+	match logged_user {
+		Some(user) => !comment.hidden || user.isadmin || user.id == comment.user_id,
+		None => !comment.hidden,
+	}
+}
+
+fn visible(comment: Comment, logged_user: &Option<LoggedUser>) -> Comment {
+	if can_read(&comment, logged_user) {
+		return comment;
+	}
+	comment.redacted()
+}
+
+pub async fn get_comments(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting comments: id = {:#?}", &id);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		articles_storage::get_article(article_id, &pool)?;
+		comments_storage::query_comments_by_article_uuid(article_id, &pool)
+	})
+	.await;
+	match res {
+		Ok(comments) => {
+			let (top_level, replies): (Vec<Comment>, Vec<Comment>) =
+				comments.into_iter().partition(|comment| comment.parent_id.is_none());
+
+			let mut threads: Vec<CommentThread> = top_level
+				.into_iter()
+				.map(|comment| CommentThread {
+					comment: visible(comment, &logged_user),
+					replies: Vec::new(),
+				})
+				.collect();
+			for reply in replies {
+				if let Some(thread) = threads.iter_mut().find(|thread| Some(thread.comment.id) == reply.parent_id) {
+					thread.replies.push(visible(reply, &logged_user));
+				}
+			}
+
+			Ok(HttpResponse::Ok().json(&threads))
+		}
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn add_comment(
+	id: web::Path<String>,
+	comment_data: web::Json<CommentData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding a comment: id = {:#?} comment_data = {:#?} logged_user = {:#?}",
+		&id,
+		&comment_data,
+		&logged_user
+	);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let body = check_body(&comment_data.body)?;
+
+	let res = web::block(move || {
+		let parent = match comment_data.parent_id {
+			Some(parent_id) => {
+				let parent = comments_storage::get_comment(parent_id, &pool)?;
+				if parent.article_id != article_id {
+					return Err(ServiceError::BadRequest("Parent comment is on another article".into()));
+				}
+				if parent.parent_id.is_some() {
+					return Err(ServiceError::BadRequest("Replies can only be made to top level comments".into()));
+				}
+				if parent.deleted_at.is_some() {
+					return Err(ServiceError::Gone);
+				}
+				Some(parent)
+			}
+			None => None,
+		};
+		check_open(article_id, parent.as_ref(), &logged_user, &pool)?;
+
+		if let Some(character_id) = comment_data.character_id {
+			let character = characters_storage::get_character(character_id, &pool)?;
+			if logged_user.isadmin == false && character.user_id != logged_user.id {
+				return Err(ServiceError::AdminRequired);
+			}
+			character.check_alive()?;
+		}
+
+		comments_storage::create_comment(
+			article_id,
+			comment_data.parent_id,
+			logged_user.id,
+			comment_data.character_id,
+			body,
+			logged_user.email,
+			&pool,
+		)
+		.map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(comment) => Ok(HttpResponse::Ok().json(&comment)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_comment(
+	id: web::Path<String>,
+	comment_data: web::Json<CommentUpdateData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating a comment: id = {:#?} comment_data = {:#?} logged_user = {:#?}",
+		&id,
+		&comment_data,
+		&logged_user
+	);
+
+	let comment_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let body = check_body(&comment_data.body)?;
+
+	let res = web::block(move || {
+		let comment = comments_storage::get_comment(comment_id, &pool)?;
+		// Only the author may change what a comment says, admins can hide it instead
+		if comment.user_id != logged_user.id {
+			return Err(ServiceError::AdminRequired);
+		}
+		if comment.deleted_at.is_some() {
+			return Err(ServiceError::Gone);
+		}
+		let thread = thread_of(&comment, &pool)?;
+		check_open(comment.article_id, Some(thread.as_ref().unwrap_or(&comment)), &logged_user, &pool)?;
+
+		comments_storage::update_comment(comment_id, body, logged_user.email, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(comment) => Ok(HttpResponse::Ok().json(&comment)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_comment(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Deleting a comment: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let comment_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		let comment = comments_storage::get_comment(comment_id, &pool)?;
+		if logged_user.isadmin == false && comment.user_id != logged_user.id {
+			return Err(ServiceError::AdminRequired);
+		}
+
+		comments_storage::delete_comment(comment_id, logged_user.email, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(comment) => Ok(HttpResponse::Ok().json(comment.redacted())),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_comment_history(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting comment history: id = {:#?}", &id);
+
+	let comment_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		let comment = comments_storage::get_comment(comment_id, &pool)?;
+		// The history would reveal the text of a removed comment
+		if !can_read(&comment, &logged_user) {
+			return Err(ServiceError::Gone);
+		}
+
+		comments_storage::query_comment_revisions(comment_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(revisions) => Ok(HttpResponse::Ok().json(&revisions)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn moderate_comment(
+	id: web::Path<String>,
+	moderation_data: web::Json<CommentModerationData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Moderating a comment: id = {:#?} moderation_data = {:#?} logged_user = {:#?}",
+		&id,
+		&moderation_data,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let comment_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		comments_storage::moderate_comment(
+			comment_id,
+			moderation_data.hidden,
+			moderation_data.locked,
+			logged_user.email,
+			&pool,
+		)
+	})
+	.await;
+	match res {
+		Ok(comment) => Ok(HttpResponse::Ok().json(&comment)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn lock_comments(
+	id: web::Path<String>,
+	lock_data: web::Json<CommentLockData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Locking article comments: id = {:#?} lock_data = {:#?} logged_user = {:#?}",
+		&id,
+		&lock_data,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		articles_storage::set_comments_locked(article_id, lock_data.locked, logged_user.email, &pool)
+	})
+	.await;
+	match res {
+		Ok(article) => Ok(HttpResponse::Ok().json(&article)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
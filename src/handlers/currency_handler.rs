@@ -0,0 +1,232 @@
+use crate::calendar::CALENDAR;
+use crate::errors::ServiceError;
+use crate::handlers::character_handler::check_character_owner;
+use crate::models::currency::{NewAdjustment, NewPayment};
+use crate::models::listing::ListParams;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+const MAX_AMOUNT: i64 = 1_000_000_000_000;
+
+#[derive(Deserialize, Debug)]
+pub struct PaymentData {
+	pub payee_character_id: uuid::Uuid,
+	pub amount: i64,
+	pub memo: Option<String>,
+	// Defaults to the campaign calendar's current date
+	pub in_world_at: Option<chrono::NaiveDateTime>,
+}
+
+// Adds to the balance when positive, takes away when negative
+#[derive(Deserialize, Debug)]
+pub struct AdjustmentData {
+	pub amount: i64,
+	pub memo: String,
+	pub in_world_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AccountData {
+	pub credit_limit: i64,
+}
+
+fn in_world_or_default(in_world_at: Option<chrono::NaiveDateTime>) -> Result<Option<chrono::NaiveDateTime>, ServiceError> {
+	let in_world_at = in_world_at.or_else(|| CALENDAR.default_in_world_at(chrono::Local::now().naive_local()));
+	if let Some(at) = in_world_at {
+		CALENDAR.check(at)?;
+	}
+	Ok(in_world_at)
+}
+
+pub async fn get_account(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting currency account: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?;
+		currency_storage::get_balance(character_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(balance) => Ok(HttpResponse::Ok().json(&balance)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Lets the balance go as far below zero as the credit limit. Only GMs set it.
+pub async fn update_account(
+	id: web::Path<String>,
+	payload: web::Json<AccountData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating currency account: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	if payload.credit_limit < 0 || payload.credit_limit > MAX_AMOUNT {
+		return Err(ServiceError::BadRequest(format!("Credit limits are from 0 to {}", MAX_AMOUNT)));
+	}
+
+	let res = web::block(move || {
+		characters_storage::get_character(character_id, &pool)?.check_alive()?;
+		currency_storage::set_credit_limit(character_id, payload.credit_limit, logged_user.email, &pool)
+			.map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(balance) => Ok(HttpResponse::Ok().json(&balance)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_transactions(
+	req: HttpRequest,
+	id: web::Path<String>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting currency transactions: id = {:#?} params = {:#?} logged_user = {:#?}",
+		&id,
+		&params,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?;
+		let page = currency_storage::query_ledger(character_id, &params, &pool)?;
+		Ok((page, params))
+	})
+	.await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Pays from the character to another character
+pub async fn add_payment(
+	id: web::Path<String>,
+	payload: web::Json<PaymentData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding a payment: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	if payload.amount < 1 || payload.amount > MAX_AMOUNT {
+		return Err(ServiceError::BadRequest(format!("Amounts are from 1 to {}", MAX_AMOUNT)));
+	}
+	if payload.payee_character_id == character_id {
+		return Err(ServiceError::BadRequest("Payments go to another character".into()));
+	}
+	let in_world_at = in_world_or_default(payload.in_world_at)?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		characters_storage::get_character(payload.payee_character_id, &pool)?.check_alive()?;
+		let new_payment = NewPayment {
+			payer_character_id: character_id,
+			payee_character_id: payload.payee_character_id,
+			amount: payload.amount,
+			memo: payload.memo.unwrap_or_default().trim().to_string(),
+			in_world_at,
+			user_id: logged_user.id,
+			updated_by: logged_user.email,
+		};
+		currency_storage::pay(new_payment, &pool)?
+		.ok_or_else(|| ServiceError::BadRequest("The payment would exceed the balance".into()))
+	})
+	.await;
+	match res {
+		Ok(transaction) => Ok(HttpResponse::Ok().json(&transaction)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// GM adjustments are booked against the treasury and need a memo
+pub async fn add_adjustment(
+	id: web::Path<String>,
+	payload: web::Json<AdjustmentData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding an adjustment: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	let memo = payload.memo.trim().to_string();
+	if payload.amount == 0 || payload.amount.abs() > MAX_AMOUNT || memo.is_empty() {
+		return Err(ServiceError::BadRequest(format!(
+			"Adjustments need a memo and an amount from 1 to {} either way",
+			MAX_AMOUNT
+		)));
+	}
+	let in_world_at = in_world_or_default(payload.in_world_at)?;
+
+	let res = web::block(move || {
+		characters_storage::get_character(character_id, &pool)?.check_alive()?;
+		let new_adjustment = NewAdjustment {
+			character_id,
+			amount: payload.amount,
+			memo,
+			in_world_at,
+			user_id: logged_user.id,
+			updated_by: logged_user.email,
+		};
+		currency_storage::adjust(new_adjustment, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(transaction) => Ok(HttpResponse::Ok().json(&transaction)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
@@ -0,0 +1,204 @@
+use crate::errors::ServiceError;
+use crate::handlers::character_handler::check_character_owner;
+use crate::models::experience::{ExperienceEntry, ExperienceKind};
+use crate::models::listing::ListParams;
+use crate::models::sheets::SheetData;
+use crate::models::users::{LoggedUser, Pool};
+use crate::rules::RULESET;
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+// An award when positive, a spend when negative
+#[derive(Deserialize, Debug)]
+pub struct ExperienceData {
+	pub amount: i32,
+	pub reason: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AdvancementData {
+	// The skill or attribute to raise by one
+	pub key: String,
+	pub reason: Option<String>,
+}
+
+fn not_enough() -> ServiceError {
+	ServiceError::BadRequest("Not enough experience".into())
+}
+
+pub async fn get_experience(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting experience: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?;
+		experience_storage::query_summary(character_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(summary) => Ok(HttpResponse::Ok().json(&summary)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// The character's experience ledger
+pub async fn get_history(
+	req: HttpRequest,
+	id: web::Path<String>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting experience history: id = {:#?} params = {:#?} logged_user = {:#?}",
+		&id,
+		&params,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?;
+		let page = experience_storage::query_history(character_id, &params, &pool)?;
+		Ok((page, params))
+	})
+	.await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Awards or takes away experience. Only GMs do this.
+pub async fn add_experience(
+	id: web::Path<String>,
+	payload: web::Json<ExperienceData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding experience: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let reason = payload.reason.trim().to_string();
+	if payload.amount == 0 || reason.is_empty() {
+		return Err(ServiceError::BadRequest("Experience needs a reason and an amount other than zero".into()));
+	}
+	let kind = if payload.amount > 0 {
+		ExperienceKind::Award
+	} else {
+		ExperienceKind::Spend
+	};
+
+	let new_entry = ExperienceEntry {
+		id: uuid::Uuid::new_v4(),
+		character_id,
+		user_id: logged_user.id,
+		kind: kind.as_str().to_string(),
+		amount: payload.amount,
+		reason,
+		advanced_key: None,
+		advanced_from: None,
+		advanced_to: None,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: logged_user.email,
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	let res = web::block(move || {
+		characters_storage::get_character(character_id, &pool)?.check_alive()?;
+		experience_storage::add_entry(new_entry, None, &pool)?.ok_or_else(not_enough)
+	})
+	.await;
+	match res {
+		Ok(entry) => Ok(HttpResponse::Ok().json(&entry)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Spends experience on raising a skill or attribute of the character's sheet
+// by one, at the cost the ruleset sets
+pub async fn add_advancement(
+	id: web::Path<String>,
+	payload: web::Json<AdvancementData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding an advancement: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		let sheet = sheets_storage::get_sheet(character_id, &pool)?;
+		if sheet.ruleset != RULESET.name || sheet.ruleset_version != RULESET.version {
+			return Err(ServiceError::BadRequest(
+				"Save the sheet under the current ruleset before advancing".into(),
+			));
+		}
+		let data: SheetData = serde_json::from_value(sheet.data.clone()).map_err(|_| ServiceError::InternalServerError)?;
+		let advancement = RULESET.advance(payload.key.trim(), &data)?;
+		let new_data = serde_json::to_value(&advancement.data).map_err(|_| ServiceError::InternalServerError)?;
+		let cost = i32::try_from(advancement.cost).map_err(|_| {
+			ServiceError::BadRequest(format!("Raising {} costs more experience than can be spent", advancement.key))
+		})?;
+
+		let new_entry = ExperienceEntry {
+			id: uuid::Uuid::new_v4(),
+			character_id,
+			user_id: logged_user.id,
+			kind: ExperienceKind::Advancement.as_str().to_string(),
+			amount: -cost,
+			reason: payload
+				.reason
+				.map(|reason| reason.trim().to_string())
+				.unwrap_or_else(|| format!("{} {} to {}", advancement.key, advancement.current, advancement.new)),
+			advanced_key: Some(advancement.key),
+			advanced_from: Some(advancement.current as i32),
+			advanced_to: Some(advancement.new as i32),
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: logged_user.email,
+			updated_at: chrono::Local::now().naive_local(),
+		};
+		experience_storage::add_entry(new_entry, Some((sheet.data, new_data)), &pool)?.ok_or_else(not_enough)
+	})
+	.await;
+	match res {
+		Ok(entry) => Ok(HttpResponse::Ok().json(&entry)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
@@ -0,0 +1,193 @@
+use crate::errors::ServiceError;
+use crate::feeds::{rfc2822, utc, Feed, FeedEntry, FeedFormat, PUBLIC_URL};
+use crate::models::listing::ListParams;
+use crate::models::users::Pool;
+use crate::storage::*;
+use actix_web::http::header;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use chrono::{NaiveDate, NaiveDateTime, SubsecRound, Utc};
+use log::trace;
+use std::collections::HashMap;
+
+const FEED_LIMIT: i64 = 50;
+
+#[derive(Debug)]
+enum FeedSubject {
+	All,
+	Character(uuid::Uuid),
+	Tag(uuid::Uuid),
+	User(uuid::Uuid),
+}
+
+fn parse_format(format: &str) -> Result<FeedFormat, ServiceError> {
+	FeedFormat::from_extension(format).ok_or_else(|| ServiceError::BadRequest(format!("Unknown feed format: {}", format)))
+}
+
+pub async fn get_articles_feed(
+	req: HttpRequest,
+	format: web::Path<String>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	let format = parse_format(&format.into_inner())?;
+	feed_response(req, FeedSubject::All, format, params, pool).await
+}
+
+pub async fn get_character_feed(
+	req: HttpRequest,
+	path: web::Path<(String, String)>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	let (id, format) = path.into_inner();
+	let subject = FeedSubject::Character(uuid::Uuid::parse_str(&id)?);
+	feed_response(req, subject, parse_format(&format)?, params, pool).await
+}
+
+pub async fn get_tag_feed(
+	req: HttpRequest,
+	path: web::Path<(String, String)>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	let (id, format) = path.into_inner();
+	let subject = FeedSubject::Tag(uuid::Uuid::parse_str(&id)?);
+	feed_response(req, subject, parse_format(&format)?, params, pool).await
+}
+
+pub async fn get_user_feed(
+	req: HttpRequest,
+	path: web::Path<(String, String)>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	let (id, format) = path.into_inner();
+	let subject = FeedSubject::User(uuid::Uuid::parse_str(&id)?);
+	feed_response(req, subject, parse_format(&format)?, params, pool).await
+}
+
+async fn feed_response(
+	req: HttpRequest,
+	subject: FeedSubject,
+	format: FeedFormat,
+	params: ListParams,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting feed: subject = {:#?} format = {:#?} params = {:#?}",
+		&subject,
+		&format,
+		&params
+	);
+
+	let self_link = format!("{}{}", PUBLIC_URL.as_str(), req.uri());
+	let res = web::block(move || build_feed(subject, params, self_link, &pool)).await;
+	let feed = match res {
+		Ok(feed) => feed,
+		Err(err) => {
+			return match err {
+				BlockingError::Error(service_error) => Err(service_error),
+				BlockingError::Canceled => Err(ServiceError::InternalServerError),
+			}
+		}
+	};
+
+	let etag = feed.etag();
+	let last_modified = rfc2822(&feed.updated);
+	if not_modified(&req, &etag, &feed.updated) {
+		return Ok(HttpResponse::NotModified()
+			.header(header::ETAG, etag)
+			.header(header::LAST_MODIFIED, last_modified)
+			.finish());
+	}
+
+	Ok(HttpResponse::Ok()
+		.content_type(format.content_type())
+		.header(header::ETAG, etag)
+		.header(header::LAST_MODIFIED, last_modified)
+		.body(feed.render(format)))
+}
+
+// If-None-Match wins over If-Modified-Since when both are sent
+fn not_modified(req: &HttpRequest, etag: &str, updated: &NaiveDateTime) -> bool {
+	let headers = req.headers();
+	if let Some(value) = headers.get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok()) {
+		return value
+			.split(',')
+			.map(str::trim)
+			.any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+	}
+	if let Some(since) = headers
+		.get(header::IF_MODIFIED_SINCE)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+	{
+		// HTTP dates have no fractions of a second
+		return utc(updated).trunc_subsecs(0) <= since.with_timezone(&Utc);
+	}
+	false
+}
+
+// The listing parameters filter and sort the feed like the article listing,
+// e.g. ?sort=in_world_at&direction=desc, but a feed is never longer than FEED_LIMIT
+fn build_feed(
+	subject: FeedSubject,
+	mut params: ListParams,
+	self_link: String,
+	pool: &web::Data<Pool>,
+) -> Result<Feed, ServiceError> {
+	params.limit = Some(params.limit().min(FEED_LIMIT));
+
+	// The subject's own timestamp is used as the feed's when it has no articles yet
+	let (title, fallback_updated) = match subject {
+		FeedSubject::All => (
+			String::from("HKIbook"),
+			NaiveDate::from_ymd_opt(1970, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+		),
+		FeedSubject::Character(id) => {
+			let character = characters_storage::get_character(id, pool)?;
+			params.character_id = Some(id);
+			(format!("HKIbook: {}", character.name), character.updated_at)
+		}
+		FeedSubject::Tag(id) => {
+			let tag = tags_storage::get_tag(id, pool)?;
+			params.tags_all.get_or_insert_with(Vec::new).push(id);
+			(format!("HKIbook: #{}", tag.title), tag.updated_at)
+		}
+		FeedSubject::User(id) => {
+			let user = users_storage::get(id, pool)?;
+			params.user_id = Some(id);
+			(format!("HKIbook: {}", user.username), user.updated_at)
+		}
+	};
+
+	let page = articles_storage::query_articles(&params, pool)?;
+	let article_ids = page.items.iter().map(|article| article.id).collect();
+	let mut authors: HashMap<uuid::Uuid, Vec<String>> = HashMap::new();
+	for byline in articles_storage::query_bylines(article_ids, pool)? {
+		authors.entry(byline.article_id).or_default().push(byline.character_name);
+	}
+
+	let entries: Vec<FeedEntry> = page
+		.items
+		.into_iter()
+		.map(|article| FeedEntry {
+			id: article.id,
+			authors: authors.remove(&article.id).unwrap_or_default(),
+			link: format!("{}/app/hkibook/{}", PUBLIC_URL.as_str(), article.id),
+			title: article.title,
+			summary: article.ingress,
+			content: article.body,
+			published: article.created_at,
+			updated: article.updated_at,
+		})
+		.collect();
+
+	Ok(Feed {
+		title,
+		self_link,
+		link: format!("{}/app/hkibook", PUBLIC_URL.as_str()),
+		updated: entries.iter().map(|entry| entry.updated).max().unwrap_or(fallback_updated),
+		entries,
+	})
+}
@@ -0,0 +1,164 @@
+use crate::errors::ServiceError;
+use crate::models::items::ItemCategory;
+use crate::models::listing::ListParams;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ItemData {
+	pub name: String,
+	pub category: ItemCategory,
+	pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CategoryQuery {
+	pub category: Option<ItemCategory>,
+}
+
+fn check_name(name: &str) -> Result<String, ServiceError> {
+	let name = name.trim();
+	if name.is_empty() || name.chars().count() > 100 {
+		return Err(ServiceError::BadRequest("An item is named with 1 to 100 characters".into()));
+	}
+	Ok(name.to_string())
+}
+
+pub async fn get_items(
+	req: HttpRequest,
+	web::Query(query): web::Query<CategoryQuery>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting items: query = {:#?} params = {:#?} logged_user = {:#?}",
+		&query,
+		&params,
+		&logged_user
+	);
+
+	let res = web::block(move || items_storage::query_items(query.category, &params, &pool).map(|page| (page, params))).await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_item(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting item: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let item_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || items_storage::get_item(item_id, &pool)).await;
+	match res {
+		Ok(item) => Ok(HttpResponse::Ok().json(&item)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// The catalog is kept by GMs
+pub async fn add_item(
+	payload: web::Json<ItemData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Adding an item: payload = {:#?} logged_user = {:#?}", &payload, &logged_user);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let payload = payload.into_inner();
+	let name = check_name(&payload.name)?;
+
+	let res = web::block(move || {
+		items_storage::create_item(
+			name,
+			payload.category,
+			payload.description.unwrap_or_default(),
+			logged_user.email,
+			&pool,
+		)
+	})
+	.await;
+	match res {
+		Ok(item) => Ok(HttpResponse::Ok().json(&item)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_item(
+	id: web::Path<String>,
+	payload: web::Json<ItemData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating an item: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let item_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	let name = check_name(&payload.name)?;
+
+	let res = web::block(move || {
+		let description = match payload.description {
+			Some(description) => description,
+			None => items_storage::get_item(item_id, &pool)?.description,
+		};
+		items_storage::update_item(item_id, name, payload.category, description, logged_user.email, &pool)
+	})
+	.await;
+	match res {
+		Ok(item) => Ok(HttpResponse::Ok().json(&item)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Items still carried by characters cannot be deleted
+pub async fn delete_item(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Deleting an item: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let item_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || items_storage::delete_item(item_id, &pool)).await;
+	match res {
+		Ok(()) => Ok(HttpResponse::Ok().finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
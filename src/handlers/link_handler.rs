@@ -0,0 +1,92 @@
+use crate::errors::ServiceError;
+use crate::models::slugs::SlugOwner;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpResponse};
+use log::trace;
+
+// [[links]] of an article, broken ones without target_id
+pub async fn get_links(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting article links: id = {:#?}", &id);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		articles_storage::get_article(article_id, &pool)?;
+		links_storage::query_article_links(article_id, &pool)
+	})
+	.await;
+	match res {
+		Ok(links) => Ok(HttpResponse::Ok().json(&links)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_article_backlinks(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting article backlinks: id = {:#?}", &id);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		articles_storage::get_article(article_id, &pool)?;
+		links_storage::query_backlinks(SlugOwner::Article, article_id, &pool)
+	})
+	.await;
+	match res {
+		Ok(backlinks) => Ok(HttpResponse::Ok().json(&backlinks)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_character_backlinks(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting character backlinks: id = {:#?}", &id);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		characters_storage::get_character(character_id, &pool)?;
+		links_storage::query_backlinks(SlugOwner::Character, character_id, &pool)
+	})
+	.await;
+	match res {
+		Ok(backlinks) => Ok(HttpResponse::Ok().json(&backlinks)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Broken links in the articles the user has written, for fixing them
+pub async fn get_broken_links(
+	uuid_path: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting broken links: user_id = {:#?} logged_user = {:#?}",
+		&uuid_path,
+		&logged_user
+	);
+
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
+
+	if logged_user.isadmin == false && logged_user.id != user_id {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let res = web::block(move || links_storage::query_broken_links(user_id, &pool)).await;
+	match res {
+		Ok(broken) => Ok(HttpResponse::Ok().json(&broken)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
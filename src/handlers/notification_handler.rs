@@ -0,0 +1,184 @@
+use crate::errors::ServiceError;
+use crate::models::listing::ListParams;
+use crate::models::notifications::NotificationKind;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct NotificationQuery {
+	pub unread: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NotificationSettingData {
+	pub kind: NotificationKind,
+	pub enabled: bool,
+}
+
+fn check_user(user_id: uuid::Uuid, logged_user: &LoggedUser) -> Result<(), ServiceError> {
+	if logged_user.isadmin == false && logged_user.id != user_id {
+		return Err(ServiceError::AdminRequired);
+	}
+	Ok(())
+}
+
+pub async fn get_notifications(
+	req: HttpRequest,
+	uuid_path: web::Path<String>,
+	web::Query(params): web::Query<ListParams>,
+	web::Query(query): web::Query<NotificationQuery>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting notifications: params = {:#?} query = {:#?} logged_user = {:#?}",
+		&params,
+		&query,
+		&logged_user
+	);
+
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
+	check_user(user_id, &logged_user)?;
+	let unread = query.unread.unwrap_or(false);
+
+	let res = web::block(move || {
+		notifications_storage::query_notifications(user_id, unread, &params, &pool).map(|page| (page, params))
+	})
+	.await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn read_notification(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Reading a notification: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let notification_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		let notification = notifications_storage::get_notification(notification_id, &pool)?;
+		if notification.user_id != logged_user.id {
+			return Err(ServiceError::AdminRequired);
+		}
+		notifications_storage::mark_read(notification.user_id, Some(notification_id), logged_user.email, &pool)?;
+		notifications_storage::get_notification(notification_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(notification) => Ok(HttpResponse::Ok().json(&notification)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn read_all_notifications(
+	uuid_path: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Reading all notifications: user_id = {:#?} logged_user = {:#?}",
+		&uuid_path,
+		&logged_user
+	);
+
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
+	if logged_user.id != user_id {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let res = web::block(move || notifications_storage::mark_read(user_id, None, logged_user.email, &pool)).await;
+	match res {
+		Ok(_) => Ok(HttpResponse::Ok().finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_settings(
+	uuid_path: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting notification settings: user_id = {:#?} logged_user = {:#?}",
+		&uuid_path,
+		&logged_user
+	);
+
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
+	check_user(user_id, &logged_user)?;
+
+	let res = web::block(move || notifications_storage::query_settings(user_id, &pool)).await;
+	match res {
+		Ok(settings) => Ok(HttpResponse::Ok().json(&settings)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_setting(
+	uuid_path: web::Path<String>,
+	payload: web::Json<NotificationSettingData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating a notification setting: user_id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&uuid_path,
+		&payload,
+		&logged_user
+	);
+
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
+	check_user(user_id, &logged_user)?;
+
+	let res = web::block(move || {
+		notifications_storage::set_setting(user_id, payload.kind, payload.enabled, logged_user.email, &pool)
+	})
+	.await;
+	match res {
+		Ok(settings) => Ok(HttpResponse::Ok().json(&settings)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Articles mentioning the character
+pub async fn get_character_mentions(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting character mentions: id = {:#?}", &id);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		characters_storage::get_character(character_id, &pool)?;
+		notifications_storage::query_mentioning_articles(character_id, &pool)
+	})
+	.await;
+	match res {
+		Ok(articles) => Ok(HttpResponse::Ok().json(&articles)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
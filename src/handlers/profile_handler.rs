@@ -0,0 +1,217 @@
+use crate::errors::ServiceError;
+use crate::handlers::character_handler::check_character_owner;
+use crate::handlers::sheet_handler;
+use crate::models::characters::Character;
+use crate::models::profiles::{CharacterProfile, ProfileView, Visibility};
+use crate::models::slugs::Resolved;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, http::header, web, HttpResponse};
+use diesel::result::Error::NotFound;
+use log::trace;
+use serde::Deserialize;
+
+const MAX_AFFILIATIONS: usize = 20;
+
+// Saves the whole player-written profile
+#[derive(Deserialize, Debug)]
+pub struct ProfileData {
+	pub portrait_url: String,
+	pub bio: String,
+	pub affiliations: Vec<String>,
+	pub portrait_visibility: Visibility,
+	pub bio_visibility: Visibility,
+	pub affiliations_visibility: Visibility,
+	pub sheet_visibility: Visibility,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NotesData {
+	pub gm_notes: String,
+	pub gm_notes_visibility: Visibility,
+}
+
+fn check_portrait(portrait_url: &str) -> Result<String, ServiceError> {
+	let portrait_url = portrait_url.trim();
+	if portrait_url.is_empty() {
+		return Ok(String::new());
+	}
+	match url::Url::parse(portrait_url) {
+		Ok(url) if (url.scheme() == "https" || url.scheme() == "http") && portrait_url.len() <= 500 => {
+			Ok(portrait_url.to_string())
+		}
+		_ => Err(ServiceError::BadRequest("A portrait is an http(s) URL of up to 500 characters".into())),
+	}
+}
+
+fn check_affiliations(affiliations: Vec<String>) -> Result<Vec<String>, ServiceError> {
+	let affiliations: Vec<String> = affiliations.iter().map(|a| a.trim().to_string()).collect();
+	if affiliations.len() > MAX_AFFILIATIONS
+		|| affiliations.iter().any(|a| a.is_empty() || a.chars().count() > 100)
+	{
+		return Err(ServiceError::BadRequest(format!(
+			"A character has up to {} affiliations of 1 to 100 characters",
+			MAX_AFFILIATIONS
+		)));
+	}
+	if affiliations.iter().enumerate().any(|(i, a)| affiliations[..i].contains(a)) {
+		return Err(ServiceError::BadRequest("An affiliation can be listed only once".into()));
+	}
+	Ok(affiliations)
+}
+
+fn check_text(text: &str, what: &str) -> Result<String, ServiceError> {
+	let text = text.trim();
+	if text.chars().count() > 10000 {
+		return Err(ServiceError::BadRequest(format!("{} is up to 10000 characters", what)));
+	}
+	Ok(text.to_string())
+}
+
+// Leaves out what the viewer may not see. Owners always see what they wrote
+// themselves; the GM notes are shown to them only as their visibility allows.
+fn view(
+	character: Character,
+	profile: Option<CharacterProfile>,
+	logged_user: &Option<LoggedUser>,
+	pool: &web::Data<Pool>,
+) -> Result<ProfileView, ServiceError> {
+	let profile = profile.unwrap_or_else(|| CharacterProfile::new(character.id, character.updated_by.clone()));
+	let visibility = profile.visibility();
+	let is_owner = matches!(logged_user, Some(user) if user.id == character.user_id);
+	let is_gm = matches!(logged_user, Some(user) if user.isadmin);
+	let sees = |field: Visibility| is_owner || field.allows(character.user_id, logged_user);
+
+	let sheet = if sees(visibility.sheet) {
+		match sheets_storage::get_sheet(character.id, pool) {
+			Ok(sheet) => Some(sheet_handler::view(sheet)?),
+			Err(NotFound) => None,
+			Err(err) => return Err(err.into()),
+		}
+	} else {
+		None
+	};
+
+	Ok(ProfileView {
+		character_id: character.id,
+		portrait_url: Some(profile.portrait_url).filter(|_| sees(visibility.portrait)),
+		bio: Some(profile.bio).filter(|_| sees(visibility.bio)),
+		affiliations: Some(profile.affiliations).filter(|_| sees(visibility.affiliations)),
+		sheet,
+		gm_notes: Some(profile.gm_notes).filter(|_| visibility.gm_notes.allows(character.user_id, logged_user)),
+		visibility: Some(visibility).filter(|_| is_owner || is_gm),
+		name: character.name,
+		slug: character.slug,
+		status: character.status,
+		died_at: character.died_at,
+	})
+}
+
+// The character's public persona and whatever else its profile lets the viewer
+// see. key is the character UUID or slug; earlier slugs redirect to the current one.
+pub async fn get_profile(
+	key: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting character profile: key = {:#?} logged_user = {:#?}", &key, &logged_user);
+
+	let key = key.into_inner();
+
+	let res = web::block(move || match characters_storage::resolve_character(key, &pool)? {
+		Resolved::Current(character) => {
+			let profile = profiles_storage::get_profile(character.id, &pool)?;
+			Ok(Resolved::Current(view(character, profile, &logged_user, &pool)?))
+		}
+		Resolved::Renamed(slug) => Ok(Resolved::Renamed(slug)),
+	})
+	.await;
+	match res {
+		Ok(Resolved::Current(profile)) => Ok(HttpResponse::Ok().json(&profile)),
+		Ok(Resolved::Renamed(slug)) => Ok(HttpResponse::MovedPermanently()
+			.header(header::LOCATION, format!("/api/characters/{}/profile", slug))
+			.finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_profile(
+	id: web::Path<String>,
+	payload: web::Json<ProfileData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating character profile: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	let mut new_profile = CharacterProfile::new(character_id, logged_user.email.clone());
+	new_profile.portrait_url = check_portrait(&payload.portrait_url)?;
+	new_profile.bio = check_text(&payload.bio, "A bio")?;
+	new_profile.affiliations = check_affiliations(payload.affiliations)?;
+	new_profile.portrait_visibility = payload.portrait_visibility.as_str().to_string();
+	new_profile.bio_visibility = payload.bio_visibility.as_str().to_string();
+	new_profile.affiliations_visibility = payload.affiliations_visibility.as_str().to_string();
+	new_profile.sheet_visibility = payload.sheet_visibility.as_str().to_string();
+
+	let res = web::block(move || {
+		let character = check_character_owner(character_id, &logged_user, &pool)?;
+		character.check_alive()?;
+		let profile = profiles_storage::save_profile(new_profile, &pool)?;
+		view(character, Some(profile), &Some(logged_user), &pool)
+	})
+	.await;
+	match res {
+		Ok(profile) => Ok(HttpResponse::Ok().json(&profile)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// GM notes are the GMs' own record and stay open on deceased characters
+pub async fn update_gm_notes(
+	id: web::Path<String>,
+	payload: web::Json<NotesData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating GM notes: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	let mut new_profile = CharacterProfile::new(character_id, logged_user.email.clone());
+	new_profile.gm_notes = check_text(&payload.gm_notes, "GM notes")?;
+	new_profile.gm_notes_visibility = payload.gm_notes_visibility.as_str().to_string();
+
+	let res = web::block(move || {
+		let character = characters_storage::get_character(character_id, &pool)?;
+		let profile = profiles_storage::save_gm_notes(new_profile, &pool)?;
+		view(character, Some(profile), &Some(logged_user), &pool)
+	})
+	.await;
+	match res {
+		Ok(profile) => Ok(HttpResponse::Ok().json(&profile)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
@@ -0,0 +1,205 @@
+use crate::errors::ServiceError;
+use crate::models::listing::ListParams;
+use crate::models::reactions::{ReactionKind, ReactionType};
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ReactionData {
+	pub reaction: ReactionType,
+	pub character_id: Option<uuid::Uuid>,
+}
+
+pub async fn get_reaction_kinds() -> Result<HttpResponse, ServiceError> {
+	let kinds: Vec<ReactionKind> = ReactionType::ALL
+		.iter()
+		.map(|reaction| ReactionKind {
+			reaction: *reaction,
+			emoji: reaction.emoji(),
+		})
+		.collect();
+
+	Ok(HttpResponse::Ok().json(&kinds))
+}
+
+pub async fn get_reactions(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting article reactions: id = {:#?}", &id);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		articles_storage::get_article(article_id, &pool)?;
+		reactions_storage::query_reactions(article_id, &pool)
+	})
+	.await;
+	match res {
+		Ok(reactions) => Ok(HttpResponse::Ok().json(&reactions)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Reacting in-world is only possible with one's own characters
+fn check_character(
+	character_id: Option<uuid::Uuid>,
+	logged_user: &LoggedUser,
+	pool: &web::Data<Pool>,
+) -> Result<(), ServiceError> {
+	if let Some(character_id) = character_id {
+		let character = characters_storage::get_character(character_id, pool)?;
+		if character.user_id != logged_user.id {
+			return Err(ServiceError::AdminRequired);
+		}
+	}
+	Ok(())
+}
+
+pub async fn add_reaction(
+	id: web::Path<String>,
+	reaction_data: web::Json<ReactionData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding a reaction: id = {:#?} reaction_data = {:#?} logged_user = {:#?}",
+		&id,
+		&reaction_data,
+		&logged_user
+	);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_character(reaction_data.character_id, &logged_user, &pool)?;
+		reactions_storage::create_reaction(
+			article_id,
+			logged_user.id,
+			reaction_data.character_id,
+			reaction_data.reaction,
+			logged_user.email,
+			&pool,
+		)
+		.map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(reaction) => Ok(HttpResponse::Ok().json(&reaction)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_reaction(
+	id: web::Path<String>,
+	reaction_data: web::Json<ReactionData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Deleting a reaction: id = {:#?} reaction_data = {:#?} logged_user = {:#?}",
+		&id,
+		&reaction_data,
+		&logged_user
+	);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		reactions_storage::delete_reaction(
+			article_id,
+			logged_user.id,
+			reaction_data.character_id,
+			reaction_data.reaction,
+			&pool,
+		)
+	})
+	.await;
+	match res {
+		Ok(()) => Ok(HttpResponse::Ok().json(())),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn add_favorite(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Adding a favorite: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res =
+		web::block(move || reactions_storage::create_favorite(article_id, logged_user.id, logged_user.email, &pool))
+			.await;
+	match res {
+		Ok(favorite) => Ok(HttpResponse::Ok().json(&favorite)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_favorite(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Deleting a favorite: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let article_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || reactions_storage::delete_favorite(article_id, logged_user.id, &pool)).await;
+	match res {
+		Ok(()) => Ok(HttpResponse::Ok().json(())),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_favorites(
+	req: HttpRequest,
+	uuid_path: web::Path<String>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting user favorites: params = {:#?} logged_user = {:#?}",
+		&params,
+		&logged_user
+	);
+
+	let user_id = uuid::Uuid::parse_str(&uuid_path.into_inner())?;
+
+	if logged_user.isadmin == false && logged_user.id != user_id {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let res = web::block(move || {
+		articles_storage::query_favorite_articles(user_id, &params, &pool)
+			.and_then(|page| reactions_storage::page_with_reactions(page, &pool))
+			.map(|page| (page, params))
+	})
+	.await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
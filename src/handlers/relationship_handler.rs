@@ -0,0 +1,329 @@
+use crate::errors::ServiceError;
+use crate::handlers::character_handler::check_character_owner;
+use crate::graphs::{Graph, GraphEdge, GraphFormat, GraphNode};
+use crate::models::characters::Character;
+use crate::models::profiles::Visibility;
+use crate::models::relationships::{Neighborhood, Relationship, RelationshipChanges, RelationshipKind, RelationshipNode};
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+const MAX_DEPTH: u32 = 3;
+
+#[derive(Deserialize, Debug)]
+pub struct RelationshipData {
+	pub to_character_id: uuid::Uuid,
+	pub kind: RelationshipKind,
+	pub directed: Option<bool>,
+	pub notes: Option<String>,
+	// Defaults to party
+	pub visibility: Option<Visibility>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RelationshipUpdateData {
+	pub kind: RelationshipKind,
+	pub directed: bool,
+	pub notes: String,
+	pub visibility: Visibility,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DepthQuery {
+	pub depth: Option<u32>,
+}
+
+fn check_notes(notes: &str) -> Result<String, ServiceError> {
+	let notes = notes.trim();
+	if notes.chars().count() > 10000 {
+		return Err(ServiceError::BadRequest("Notes are up to 10000 characters".into()));
+	}
+	Ok(notes.to_string())
+}
+
+fn parse_format(format: &str) -> Result<GraphFormat, ServiceError> {
+	GraphFormat::from_extension(format).ok_or_else(|| ServiceError::BadRequest(format!("Unknown graph format: {}", format)))
+}
+
+// A relationship is seen as its visibility allows, the owner being the owner of
+// the character it is from, who always sees what they wrote. The owner of the
+// other character is just another player.
+fn can_see(relationship: &Relationship, owners: &HashMap<uuid::Uuid, uuid::Uuid>, logged_user: &Option<LoggedUser>) -> bool {
+	match owners.get(&relationship.from_character_id) {
+		Some(owner) => {
+			matches!(logged_user, Some(user) if user.id == *owner)
+				|| Visibility::stored(&relationship.visibility).allows(*owner, logged_user)
+		}
+		None => false,
+	}
+}
+
+fn node(character: &Character) -> RelationshipNode {
+	RelationshipNode {
+		character_id: character.id,
+		name: character.name.clone(),
+		slug: character.slug.clone(),
+		status: character.status.clone(),
+	}
+}
+
+// Walks out from the character one relationship at a time. Relationships the
+// viewer may not see are not followed.
+fn neighborhood(
+	character: Character,
+	depth: u32,
+	logged_user: &Option<LoggedUser>,
+	pool: &web::Data<Pool>,
+) -> Result<Neighborhood, ServiceError> {
+	let character_id = character.id;
+	let mut node_ids = vec![character.id];
+	let mut characters: HashMap<uuid::Uuid, Character> = HashMap::new();
+	characters.insert(character.id, character);
+	let mut seen: HashSet<uuid::Uuid> = HashSet::new();
+	let mut relationships = Vec::new();
+
+	let mut frontier = vec![character_id];
+	for _ in 0..depth {
+		if frontier.is_empty() {
+			break;
+		}
+		let found = relationships_storage::query_relationships_of(&frontier, pool)?;
+		let missing: Vec<uuid::Uuid> = found
+			.iter()
+			.flat_map(|relationship| vec![relationship.from_character_id, relationship.to_character_id])
+			.filter(|id| !characters.contains_key(id))
+			.collect();
+		for found_character in characters_storage::get_characters(&missing, pool)? {
+			characters.insert(found_character.id, found_character);
+		}
+		let owners: HashMap<uuid::Uuid, uuid::Uuid> = characters.values().map(|c| (c.id, c.user_id)).collect();
+
+		let mut next = Vec::new();
+		for relationship in found {
+			if seen.contains(&relationship.id) || !can_see(&relationship, &owners, logged_user) {
+				continue;
+			}
+			for id in [relationship.from_character_id, relationship.to_character_id].iter() {
+				if !node_ids.contains(id) {
+					node_ids.push(*id);
+					next.push(*id);
+				}
+			}
+			seen.insert(relationship.id);
+			relationships.push(relationship);
+		}
+		frontier = next;
+	}
+
+	Ok(Neighborhood {
+		character_id,
+		depth,
+		nodes: node_ids.iter().filter_map(|id| characters.get(id)).map(node).collect(),
+		relationships,
+	})
+}
+
+// Every relationship the viewer may see and the characters they tie together
+fn whole_graph(logged_user: &Option<LoggedUser>, pool: &web::Data<Pool>) -> Result<Graph, ServiceError> {
+	let relationships = relationships_storage::query_all_relationships(pool)?;
+	let ids: Vec<uuid::Uuid> = relationships
+		.iter()
+		.flat_map(|relationship| vec![relationship.from_character_id, relationship.to_character_id])
+		.collect();
+	let mut characters = characters_storage::get_characters(&ids, pool)?;
+	characters.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+	let owners: HashMap<uuid::Uuid, uuid::Uuid> = characters.iter().map(|c| (c.id, c.user_id)).collect();
+
+	let edges: Vec<GraphEdge> = relationships
+		.into_iter()
+		.filter(|relationship| can_see(relationship, &owners, logged_user))
+		.map(|relationship| GraphEdge {
+			id: relationship.id,
+			source: relationship.from_character_id,
+			target: relationship.to_character_id,
+			kind: relationship.kind,
+			directed: relationship.directed,
+			notes: relationship.notes,
+		})
+		.collect();
+	let nodes = characters
+		.into_iter()
+		.filter(|character| edges.iter().any(|edge| edge.source == character.id || edge.target == character.id))
+		.map(|character| GraphNode {
+			id: character.id,
+			name: character.name,
+			status: character.status,
+		})
+		.collect();
+
+	Ok(Graph { nodes, edges })
+}
+
+// The characters within depth (1 to 3, by default 1) relationships of the character
+pub async fn get_relationships(
+	id: web::Path<String>,
+	web::Query(query): web::Query<DepthQuery>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting relationships: id = {:#?} query = {:#?} logged_user = {:#?}",
+		&id,
+		&query,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let depth = query.depth.unwrap_or(1);
+	if !(1..=MAX_DEPTH).contains(&depth) {
+		return Err(ServiceError::BadRequest(format!("Depth is from 1 to {}", MAX_DEPTH)));
+	}
+
+	let res = web::block(move || {
+		let character = characters_storage::get_character(character_id, &pool)?;
+		neighborhood(character, depth, &logged_user, &pool)
+	})
+	.await;
+	match res {
+		Ok(neighborhood) => Ok(HttpResponse::Ok().json(&neighborhood)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Ties the character to another. Deceased characters make no new ties, but the
+// living may still name the dead.
+pub async fn add_relationship(
+	id: web::Path<String>,
+	payload: web::Json<RelationshipData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding a relationship: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	if payload.to_character_id == character_id {
+		return Err(ServiceError::BadRequest("Relationships are with another character".into()));
+	}
+	let new_relationship = Relationship {
+		id: uuid::Uuid::new_v4(),
+		from_character_id: character_id,
+		to_character_id: payload.to_character_id,
+		kind: payload.kind.as_str().to_string(),
+		directed: payload.directed.unwrap_or(false),
+		notes: check_notes(&payload.notes.unwrap_or_default())?,
+		visibility: payload.visibility.unwrap_or(Visibility::Party).as_str().to_string(),
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: logged_user.email.clone(),
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		relationships_storage::create_relationship(new_relationship, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(relationship) => Ok(HttpResponse::Ok().json(&relationship)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_relationship(
+	path: web::Path<(String, String)>,
+	payload: web::Json<RelationshipUpdateData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating a relationship: path = {:#?} payload = {:#?} logged_user = {:#?}",
+		&path,
+		&payload,
+		&logged_user
+	);
+
+	let (character_id, relationship_id) = path.into_inner();
+	let character_id = uuid::Uuid::parse_str(&character_id)?;
+	let relationship_id = uuid::Uuid::parse_str(&relationship_id)?;
+	let payload = payload.into_inner();
+	let notes = check_notes(&payload.notes)?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		let changes = RelationshipChanges {
+			kind: payload.kind,
+			directed: payload.directed,
+			notes,
+			visibility: payload.visibility.as_str().to_string(),
+			updated_by: logged_user.email,
+		};
+		relationships_storage::update_relationship(character_id, relationship_id, changes, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(relationship) => Ok(HttpResponse::Ok().json(&relationship)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_relationship(
+	path: web::Path<(String, String)>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Deleting a relationship: path = {:#?} logged_user = {:#?}", &path, &logged_user);
+
+	let (character_id, relationship_id) = path.into_inner();
+	let character_id = uuid::Uuid::parse_str(&character_id)?;
+	let relationship_id = uuid::Uuid::parse_str(&relationship_id)?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		relationships_storage::delete_relationship(character_id, relationship_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(()) => Ok(HttpResponse::Ok().finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// The whole graph as the viewer may see it, in GraphML (.graphml) or DOT (.dot, .gv)
+pub async fn export_relationships(
+	format: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Exporting relationships: format = {:#?} logged_user = {:#?}", &format, &logged_user);
+
+	let format = parse_format(&format.into_inner())?;
+
+	let res = web::block(move || whole_graph(&logged_user, &pool)).await;
+	match res {
+		Ok(graph) => Ok(HttpResponse::Ok().content_type(format.content_type()).body(graph.render(format))),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
@@ -0,0 +1,331 @@
+use crate::calendar::CALENDAR;
+use crate::dice;
+use crate::errors::ServiceError;
+use crate::handlers::character_handler::check_character_owner;
+use crate::handlers::sheet_handler::reads_sheet;
+use crate::models::listing::ListParams;
+use crate::models::rolls::{CheckModifier, CheckResult, NewRoll, Roll};
+use crate::models::sheets::SheetData;
+use crate::models::users::{LoggedUser, Pool};
+use crate::rules::{CheckRule, RULESET};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct RollData {
+	pub expression: String,
+	pub character_id: uuid::Uuid,
+	// Defaults to the campaign of the calendar
+	pub campaign: Option<String>,
+	pub reason: Option<String>,
+	// Only admins may choose the seed, for repeatable test rolls
+	pub seed: Option<i64>,
+}
+
+// A check of a skill, integer attribute or derived value of the character's
+// sheet, such as hacking against difficulty 12
+#[derive(Deserialize, Debug)]
+pub struct CheckData {
+	pub check: String,
+	pub difficulty: i64,
+	// Situational modifiers, added to what the sheet gives
+	#[serde(default)]
+	pub modifiers: Vec<CheckModifier>,
+	pub campaign: Option<String>,
+	pub reason: Option<String>,
+	pub seed: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CampaignQuery {
+	pub campaign: Option<String>,
+}
+
+pub fn campaign_or_default(campaign: Option<String>) -> Result<String, ServiceError> {
+	let campaign = campaign.map(|c| c.trim().to_string()).unwrap_or_else(|| CALENDAR.name.clone());
+	if campaign.is_empty() || campaign.chars().count() > 100 {
+		return Err(ServiceError::BadRequest("A campaign is named with 1 to 100 characters".into()));
+	}
+	Ok(campaign)
+}
+
+// The ruleset's check dice with the modifiers added as constants
+fn check_expression(rule: &CheckRule, modifiers: &[CheckModifier]) -> dice::DiceExpression {
+	let mut expression = dice::parse(&rule.dice).unwrap_or(dice::DiceExpression { terms: Vec::new() });
+	for modifier in modifiers.iter().filter(|modifier| modifier.value != 0) {
+		expression.terms.push((modifier.value.signum(), dice::Term::Constant(modifier.value.abs())));
+	}
+	expression
+}
+
+// Leaves the sheet's values out of a check, summing its modifiers up as one.
+// The dice, situational modifiers and outcome stay as they were rolled.
+fn hide_sheet(roll: &mut Roll) {
+	let (rule, check) = match (RULESET.checks.as_ref(), roll.result.get("check").and_then(|check| check.as_str())) {
+		(Some(rule), Some(check)) => (rule, check.to_string()),
+		_ => return,
+	};
+	let sheet_keys: Vec<String> = RULESET
+		.check_modifiers(&check, &SheetData::default())
+		.unwrap_or_default()
+		.into_iter()
+		.map(|modifier| modifier.name)
+		.collect();
+	let modifiers: Vec<CheckModifier> = serde_json::from_value(roll.result["modifiers"].clone()).unwrap_or_default();
+	let (from_sheet, situational): (Vec<CheckModifier>, Vec<CheckModifier>) =
+		modifiers.into_iter().partition(|modifier| sheet_keys.contains(&modifier.name));
+	let mut shown = vec![CheckModifier {
+		name: String::from("sheet"),
+		value: from_sheet.iter().map(|modifier| modifier.value).sum(),
+	}];
+	shown.extend(situational);
+
+	let expression = check_expression(rule, &shown).to_string();
+	let mut terms: Vec<serde_json::Value> = roll.result["roll"]["terms"]
+		.as_array()
+		.map(|terms| terms.iter().filter(|term| term["dice"].as_array().is_some_and(|dice| !dice.is_empty())).cloned().collect())
+		.unwrap_or_default();
+	terms.extend(shown.iter().filter(|modifier| modifier.value != 0).map(|modifier| {
+		serde_json::json!({
+			"notation": modifier.value.abs().to_string(),
+			"sign": modifier.value.signum(),
+			"dice": [],
+			"value": modifier.value.abs(),
+		})
+	}));
+	roll.result["modifiers"] = serde_json::to_value(&shown).unwrap_or_default();
+	roll.result["roll"]["expression"] = serde_json::Value::from(expression.clone());
+	roll.result["roll"]["terms"] = serde_json::Value::from(terms);
+	roll.expression = expression;
+}
+
+// Checks show the sheet only to those who may read it
+fn hide_sheets(rolls: &mut [Roll], logged_user: &Option<LoggedUser>, pool: &web::Data<Pool>) -> Result<(), ServiceError> {
+	let ids: Vec<uuid::Uuid> = rolls
+		.iter()
+		.filter(|roll| roll.result.get("check").is_some())
+		.map(|roll| roll.character_id)
+		.collect();
+	let mut readable: HashMap<uuid::Uuid, bool> = HashMap::new();
+	for character in characters_storage::get_characters(&ids, pool)? {
+		readable.insert(character.id, reads_sheet(&character, logged_user, pool)?);
+	}
+	for roll in rolls.iter_mut() {
+		if readable.get(&roll.character_id).copied() != Some(true) {
+			hide_sheet(roll);
+		}
+	}
+	Ok(())
+}
+
+pub async fn get_rolls(
+	req: HttpRequest,
+	web::Query(query): web::Query<CampaignQuery>,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Getting rolls: query = {:#?} params = {:#?} logged_user = {:#?}",
+		&query,
+		&params,
+		&logged_user
+	);
+
+	let campaign = campaign_or_default(query.campaign)?;
+
+	let res = web::block(move || {
+		let mut page = rolls_storage::query_rolls(&campaign, &params, &pool)?;
+		hide_sheets(&mut page.items, &Some(logged_user), &pool)?;
+		Ok((page, params))
+	})
+	.await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_roll(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting roll: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let roll_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		let mut roll = rolls_storage::get_roll(roll_id, &pool)?;
+		hide_sheets(std::slice::from_mut(&mut roll), &Some(logged_user), &pool)?;
+		Ok(roll)
+	})
+	.await;
+	match res {
+		Ok(roll) => Ok(HttpResponse::Ok().json(&roll)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Checks that the campaign's log is whole and every roll matches its seed
+pub async fn verify_rolls(
+	web::Query(query): web::Query<CampaignQuery>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Verifying rolls: query = {:#?} logged_user = {:#?}", &query, &logged_user);
+
+	let campaign = campaign_or_default(query.campaign)?;
+
+	let res = web::block(move || rolls_storage::check_roll_log(&campaign, &pool)).await;
+	match res {
+		Ok(check) => Ok(HttpResponse::Ok().json(&check)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn add_roll(
+	payload: web::Json<RollData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Rolling: payload = {:#?} logged_user = {:#?}", &payload, &logged_user);
+
+	let payload = payload.into_inner();
+	if payload.seed.is_some() && logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let campaign = campaign_or_default(payload.campaign)?;
+	let reason = payload.reason.unwrap_or_default().trim().to_string();
+	let character_id = payload.character_id;
+	let expression = dice::parse(&payload.expression).map_err(ServiceError::BadRequest)?;
+	let seed = payload.seed.unwrap_or_else(rand::random);
+	let result = expression.roll_seeded(seed);
+	let total = result.total;
+	let result = serde_json::to_value(&result).map_err(|_| ServiceError::InternalServerError)?;
+
+	let res = web::block(move || {
+		// Players roll for their own characters, admins for anyone's. The dead roll no more.
+		let character = check_character_owner(character_id, &logged_user, &pool)?;
+		character.check_alive()?;
+		let new_roll = NewRoll {
+			campaign,
+			user_id: logged_user.id,
+			character_id: character.id,
+			character_name: character.name,
+			expression: expression.to_string(),
+			seed,
+			result,
+			total,
+			reason,
+			updated_by: logged_user.email,
+		};
+		rolls_storage::add_roll(new_roll, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(roll) => Ok(HttpResponse::Ok().json(&roll)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Rolls the ruleset's check dice with the modifiers of the sheet and the
+// situation, and logs the roll with the resolved check as its result
+pub async fn add_check(
+	id: web::Path<String>,
+	payload: web::Json<CheckData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Making a check: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	if payload.seed.is_some() && logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+	let rule = RULESET
+		.checks
+		.as_ref()
+		.ok_or_else(|| ServiceError::BadRequest("The ruleset has no checks".into()))?;
+	if payload.difficulty.abs() > 1000 || payload.modifiers.len() > 10 || payload.modifiers.iter().any(|m| m.value.abs() > 100) {
+		return Err(ServiceError::BadRequest(
+			"Difficulties are at most 1000 and checks have at most 10 modifiers of at most 100".into(),
+		));
+	}
+	let campaign = campaign_or_default(payload.campaign)?;
+	let check = payload.check.trim().to_string();
+	let difficulty = payload.difficulty;
+	let situational = payload.modifiers;
+	let reason = payload
+		.reason
+		.map(|reason| reason.trim().to_string())
+		.unwrap_or_else(|| format!("{} vs difficulty {}", check, difficulty));
+	let seed = payload.seed.unwrap_or_else(rand::random);
+
+	let res = web::block(move || {
+		let character = check_character_owner(character_id, &logged_user, &pool)?;
+		character.check_alive()?;
+		let sheet = sheets_storage::get_sheet(character_id, &pool)?;
+		let data: SheetData = serde_json::from_value(sheet.data).map_err(|_| ServiceError::InternalServerError)?;
+		let mut modifiers = RULESET
+			.check_modifiers(&check, &data)
+			.ok_or_else(|| ServiceError::BadRequest(format!("Unknown check {}", check)))?;
+		modifiers.extend(situational);
+
+		let dice_terms = check_expression(rule, &[]).terms.len();
+		let expression = check_expression(rule, &modifiers);
+		let roll = expression.roll_seeded(seed);
+		let natural: i64 = roll.terms[..dice_terms].iter().map(|term| term.sign * term.value).sum();
+		let result = CheckResult {
+			check,
+			difficulty,
+			modifiers,
+			natural,
+			total: roll.total,
+			outcome: rule.outcome(natural, roll.total, difficulty),
+			roll,
+		};
+		let new_roll = NewRoll {
+			campaign,
+			user_id: logged_user.id,
+			character_id: character.id,
+			character_name: character.name,
+			expression: expression.to_string(),
+			seed,
+			total: result.total,
+			result: serde_json::to_value(&result).map_err(|_| ServiceError::InternalServerError)?,
+			reason,
+			updated_by: logged_user.email,
+		};
+		rolls_storage::add_roll(new_roll, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(roll) => Ok(HttpResponse::Ok().json(&roll)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
@@ -0,0 +1,56 @@
+use crate::errors::ServiceError;
+use crate::models::search::SearchResults;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize, Debug)]
+pub struct SearchQuery {
+	pub q: String,
+	pub limit: Option<i64>,
+	pub offset: Option<i64>,
+}
+
+// Open to everyone, but characters only match on what the viewer may read of them
+pub async fn search(
+	web::Query(search_query): web::Query<SearchQuery>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Searching: search_query = {:#?} logged_user = {:#?}",
+		&search_query,
+		&logged_user
+	);
+
+	let query = search_query.q.trim().to_string();
+	if query.is_empty() {
+		return Err(ServiceError::BadRequest("Search query is empty".into()));
+	}
+
+	let limit = search_query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+	let offset = search_query.offset.unwrap_or(0).max(0);
+
+	let q_query = query.clone();
+	let user_id = logged_user.as_ref().map(|user| user.id);
+	let isadmin = matches!(logged_user, Some(user) if user.isadmin);
+	let res = web::block(move || search_storage::search(q_query, user_id, isadmin, limit, offset, &pool)).await;
+	match res {
+		Ok((results, total)) => Ok(HttpResponse::Ok().json(&SearchResults {
+			query,
+			total,
+			limit,
+			offset,
+			results,
+		})),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
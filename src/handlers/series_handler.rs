@@ -0,0 +1,200 @@
+use crate::errors::ServiceError;
+use crate::models::listing::ListParams;
+use crate::models::users::{LoggedUser, Pool};
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
+use log::trace;
+use serde::Deserialize;
+
+// Positions are stored as SMALLINT, so a series stays well below its range
+const MAX_ARTICLES: usize = 1000;
+
+#[derive(Deserialize, Debug)]
+pub struct SeriesData {
+	pub title: String,
+	pub description: Option<String>,
+}
+
+// Articles in reading order
+#[derive(Deserialize, Debug)]
+pub struct SeriesArticlesData {
+	pub article_ids: Vec<uuid::Uuid>,
+}
+
+fn check_title(title: &str) -> Result<String, ServiceError> {
+	let title = title.trim();
+	if title.is_empty() {
+		return Err(ServiceError::BadRequest("A series needs a title".into()));
+	}
+	Ok(title.to_string())
+}
+
+// The user who started a series and admins may change it
+fn check_owner(series_id: uuid::Uuid, logged_user: &LoggedUser, pool: &web::Data<Pool>) -> Result<(), ServiceError> {
+	let series = series_storage::get_series(series_id, pool)?;
+	if logged_user.isadmin == false && series.user_id != logged_user.id {
+		return Err(ServiceError::AdminRequired);
+	}
+	Ok(())
+}
+
+pub async fn get_series_list(
+	req: HttpRequest,
+	web::Query(params): web::Query<ListParams>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting series: params = {:#?}", &params);
+
+	let res = web::block(move || series_storage::query_series(&params, &pool).map(|page| (page, params))).await;
+	match res {
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// The series index: the series and its articles in reading order
+pub async fn get_series(id: web::Path<String>, pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting series: id = {:#?}", &id);
+
+	let series_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || series_storage::get_series_index(series_id, &pool)).await;
+	match res {
+		Ok(index) => Ok(HttpResponse::Ok().json(&index)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn add_series(
+	payload: web::Json<SeriesData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding a series: payload = {:#?} logged_user = {:#?}",
+		&payload,
+		&logged_user
+	);
+
+	let payload = payload.into_inner();
+	let title = check_title(&payload.title)?;
+
+	let res = web::block(move || {
+		series_storage::create_series(
+			logged_user.id,
+			title,
+			payload.description.unwrap_or_default(),
+			logged_user.email,
+			&pool,
+		)
+	})
+	.await;
+	match res {
+		Ok(series) => Ok(HttpResponse::Ok().json(&series)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn update_series(
+	id: web::Path<String>,
+	payload: web::Json<SeriesData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating a series: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let series_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let payload = payload.into_inner();
+	let title = check_title(&payload.title)?;
+
+	let res = web::block(move || {
+		check_owner(series_id, &logged_user, &pool)?;
+		let description = match payload.description {
+			Some(description) => description,
+			None => series_storage::get_series(series_id, &pool)?.description,
+		};
+		series_storage::update_series(series_id, title, description, logged_user.email, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(series) => Ok(HttpResponse::Ok().json(&series)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_series(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Deleting a series: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let series_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_owner(series_id, &logged_user, &pool)?;
+		series_storage::delete_series(series_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(()) => Ok(HttpResponse::Ok().finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn set_series_articles(
+	id: web::Path<String>,
+	payload: web::Json<SeriesArticlesData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Setting series articles: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let series_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let article_ids = payload.into_inner().article_ids;
+
+	if article_ids.len() > MAX_ARTICLES {
+		return Err(ServiceError::BadRequest(format!("A series can have at most {} articles", MAX_ARTICLES)));
+	}
+	if article_ids.iter().enumerate().any(|(i, article_id)| article_ids[..i].contains(article_id)) {
+		return Err(ServiceError::BadRequest("An article can be in a series only once".into()));
+	}
+
+	let res = web::block(move || {
+		check_owner(series_id, &logged_user, &pool)?;
+		series_storage::set_series_articles(series_id, article_ids, logged_user.email, &pool).map_err(ServiceError::from)
+	})
+	.await;
+	match res {
+		Ok(index) => Ok(HttpResponse::Ok().json(&index)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
@@ -0,0 +1,131 @@
+use crate::errors::ServiceError;
+use crate::handlers::character_handler::check_character_owner;
+use crate::models::characters::Character;
+use crate::models::profiles::CharacterProfile;
+use crate::models::sheets::{CharacterSheet, SheetData, SheetView};
+use crate::models::users::{LoggedUser, Pool};
+use crate::rules::RULESET;
+use crate::storage::*;
+use actix_web::{error::BlockingError, web, HttpResponse};
+use diesel::result::Error::NotFound;
+use log::trace;
+
+// Owners read their sheets, others as far as the profile lets them, by
+// default not at all
+pub fn reads_sheet(character: &Character, logged_user: &Option<LoggedUser>, pool: &web::Data<Pool>) -> Result<bool, ServiceError> {
+	if matches!(logged_user, Some(user) if user.id == character.user_id) {
+		return Ok(true);
+	}
+	let profile = profiles_storage::get_profile(character.id, pool)?
+		.unwrap_or_else(|| CharacterProfile::new(character.id, character.updated_by.clone()));
+	Ok(profile.visibility().sheet.allows(character.user_id, logged_user))
+}
+
+fn check_reader(character_id: uuid::Uuid, logged_user: &Option<LoggedUser>, pool: &web::Data<Pool>) -> Result<(), ServiceError> {
+	let character = characters_storage::get_character(character_id, pool)?;
+	if reads_sheet(&character, logged_user, pool)? {
+		return Ok(());
+	}
+	match logged_user {
+		Some(_) => Err(ServiceError::AdminRequired),
+		None => Err(ServiceError::Unauthorized),
+	}
+}
+
+pub fn view(sheet: CharacterSheet) -> Result<SheetView, ServiceError> {
+	let data: SheetData = serde_json::from_value(sheet.data).map_err(|_| ServiceError::InternalServerError)?;
+	Ok(SheetView {
+		character_id: sheet.character_id,
+		outdated: sheet.ruleset != RULESET.name || sheet.ruleset_version != RULESET.version,
+		ruleset: sheet.ruleset,
+		ruleset_version: sheet.ruleset_version,
+		derived: RULESET.derive(&data),
+		data,
+		updated_by: sheet.updated_by,
+		updated_at: sheet.updated_at,
+	})
+}
+
+// The ruleset sheets are checked against, for building sheet forms
+pub async fn get_rules() -> Result<HttpResponse, ServiceError> {
+	Ok(HttpResponse::Ok().json(&*RULESET))
+}
+
+pub async fn get_sheet(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: Option<LoggedUser>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting character sheet: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || {
+		check_reader(character_id, &logged_user, &pool)?;
+		view(sheets_storage::get_sheet(character_id, &pool)?)
+	})
+	.await;
+	match res {
+		Ok(sheet) => Ok(HttpResponse::Ok().json(&sheet)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// Saves the whole sheet under the current ruleset; left out values get their
+// defaults. Once a sheet exists players raise its values only by spending
+// experience on advancements, GMs may set anything.
+pub async fn update_sheet(
+	id: web::Path<String>,
+	payload: web::Json<SheetData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Updating character sheet: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let character_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let data = RULESET.validate(payload.into_inner())?;
+
+	let res = web::block(move || {
+		check_character_owner(character_id, &logged_user, &pool)?.check_alive()?;
+		if logged_user.isadmin == false {
+			let old: SheetData = match sheets_storage::get_sheet(character_id, &pool) {
+				Ok(sheet) => serde_json::from_value(sheet.data).map_err(|_| ServiceError::InternalServerError)?,
+				Err(NotFound) => data.clone(),
+				Err(err) => return Err(err.into()),
+			};
+			let raised = RULESET.raised(&old, &data);
+			if !raised.is_empty() {
+				return Err(ServiceError::BadRequest(format!(
+					"{} can only be raised through advancement",
+					raised.join(", ")
+				)));
+			}
+		}
+		let data = serde_json::to_value(&data).map_err(|_| ServiceError::InternalServerError)?;
+		let sheet = sheets_storage::save_sheet(
+			character_id,
+			RULESET.name.clone(),
+			RULESET.version,
+			data,
+			logged_user.email,
+			&pool,
+		)?;
+		view(sheet)
+	})
+	.await;
+	match res {
+		Ok(sheet) => Ok(HttpResponse::Ok().json(&sheet)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
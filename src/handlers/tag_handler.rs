@@ -1,31 +1,91 @@
-use crate::errors::ServiceError;
+use crate::errors::{ForbiddenReference, ForbiddenStruct, ForbiddenType, ServiceError};
+use crate::models::listing::ListParams;
+use crate::models::tags::ContentType;
 use crate::models::users::{LoggedUser, Pool};
 use crate::storage::*;
 use crate::handlers::*;
-use actix_web::{error::BlockingError, web, HttpResponse};
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
 use log::trace;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+const SUGGESTION_LIMIT: i64 = 10;
+const USAGE_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 500;
 
 #[derive(Deserialize, Debug)]
 pub struct TagData {
 	pub user_id: uuid::Uuid,
 	pub title: String,
+	pub parent_id: Option<uuid::Uuid>,
+}
+
+// An omitted parent_id keeps the current parent, a null one un-nests the tag.
+#[derive(Deserialize, Debug)]
+pub struct TagUpdateData {
+	pub title: String,
+	#[serde(default, deserialize_with = "present")]
+	pub parent_id: Option<Option<uuid::Uuid>>,
+}
+
+fn present<'de, D>(deserializer: D) -> Result<Option<Option<uuid::Uuid>>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	Option::<uuid::Uuid>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagAliasData {
+	pub alias: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagMergeData {
+	pub target_id: uuid::Uuid,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagAutocompleteQuery {
+	pub q: String,
+	pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagUsageQuery {
+	pub content_type: Option<String>,
+	pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagRelatedQuery {
+	pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagDeleteQuery {
+	pub cascade: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TagResolveQuery {
+	pub title: String,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ContentTagData {
-	pub user_id: uuid::Uuid,
 	pub tag_id: uuid::Uuid,
 }
 
 pub async fn get_tags(
+	req: HttpRequest,
+	web::Query(params): web::Query<ListParams>,
 	pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!("Getting tags");
+	trace!("Getting tags: params = {:#?}", &params);
 
-	let res = web::block(move || tags_storage::query_tags(&pool)).await;
+	let res = web::block(move || tags_storage::query_tags(&params, &pool).map(|page| (page, params))).await;
 	match res {
-		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -33,15 +93,81 @@ pub async fn get_tags(
 	}
 }
 
-pub async fn get_content_tags(
+pub async fn autocomplete(
+	web::Query(query): web::Query<TagAutocompleteQuery>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Autocompleting tags: query = {:#?}", &query);
+
+	let text = query.q.trim().to_string();
+	if text.is_empty() {
+		return Ok(HttpResponse::Ok().json(Vec::<()>::new()));
+	}
+	let limit = query.limit.unwrap_or(SUGGESTION_LIMIT).clamp(1, MAX_LIMIT);
+
+	let res = web::block(move || tags_storage::query_tag_suggestions(text, limit, &pool)).await;
+	match res {
+		Ok(suggestions) => Ok(HttpResponse::Ok().json(&suggestions)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_tag_usage(
+	web::Query(query): web::Query<TagUsageQuery>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting tag usage: query = {:#?}", &query);
+
+	let content_type = match query.content_type {
+		Some(ref content_type) => Some(content_type.parse::<ContentType>()?),
+		None => None,
+	};
+	let limit = query.limit.unwrap_or(USAGE_LIMIT).clamp(1, MAX_LIMIT);
+
+	let res = web::block(move || tags_storage::query_tag_usage(content_type, limit, &pool)).await;
+	match res {
+		Ok(usage) => Ok(HttpResponse::Ok().json(&usage)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_related_tags(
 	id: web::Path<String>,
+	web::Query(query): web::Query<TagRelatedQuery>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting related tags: id = {:#?} query = {:#?}", &id, &query);
+
+	let tag_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let limit = query.limit.unwrap_or(SUGGESTION_LIMIT).clamp(1, MAX_LIMIT);
+
+	let res = web::block(move || tags_storage::query_related_tags(tag_id, limit, &pool)).await;
+	match res {
+		Ok(related) => Ok(HttpResponse::Ok().json(&related)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_content_tags(
+	path: web::Path<(String, String)>,
 	pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!("Getting content tags");
+	trace!("Getting content tags: path = {:#?}", &path);
 
-	let content_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let (content_type, content_id) = path.into_inner();
+	let content_type = content_type.parse::<ContentType>()?;
+	let content_id = uuid::Uuid::parse_str(&content_id)?;
 
-	let res = web::block(move || tags_storage::query_content_tags(content_id, &pool)).await;
+	let res = web::block(move || tags_storage::query_content_tags(content_type, content_id, &pool)).await;
 	match res {
 		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
 		Err(err) => match err {
@@ -69,6 +195,7 @@ pub async fn add_tag(
 	let res = web::block(move || {
 		tags_storage::create_tag(
 			tag_data.title.clone(),
+			tag_data.parent_id,
 			logged_user.email,
 			&pool,
 		)
@@ -84,78 +211,89 @@ pub async fn add_tag(
 }
 
 pub async fn add_content_tag(
-	id: web::Path<String>,
+	path: web::Path<(String, String)>,
 	tag_data: web::Json<ContentTagData>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
 	trace!(
-		"Adding a content tag to article: content id = {:#?} tag_data = {:#?} logged_user = {:#?}",
-		&id,
+		"Adding a content tag: path = {:#?} tag_data = {:#?} logged_user = {:#?}",
+		&path,
 		&tag_data,
 		&logged_user
 	);
 
-	if logged_user.isadmin == false && logged_user.id != tag_data.user_id {
-		return Err(ServiceError::AdminRequired);
-	};
-
-	let content_id = uuid::Uuid::parse_str(&id.into_inner())?;
+	let (content_type, content_id) = path.into_inner();
+	let content_type = content_type.parse::<ContentType>()?;
+	let content_id = uuid::Uuid::parse_str(&content_id)?;
 
 	let res = web::block(move || {
-		tags_storage::create_content_tag(
-			tag_data.tag_id,
-			content_id,
-			logged_user.email,
-			&pool,
-		)
+		check_content_owner(content_type, content_id, &logged_user, &pool)?;
+		tags_storage::create_content_tag(tag_data.tag_id, content_type, content_id, logged_user.email, &pool)
+			.map_err(ServiceError::from)
 	})
 	.await;
 	match res {
 		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
 		Err(err) => match err {
-			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Error(service_error) => Err(service_error),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
 		},
 	}
 }
 
-/*
+// Only the owner of the content or an admin may change its tags
+fn check_content_owner(
+	content_type: ContentType,
+	content_id: uuid::Uuid,
+	logged_user: &LoggedUser,
+	pool: &web::Data<Pool>,
+) -> Result<(), ServiceError> {
+	if logged_user.isadmin {
+		return Ok(());
+	}
+
+	let owner_id = tags_storage::query_content_owner(content_type, content_id, pool)?;
+	if owner_id != logged_user.id {
+		return Err(ServiceError::AdminRequired);
+	}
+	Ok(())
+}
+
 pub async fn get_articles_by_tag(
-	tag_data: web::Path<String>,
+	req: HttpRequest,
+	id: web::Path<String>,
+	web::Query(mut params): web::Query<ListParams>,
 	pool: web::Data<Pool>,
-	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!(
-		"Adding a favorite project: tag_data = {:#?} logged_user = {:#?}",
-		&tag_data,
-		&logged_user
-	);
-
-	let user_id = logged_user.id;
+	trace!("Getting articles by tag: id = {:#?} params = {:#?}", &id, &params);
 
-	if logged_user.isadmin == false && logged_user.id != user_id {
-		return Err(ServiceError::AdminRequired);
-	}
+	let tag_id = uuid::Uuid::parse_str(&id.into_inner())?;
 
-	let res = web::block(move || articles_storage::query_articles_by_tag_uuid(user_id, &pool)).await;
+	let res = web::block(move || {
+		articles_storage::query_articles_by_tag_uuid(tag_id, &mut params, &pool)
+			.and_then(|page| reactions_storage::page_with_reactions(page, &pool))
+			.map(|page| (page, params))
+	})
+	.await;
 	match res {
-		Ok(project) => Ok(HttpResponse::Ok().json(&project)),
+		Ok((page, params)) => Ok(page.into_response(&req, &params)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
 		},
 	}
 }
-*/
 
 pub async fn delete_tag(
 	id: web::Path<String>,
+	web::Query(query): web::Query<TagDeleteQuery>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
 	trace!(
-		"Delete a tag: logged_user = {:#?}",
+		"Delete a tag: query = {:#?} logged_user = {:#?}",
+		&query,
 		&logged_user
 	);
 
@@ -164,9 +302,26 @@ pub async fn delete_tag(
 		return Err(ServiceError::AdminRequired);
 	}
 
-	let res = web::block(move || tags_storage::delete_tag(tag_id, &pool)).await;
+	let cascade = query.cascade.unwrap_or(false);
+
+	let res = web::block(move || tags_storage::delete_tag(tag_id, cascade, &pool)).await;
 	match res {
-		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
+		Ok(deletion) => {
+			if deletion.deleted {
+				return Ok(HttpResponse::Ok().json(&deletion));
+			}
+			Err(ServiceError::Forbidden(ForbiddenStruct {
+				error_type: ForbiddenType::InUse,
+				description: Some(format!(
+					"Tag is linked to {} content items, delete with cascade=true to remove the links too",
+					deletion.link_count
+				)),
+				details: Some(ForbiddenReference {
+					table_name: String::from("contenttags"),
+					field_name: Some(String::from("tag_id")),
+				}),
+			}))
+		}
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
@@ -175,25 +330,30 @@ pub async fn delete_tag(
 }
 
 pub async fn delete_content_tag(
-	id: web::Path<String>,
+	path: web::Path<(String, String, String)>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
 	trace!(
-		"Delete a tag: logged_user = {:#?}",
+		"Delete a content tag: path = {:#?} logged_user = {:#?}",
+		&path,
 		&logged_user
 	);
 
-	let tag_id = uuid::Uuid::parse_str(&id.into_inner())?;
-	if logged_user.isadmin == false {
-		return Err(ServiceError::AdminRequired);
-	}
+	let (content_type, content_id, tag_id) = path.into_inner();
+	let content_type = content_type.parse::<ContentType>()?;
+	let content_id = uuid::Uuid::parse_str(&content_id)?;
+	let tag_id = uuid::Uuid::parse_str(&tag_id)?;
 
-	let res = web::block(move || tags_storage::delete_tag(tag_id, &pool)).await;
+	let res = web::block(move || {
+		check_content_owner(content_type, content_id, &logged_user, &pool)?;
+		tags_storage::delete_content_tag(tag_id, content_type, content_id, &pool).map_err(ServiceError::from)
+	})
+	.await;
 	match res {
 		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
 		Err(err) => match err {
-			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Error(service_error) => Err(service_error),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
 		},
 	}
@@ -201,7 +361,7 @@ pub async fn delete_content_tag(
 
 pub async fn update_tag(
 	id: web::Path<String>,
-	payload: web::Json<TagData>,
+	payload: web::Json<TagUpdateData>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
@@ -219,16 +379,173 @@ pub async fn update_tag(
 	}
 
 	let res = web::block(move || {
+		if let Some(Some(parent_id)) = payload.parent_id {
+			check_parent(tag_id, parent_id, &pool)?;
+		}
 		tags_storage::update_tag(
 			tag_id,
 			payload.title.clone(),
+			payload.parent_id,
 			logged_user.email,
 			&pool,
 		)
+		.map_err(ServiceError::from)
 	})
 	.await;
 	match res {
 		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+// A tag can not be nested under itself or under any of its descendants
+fn check_parent(tag_id: uuid::Uuid, parent_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), ServiceError> {
+	if tag_id == parent_id || tags_storage::query_tag_ancestor_ids(parent_id, pool)?.contains(&tag_id) {
+		return Err(ServiceError::BadRequest("Tag can not be nested under itself or its descendants".into()));
+	}
+	Ok(())
+}
+
+pub async fn get_tag_children(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting tag children: id = {:#?}", &id);
+
+	let tag_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || tags_storage::query_tag_children(tag_id, &pool)).await;
+	match res {
+		Ok(tags) => Ok(HttpResponse::Ok().json(&tags)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn resolve_tag(
+	web::Query(query): web::Query<TagResolveQuery>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Resolving tag: query = {:#?}", &query);
+
+	let title = query.title.trim().to_string();
+
+	let res = web::block(move || tags_storage::resolve_tag(title, &pool)).await;
+	match res {
+		Ok(tag) => Ok(HttpResponse::Ok().json(&tag)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn get_tag_aliases(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Getting tag aliases: id = {:#?}", &id);
+
+	let tag_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	let res = web::block(move || tags_storage::query_tag_aliases(tag_id, &pool)).await;
+	match res {
+		Ok(aliases) => Ok(HttpResponse::Ok().json(&aliases)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn add_tag_alias(
+	id: web::Path<String>,
+	payload: web::Json<TagAliasData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Adding a tag alias: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let tag_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let alias = payload.alias.trim().to_string();
+	if alias.is_empty() {
+		return Err(ServiceError::BadRequest("Alias is empty".into()));
+	}
+
+	let res = web::block(move || tags_storage::create_tag_alias(tag_id, alias, logged_user.email, &pool)).await;
+	match res {
+		Ok(alias) => Ok(HttpResponse::Ok().json(&alias)),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn delete_tag_alias(
+	id: web::Path<String>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!("Delete a tag alias: id = {:#?} logged_user = {:#?}", &id, &logged_user);
+
+	let alias_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	let res = web::block(move || tags_storage::delete_tag_alias(alias_id, &pool)).await;
+	match res {
+		Ok(_) => Ok(HttpResponse::Ok().finish()),
+		Err(err) => match err {
+			BlockingError::Error(service_error) => Err(service_error.into()),
+			BlockingError::Canceled => Err(ServiceError::InternalServerError),
+		},
+	}
+}
+
+pub async fn merge_tag(
+	id: web::Path<String>,
+	payload: web::Json<TagMergeData>,
+	pool: web::Data<Pool>,
+	logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+	trace!(
+		"Merging tags: id = {:#?} payload = {:#?} logged_user = {:#?}",
+		&id,
+		&payload,
+		&logged_user
+	);
+
+	let source_id = uuid::Uuid::parse_str(&id.into_inner())?;
+
+	if logged_user.isadmin == false {
+		return Err(ServiceError::AdminRequired);
+	}
+
+	if source_id == payload.target_id {
+		return Err(ServiceError::BadRequest("Can not merge a tag into itself".into()));
+	}
+
+	let res = web::block(move || tags_storage::merge_tags(source_id, payload.target_id, logged_user.email, &pool)).await;
+	match res {
+		Ok(merge) => Ok(HttpResponse::Ok().json(&merge)),
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
 			BlockingError::Canceled => Err(ServiceError::InternalServerError),
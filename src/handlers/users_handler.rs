@@ -1,7 +1,8 @@
 use crate::errors::ServiceError;
+use crate::models::listing::{ListParams, Page};
 use crate::models::users::{User, LoggedUser, Pool};
 use crate::storage::*;
-use actix_web::{error::BlockingError, web, HttpResponse};
+use actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
 use log::trace;
 use serde::{Deserialize, Serialize};
 
@@ -20,6 +21,17 @@ pub struct UserDTO {
 	pub email: String,
 }
 
+impl From<User> for UserDTO {
+	fn from(user: User) -> Self {
+		UserDTO {
+			id: user.id,
+			username: user.username,
+			isadmin: user.isadmin,
+			email: user.email,
+		}
+	}
+}
+
 #[derive(Deserialize, Debug)]
 pub struct QueryData {
 	pub id: String,
@@ -36,24 +48,26 @@ pub struct ForgotPasswordData {
 }
 
 pub async fn get_all(
-	web::Query(q_query_data): web::Query<QueryData>,
+	req: HttpRequest,
+	web::Query(params): web::Query<ListParams>,
 	pool: web::Data<Pool>,
 	logged_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
-	trace!("Getting all users: logged_user = {:#?}", &logged_user);
+	trace!("Getting all users: params = {:#?} logged_user = {:#?}", &params, &logged_user);
 
 	if logged_user.isadmin == false {
 		return Err(ServiceError::AdminRequired);
 	}
 
-	let res = web::block(move || users_storage::query_all(&pool)).await;
+	let res = web::block(move || users_storage::query_all(&params, &pool).map(|page| (page, params))).await;
 
 	match res {
-		Ok(users) => {
-			if users.is_empty() == false {
-				return Ok(HttpResponse::Ok().json(&users));
-			}
-			Err(ServiceError::Empty)
+		Ok((page, params)) => {
+			let page = Page {
+				items: page.items.into_iter().map(UserDTO::from).collect(),
+				total: page.total,
+			};
+			Ok(page.into_response(&req, &params))
 		}
 		Err(err) => match err {
 			BlockingError::Error(service_error) => Err(service_error.into()),
@@ -12,12 +12,19 @@ use diesel::r2d2::{self, ConnectionManager};
 use log::{error, info, trace};
 //use diesel::r2d2::{self, ConnectionManager};
 
+mod calendar;
+mod dice;
 mod errors;
+mod feeds;
+mod graphs;
 mod handlers;
+mod mentions;
 mod models;
+mod rules;
 mod schema;
 mod storage;
 mod utils;
+mod wikilinks;
 mod email_service;
 
 #[get("/")]
@@ -74,6 +81,8 @@ async fn main() -> std::io::Result<()> {
 	let rust_log = std::env::var("RUST_LOG").unwrap_or("info, simple-auth-server=debug".to_string());
 	std::env::set_var("RUST_LOG", rust_log);
 	env_logger::init();
+	lazy_static::initialize(&calendar::CALENDAR);
+	lazy_static::initialize(&rules::RULESET);
 	let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 	initialize_db(&database_url);
 	let manager = ConnectionManager::<PgConnection>::new(database_url);
@@ -116,6 +125,10 @@ async fn main() -> std::io::Result<()> {
 					.service(
 						web::resource("/updatepassword").route(web::put().to(handlers::users_handler::update_password)),
 					)
+					.service(
+						web::resource("/users")
+							.route(web::get().to(handlers::users_handler::get_all)),
+					)
 					.service(
 						web::resource("/users/{user_id}")
 							.route(web::get().to(handlers::users_handler::get_by_uuid))
@@ -139,6 +152,19 @@ async fn main() -> std::io::Result<()> {
 						web::resource("/characters/{character_id}")
 							.route(web::get().to(handlers::character_handler::get_by_character_uuid))
 					)
+					.service(
+						web::resource("/characters/{character_id}/status")
+							.route(web::put().to(handlers::character_handler::update_status))
+					)
+					.service(
+						web::resource("/characters/{character_id}/profile")
+							.route(web::get().to(handlers::profile_handler::get_profile))
+							.route(web::put().to(handlers::profile_handler::update_profile))
+					)
+					.service(
+						web::resource("/characters/{character_id}/notes")
+							.route(web::put().to(handlers::profile_handler::update_gm_notes))
+					)
 
 					// Articles
 
@@ -160,6 +186,237 @@ async fn main() -> std::io::Result<()> {
 						web::resource("/articles")
 							.route(web::get().to(handlers::article_handler::get_articles)),
 					)
+					.service(
+						web::resource("/articles/{article_id}/authors")
+							.route(web::get().to(handlers::article_handler::get_authors))
+							.route(web::put().to(handlers::article_handler::set_authors)),
+					)
+					.service(
+						web::resource("/calendar")
+							.route(web::get().to(handlers::article_handler::get_calendar)),
+					)
+
+					// Reactions and favorites
+
+					.service(
+						web::resource("/reactions")
+							.route(web::get().to(handlers::reaction_handler::get_reaction_kinds)),
+					)
+					.service(
+						web::resource("/articles/{article_id}/reactions")
+							.route(web::get().to(handlers::reaction_handler::get_reactions))
+							.route(web::post().to(handlers::reaction_handler::add_reaction))
+							.route(web::delete().to(handlers::reaction_handler::delete_reaction)),
+					)
+					.service(
+						web::resource("/articles/{article_id}/favorite")
+							.route(web::put().to(handlers::reaction_handler::add_favorite))
+							.route(web::delete().to(handlers::reaction_handler::delete_favorite)),
+					)
+					.service(
+						web::resource("/users/{user_id}/favorites")
+							.route(web::get().to(handlers::reaction_handler::get_favorites)),
+					)
+
+					// Comments
+
+					.service(
+						web::resource("/articles/{article_id}/comments")
+							.route(web::get().to(handlers::comment_handler::get_comments))
+							.route(web::post().to(handlers::comment_handler::add_comment)),
+					)
+					.service(
+						web::resource("/articles/{article_id}/comments/lock")
+							.route(web::put().to(handlers::comment_handler::lock_comments)),
+					)
+					.service(
+						web::resource("/comments/{comment_id}")
+							.route(web::put().to(handlers::comment_handler::update_comment))
+							.route(web::delete().to(handlers::comment_handler::delete_comment)),
+					)
+					.service(
+						web::resource("/comments/{comment_id}/history")
+							.route(web::get().to(handlers::comment_handler::get_comment_history)),
+					)
+					.service(
+						web::resource("/comments/{comment_id}/moderation")
+							.route(web::put().to(handlers::comment_handler::moderate_comment)),
+					)
+
+					// Links
+
+					.service(
+						web::resource("/articles/{article_id}/links")
+							.route(web::get().to(handlers::link_handler::get_links)),
+					)
+					.service(
+						web::resource("/articles/{article_id}/backlinks")
+							.route(web::get().to(handlers::link_handler::get_article_backlinks)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/backlinks")
+							.route(web::get().to(handlers::link_handler::get_character_backlinks)),
+					)
+					.service(
+						web::resource("/users/{user_id}/broken-links")
+							.route(web::get().to(handlers::link_handler::get_broken_links)),
+					)
+
+					// Mentions and notifications
+
+					.service(
+						web::resource("/characters/{character_id}/mentions")
+							.route(web::get().to(handlers::notification_handler::get_character_mentions)),
+					)
+					.service(
+						web::resource("/users/{user_id}/notifications")
+							.route(web::get().to(handlers::notification_handler::get_notifications)),
+					)
+					.service(
+						web::resource("/users/{user_id}/notifications/read")
+							.route(web::put().to(handlers::notification_handler::read_all_notifications)),
+					)
+					.service(
+						web::resource("/users/{user_id}/notification-settings")
+							.route(web::get().to(handlers::notification_handler::get_settings))
+							.route(web::put().to(handlers::notification_handler::update_setting)),
+					)
+					.service(
+						web::resource("/notifications/{notification_id}/read")
+							.route(web::put().to(handlers::notification_handler::read_notification)),
+					)
+
+					// Character sheets
+
+					.service(
+						web::resource("/rules")
+							.route(web::get().to(handlers::sheet_handler::get_rules)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/sheet")
+							.route(web::get().to(handlers::sheet_handler::get_sheet))
+							.route(web::put().to(handlers::sheet_handler::update_sheet)),
+					)
+
+					// Experience
+
+					.service(
+						web::resource("/characters/{character_id}/experience")
+							.route(web::get().to(handlers::experience_handler::get_experience))
+							.route(web::post().to(handlers::experience_handler::add_experience)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/history")
+							.route(web::get().to(handlers::experience_handler::get_history)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/advancements")
+							.route(web::post().to(handlers::experience_handler::add_advancement)),
+					)
+
+					// Items and inventories
+
+					.service(
+						web::resource("/items")
+							.route(web::get().to(handlers::item_handler::get_items))
+							.route(web::post().to(handlers::item_handler::add_item)),
+					)
+					.service(
+						web::resource("/items/{item_id}")
+							.route(web::get().to(handlers::item_handler::get_item))
+							.route(web::put().to(handlers::item_handler::update_item))
+							.route(web::delete().to(handlers::item_handler::delete_item)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/inventory")
+							.route(web::get().to(handlers::character_handler::get_inventory))
+							.route(web::post().to(handlers::character_handler::add_inventory_item)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/inventory/{inventory_item_id}")
+							.route(web::put().to(handlers::character_handler::update_inventory_item))
+							.route(web::delete().to(handlers::character_handler::delete_inventory_item)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/transfers")
+							.route(web::get().to(handlers::character_handler::get_transfers))
+							.route(web::post().to(handlers::character_handler::transfer_items)),
+					)
+
+					// Currency
+
+					.service(
+						web::resource("/characters/{character_id}/account")
+							.route(web::get().to(handlers::currency_handler::get_account))
+							.route(web::put().to(handlers::currency_handler::update_account)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/transactions")
+							.route(web::get().to(handlers::currency_handler::get_transactions)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/payments")
+							.route(web::post().to(handlers::currency_handler::add_payment)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/adjustments")
+							.route(web::post().to(handlers::currency_handler::add_adjustment)),
+					)
+
+					// Relationships
+
+					.service(
+						web::resource("/characters/{character_id}/relationships")
+							.route(web::get().to(handlers::relationship_handler::get_relationships))
+							.route(web::post().to(handlers::relationship_handler::add_relationship)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/relationships/{relationship_id}")
+							.route(web::put().to(handlers::relationship_handler::update_relationship))
+							.route(web::delete().to(handlers::relationship_handler::delete_relationship)),
+					)
+					.service(
+						web::resource("/relationships.{format}")
+							.route(web::get().to(handlers::relationship_handler::export_relationships)),
+					)
+
+					// Rolls
+
+					.service(
+						web::resource("/rolls")
+							.route(web::get().to(handlers::roll_handler::get_rolls))
+							.route(web::post().to(handlers::roll_handler::add_roll)),
+					)
+					.service(
+						web::resource("/rolls/verify")
+							.route(web::get().to(handlers::roll_handler::verify_rolls)),
+					)
+					.service(
+						web::resource("/rolls/{roll_id}")
+							.route(web::get().to(handlers::roll_handler::get_roll)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/checks")
+							.route(web::post().to(handlers::roll_handler::add_check)),
+					)
+
+					// Series
+
+					.service(
+						web::resource("/series")
+							.route(web::get().to(handlers::series_handler::get_series_list))
+							.route(web::post().to(handlers::series_handler::add_series)),
+					)
+					.service(
+						web::resource("/series/{series_id}")
+							.route(web::get().to(handlers::series_handler::get_series))
+							.route(web::put().to(handlers::series_handler::update_series))
+							.route(web::delete().to(handlers::series_handler::delete_series)),
+					)
+					.service(
+						web::resource("/series/{series_id}/articles")
+							.route(web::put().to(handlers::series_handler::set_series_articles)),
+					)
 
 					// Tags
 
@@ -168,25 +425,65 @@ async fn main() -> std::io::Result<()> {
 							.route(web::get().to(handlers::tag_handler::get_tags))
 							.route(web::post().to(handlers::tag_handler::add_tag))
 					)
+					.service(
+						web::resource("/tags/autocomplete")
+							.route(web::get().to(handlers::tag_handler::autocomplete)),
+					)
+					.service(
+						web::resource("/tags/usage")
+							.route(web::get().to(handlers::tag_handler::get_tag_usage)),
+					)
+					.service(
+						web::resource("/tags/resolve")
+							.route(web::get().to(handlers::tag_handler::resolve_tag)),
+					)
+					.service(
+						web::resource("/tags/aliases/{alias_id}")
+							.route(web::delete().to(handlers::tag_handler::delete_tag_alias)),
+					)
+					.service(
+						web::resource("/tags/{tag_id}/children")
+							.route(web::get().to(handlers::tag_handler::get_tag_children)),
+					)
+					.service(
+						web::resource("/tags/{tag_id}/aliases")
+							.route(web::get().to(handlers::tag_handler::get_tag_aliases))
+							.route(web::post().to(handlers::tag_handler::add_tag_alias)),
+					)
+					.service(
+						web::resource("/tags/{tag_id}/related")
+							.route(web::get().to(handlers::tag_handler::get_related_tags)),
+					)
+					.service(
+						web::resource("/tags/{tag_id}/merge")
+							.route(web::post().to(handlers::tag_handler::merge_tag)),
+					)
 					.service(
 						web::resource("/tags/{tag_id}")
-							//.route(web::get().to(handlers::tag_handler::get_articles_by_tag))
+							.route(web::get().to(handlers::tag_handler::get_articles_by_tag))
 							.route(web::put().to(handlers::tag_handler::update_tag))
 							.route(web::delete().to(handlers::tag_handler::delete_tag)),
 					)
 
-					// Article specific tags
+					// Content specific tags, content_type is one of article, character or user
 
 					.service(
-						web::resource("/content-tags/{content_id}")
+						web::resource("/content-tags/{content_type}/{content_id}")
 							.route(web::get().to(handlers::tag_handler::get_content_tags))
 							.route(web::post().to(handlers::tag_handler::add_content_tag))
 					)
 					.service(
-						web::resource("/content-tags/{tag_id}")
+						web::resource("/content-tags/{content_type}/{content_id}/{tag_id}")
 							.route(web::delete().to(handlers::tag_handler::delete_content_tag)),
 					)
 
+					// Search
+
+					.service(
+						web::resource("/search")
+							.route(web::get().to(handlers::search_handler::search)),
+					)
+
 					// Auth
 
 					.service(
@@ -196,6 +493,25 @@ async fn main() -> std::io::Result<()> {
 							.route(web::get().to(handlers::auth_handler::get_me)),
 					),
 			)
+			.service(
+				web::scope("/feeds")
+					.service(
+						web::resource("/articles.{format}")
+							.route(web::get().to(handlers::feed_handler::get_articles_feed)),
+					)
+					.service(
+						web::resource("/characters/{character_id}/articles.{format}")
+							.route(web::get().to(handlers::feed_handler::get_character_feed)),
+					)
+					.service(
+						web::resource("/tags/{tag_id}/articles.{format}")
+							.route(web::get().to(handlers::feed_handler::get_tag_feed)),
+					)
+					.service(
+						web::resource("/users/{user_id}/articles.{format}")
+							.route(web::get().to(handlers::feed_handler::get_user_feed)),
+					),
+			)
 			.service(fs::Files::new("/public", "public").show_files_listing())
 			.service(home)
 			.service(allviews)
@@ -0,0 +1,52 @@
+// @mentions of characters in article bodies. The word after @ is compared with
+// character slugs, so @kalle-karkkainen and @Kalle-Kärkkäinen mention the same
+// character. An @ right after a letter or digit belongs to something else, such
+// as an email address.
+use crate::utils::slugify;
+
+// Slugs of the mentioned characters in the order they first appear
+pub fn parse(body: &str) -> Vec<String> {
+	let mut slugs: Vec<String> = Vec::new();
+	let mut previous: Option<char> = None;
+	let mut chars = body.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		if c == '@' && !previous.is_some_and(char::is_alphanumeric) {
+			let mut word = String::new();
+			while let Some(&next) = chars.peek() {
+				if !next.is_alphanumeric() && next != '-' {
+					break;
+				}
+				word.push(next);
+				chars.next();
+			}
+			let slug = slugify(&word);
+			if !slug.is_empty() && !slugs.contains(&slug) {
+				slugs.push(slug);
+			}
+			previous = word.chars().last().or(Some(c));
+			continue;
+		}
+		previous = Some(c);
+	}
+
+	slugs
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_finds_mentions_in_order() {
+		assert_eq!(
+			parse("@Kalle-Kärkkäinen ja @maija: tavataan (@kalle-karkkainen)."),
+			vec!["kalle-karkkainen", "maija"]
+		);
+	}
+
+	#[test]
+	fn parse_skips_email_addresses_and_lone_signs() {
+		assert_eq!(parse("kalle@hki2050.com @ @- @@maija"), vec!["maija"]);
+	}
+}
@@ -2,4 +2,19 @@ pub mod users;
 pub mod invitations;
 pub mod characters;
 pub mod tags;
-pub mod articles;
\ No newline at end of file
+pub mod articles;
+pub mod search;
+pub mod listing;
+pub mod comments;
+pub mod reactions;
+pub mod slugs;
+pub mod series;
+pub mod links;
+pub mod notifications;
+pub mod sheets;
+pub mod rolls;
+pub mod experience;
+pub mod items;
+pub mod currency;
+pub mod profiles;
+pub mod relationships;
\ No newline at end of file
@@ -12,4 +12,47 @@ pub struct Article {
   pub body: String,
   pub created_at: chrono::NaiveDateTime,
   pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+  pub comments_locked: bool,
+  pub slug: String,
+  pub in_world_at: Option<chrono::NaiveDateTime>,
+}
+
+// What the authors write of an article. The user and slug follow from the lead
+// character and the title. in_world_at is kept on updates when None.
+#[derive(Debug)]
+pub struct ArticleChanges {
+  pub character_id: uuid::Uuid,
+  pub title: String,
+  pub ingress: String,
+  pub body: String,
+  pub in_world_at: Option<chrono::NaiveDateTime>,
+  pub updated_by: String,
+}
+
+// One byline of an article. position 0 is the lead author, the same character
+// as Article::character_id.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "article_authors"]
+pub struct ArticleAuthor {
+  pub id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub position: i16,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// A byline with the character's name for showing attribution. Deceased
+// characters keep their bylines, the status tells readers they are gone.
+#[derive(Debug, Serialize, Queryable)]
+pub struct Byline {
+  pub article_id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub character_name: String,
+  pub character_slug: String,
+  pub character_status: String,
+  pub user_id: uuid::Uuid,
+  pub position: i16,
 }
\ No newline at end of file
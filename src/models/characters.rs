@@ -1,6 +1,29 @@
 use super::super::schema::*;
+use crate::errors::{ForbiddenStruct, ForbiddenType, ServiceError};
 use serde::{Deserialize, Serialize};
 
+// Stored in characters.status, which has a CHECK constraint listing the same values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterStatus {
+  Active,
+  Retired,
+  Deceased,
+  // Played and owned by a GM
+  Npc,
+}
+
+impl CharacterStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      CharacterStatus::Active => "active",
+      CharacterStatus::Retired => "retired",
+      CharacterStatus::Deceased => "deceased",
+      CharacterStatus::Npc => "npc",
+    }
+  }
+}
+
 #[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
 #[table_name = "characters"]
 pub struct Character {
@@ -10,4 +33,23 @@ pub struct Character {
   pub description: String,
   pub created_at: chrono::NaiveDateTime,
   pub updated_by: String,
-}
\ No newline at end of file
+  pub updated_at: chrono::NaiveDateTime,
+  pub slug: String,
+  pub status: String,
+  // In-world date of death, set only for deceased characters
+  pub died_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Character {
+  // Deceased characters stay on the record but no longer change
+  pub fn check_alive(&self) -> Result<(), ServiceError> {
+    if self.status == CharacterStatus::Deceased.as_str() {
+      return Err(ServiceError::Forbidden(ForbiddenStruct {
+        error_type: ForbiddenType::Locked,
+        description: Some(format!("{} is deceased and read-only", self.name)),
+        details: None,
+      }));
+    }
+    Ok(())
+  }
+}
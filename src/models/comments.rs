@@ -0,0 +1,49 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// A comment on an article. Replies point to a top level comment through
+// parent_id; character_id is set when the comment is posted as a character.
+// Deleted comments are kept so that their replies stay in place.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "comments"]
+pub struct Comment {
+  pub id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub parent_id: Option<uuid::Uuid>,
+  pub user_id: uuid::Uuid,
+  pub character_id: Option<uuid::Uuid>,
+  pub body: String,
+  pub hidden: bool,
+  pub locked: bool,
+  pub deleted_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl Comment {
+  // Deleted and hidden comments are listed without their text
+  pub fn redacted(mut self) -> Self {
+    self.body = String::new();
+    self
+  }
+}
+
+// Earlier text of an edited comment
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "comment_revisions"]
+pub struct CommentRevision {
+  pub id: uuid::Uuid,
+  pub comment_id: uuid::Uuid,
+  pub body: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// A top level comment with its replies
+#[derive(Debug, Serialize)]
+pub struct CommentThread {
+  #[serde(flatten)]
+  pub comment: Comment,
+  pub replies: Vec<Comment>,
+}
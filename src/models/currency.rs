@@ -0,0 +1,125 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// Stored in currency_accounts.kind
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountKind {
+  Character,
+  // Where GM adjustments come from and go to
+  Treasury,
+}
+
+impl AccountKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AccountKind::Character => "character",
+      AccountKind::Treasury => "treasury",
+    }
+  }
+}
+
+// Stored in currency_transactions.kind, which has a CHECK constraint listing
+// the same values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+  // Between characters
+  Payment,
+  // Made by a GM between a character and the treasury
+  Adjustment,
+}
+
+impl TransactionKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      TransactionKind::Payment => "payment",
+      TransactionKind::Adjustment => "adjustment",
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "currency_accounts"]
+pub struct CurrencyAccount {
+  pub id: uuid::Uuid,
+  pub kind: String,
+  pub character_id: Option<uuid::Uuid>,
+  pub name: String,
+  pub credit_limit: i64,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "currency_transactions"]
+pub struct CurrencyTransaction {
+  pub id: uuid::Uuid,
+  pub kind: String,
+  pub payer_account_id: uuid::Uuid,
+  pub payee_account_id: uuid::Uuid,
+  pub amount: i64,
+  pub memo: String,
+  pub in_world_at: Option<chrono::NaiveDateTime>,
+  pub user_id: uuid::Uuid,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// A payment from one character to another, booked between their accounts
+#[derive(Debug)]
+pub struct NewPayment {
+  pub payer_character_id: uuid::Uuid,
+  pub payee_character_id: uuid::Uuid,
+  pub amount: i64,
+  pub memo: String,
+  pub in_world_at: Option<chrono::NaiveDateTime>,
+  pub user_id: uuid::Uuid,
+  pub updated_by: String,
+}
+
+// A GM adjustment of a character's balance against the treasury. amount is
+// added when positive and taken away when negative.
+#[derive(Debug)]
+pub struct NewAdjustment {
+  pub character_id: uuid::Uuid,
+  pub amount: i64,
+  pub memo: String,
+  pub in_world_at: Option<chrono::NaiveDateTime>,
+  pub user_id: uuid::Uuid,
+  pub updated_by: String,
+}
+
+// One side of a transaction: negative for the payer, positive for the payee
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "currency_entries"]
+pub struct CurrencyEntry {
+  pub id: uuid::Uuid,
+  pub transaction_id: uuid::Uuid,
+  pub account_id: uuid::Uuid,
+  pub amount: i64,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// A character's account; characters without transactions have a zero balance
+#[derive(Debug, Serialize)]
+pub struct AccountBalance {
+  pub character_id: uuid::Uuid,
+  pub account_id: Option<uuid::Uuid>,
+  pub balance: i64,
+  pub credit_limit: i64,
+}
+
+// A transaction as seen from one account. entry_amount is the account's side
+// of it and counterparty the name of the other account.
+#[derive(Debug, Serialize)]
+pub struct LedgerLine {
+  #[serde(flatten)]
+  pub transaction: CurrencyTransaction,
+  pub entry_amount: i64,
+  pub counterparty: String,
+}
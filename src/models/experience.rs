@@ -0,0 +1,52 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// What an experience entry does. Stored in experience.kind, which has a CHECK
+// constraint listing the same values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperienceKind {
+  // Given by a GM
+  Award,
+  // Taken away by a GM
+  Spend,
+  // Spent on raising a skill or attribute of the sheet
+  Advancement,
+}
+
+impl ExperienceKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ExperienceKind::Award => "award",
+      ExperienceKind::Spend => "spend",
+      ExperienceKind::Advancement => "advancement",
+    }
+  }
+}
+
+// An entry of a character's experience ledger. amount is negative for spends
+// and advancements.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "experience"]
+pub struct ExperienceEntry {
+  pub id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub kind: String,
+  pub amount: i32,
+  pub reason: String,
+  pub advanced_key: Option<String>,
+  pub advanced_from: Option<i32>,
+  pub advanced_to: Option<i32>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExperienceSummary {
+  pub character_id: uuid::Uuid,
+  pub awarded: i64,
+  pub spent: i64,
+  pub current: i64,
+}
@@ -0,0 +1,84 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// Stored in items.category, which has a CHECK constraint listing the same values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemCategory {
+  Gear,
+  Cyberware,
+  Weapon,
+}
+
+impl ItemCategory {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ItemCategory::Gear => "gear",
+      ItemCategory::Cyberware => "cyberware",
+      ItemCategory::Weapon => "weapon",
+    }
+  }
+}
+
+// An item of the catalog
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "items"]
+pub struct Item {
+  pub id: uuid::Uuid,
+  pub name: String,
+  pub category: String,
+  pub description: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// Catalog items a character carries
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "inventory_items"]
+pub struct InventoryItem {
+  pub id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub item_id: uuid::Uuid,
+  pub quantity: i32,
+  pub equipped: bool,
+  pub notes: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// What an owner may change of an inventory row. The quantity is kept when None.
+#[derive(Debug)]
+pub struct InventoryItemChanges {
+  pub quantity: Option<i32>,
+  pub equipped: bool,
+  pub notes: String,
+  pub updated_by: String,
+}
+
+// An inventory row as listed, with the catalog item
+#[derive(Debug, Serialize)]
+pub struct InventoryEntry {
+  #[serde(flatten)]
+  pub inventory_item: InventoryItem,
+  pub name: String,
+  pub category: String,
+}
+
+// Items given from one character to another. item_name is the name of the
+// item at the time.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "item_transfers"]
+pub struct ItemTransfer {
+  pub id: uuid::Uuid,
+  pub item_id: uuid::Uuid,
+  pub item_name: String,
+  pub from_character_id: uuid::Uuid,
+  pub to_character_id: uuid::Uuid,
+  pub quantity: i32,
+  pub note: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
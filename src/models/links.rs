@@ -0,0 +1,42 @@
+use super::super::schema::*;
+use super::articles::Article;
+use serde::{Deserialize, Serialize};
+
+// A [[link]] in an article body. target_id is None while the link is broken.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "content_links"]
+pub struct ContentLink {
+  pub id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub link_text: String,
+  pub target_type: Option<String>,
+  pub target_slug: String,
+  pub target_id: Option<uuid::Uuid>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// An article linking to the content whose page is shown
+#[derive(Debug, Serialize, Queryable)]
+pub struct Backlink {
+  pub article_id: uuid::Uuid,
+  pub slug: String,
+  pub title: String,
+}
+
+// A link that did not resolve, listed for the authors of the article
+#[derive(Debug, Serialize, Queryable)]
+pub struct BrokenLink {
+  pub article_id: uuid::Uuid,
+  pub slug: String,
+  pub title: String,
+  pub link_text: String,
+}
+
+// A saved article with the link texts that did not resolve
+#[derive(Debug, Serialize)]
+pub struct SavedArticle {
+  #[serde(flatten)]
+  pub article: Article,
+  pub broken_links: Vec<String>,
+}
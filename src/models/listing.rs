@@ -0,0 +1,116 @@
+use actix_web::{HttpRequest, HttpResponse};
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+	CreatedAt,
+	UpdatedAt,
+	// Title of an article or tag, name of a character, username of a user
+	Title,
+	// In-world date of an article; other listings sort by created_at instead
+	InWorldAt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+	Asc,
+	Desc,
+}
+
+// Query parameters shared by every list endpoint. Filters that do not make sense
+// for a listing (e.g. character_id for users) are ignored by its storage.
+// Dates are given as "2050-01-31T12:00:00", tag lists as comma separated UUIDs:
+// tags_all must all be present, one of tags_any and none of tags_none.
+// in_world_from and in_world_to compare against the campaign calendar date.
+// Without limit and offset a listing comes whole, as it did before paging;
+// giving either pages it by DEFAULT_LIMIT.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+	pub limit: Option<i64>,
+	pub offset: Option<i64>,
+	pub sort: Option<SortField>,
+	pub direction: Option<SortDirection>,
+	pub created_from: Option<chrono::NaiveDateTime>,
+	pub created_to: Option<chrono::NaiveDateTime>,
+	pub updated_from: Option<chrono::NaiveDateTime>,
+	pub updated_to: Option<chrono::NaiveDateTime>,
+	pub in_world_from: Option<chrono::NaiveDateTime>,
+	pub in_world_to: Option<chrono::NaiveDateTime>,
+	pub user_id: Option<uuid::Uuid>,
+	pub character_id: Option<uuid::Uuid>,
+	#[serde(default, deserialize_with = "comma_separated")]
+	pub tags_all: Option<Vec<uuid::Uuid>>,
+	#[serde(default, deserialize_with = "comma_separated")]
+	pub tags_any: Option<Vec<uuid::Uuid>>,
+	#[serde(default, deserialize_with = "comma_separated")]
+	pub tags_none: Option<Vec<uuid::Uuid>>,
+}
+
+fn comma_separated<'de, D>(deserializer: D) -> Result<Option<Vec<uuid::Uuid>>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let value = String::deserialize(deserializer)?;
+	value
+		.split(',')
+		.map(str::trim)
+		.filter(|s| !s.is_empty())
+		.map(|s| uuid::Uuid::parse_str(s).map_err(serde::de::Error::custom))
+		.collect::<Result<Vec<_>, _>>()
+		.map(|ids| if ids.is_empty() { None } else { Some(ids) })
+}
+
+impl ListParams {
+	pub fn limit(&self) -> i64 {
+		match (self.limit, self.offset) {
+			(None, None) => i64::MAX,
+			(limit, _) => limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+		}
+	}
+
+	pub fn offset(&self) -> i64 {
+		self.offset.unwrap_or(0).max(0)
+	}
+
+	pub fn sort_or(&self, field: SortField, direction: SortDirection) -> (SortField, SortDirection) {
+		match (self.sort, self.direction) {
+			(Some(f), Some(d)) => (f, d),
+			(Some(f), None) => (f, SortDirection::Asc),
+			(None, Some(d)) => (field, d),
+			(None, None) => (field, direction),
+		}
+	}
+}
+
+// One page of a listing. The body stays a plain JSON array; the total count
+// goes to X-Total-Count and the next page to a Link header.
+#[derive(Debug)]
+pub struct Page<T> {
+	pub items: Vec<T>,
+	pub total: i64,
+}
+
+impl<T: Serialize> Page<T> {
+	pub fn into_response(self, req: &HttpRequest, params: &ListParams) -> HttpResponse {
+		let mut response = HttpResponse::Ok();
+		response.header("X-Total-Count", self.total.to_string());
+
+		let next_offset = params.offset() + self.items.len() as i64;
+		if !self.items.is_empty() && next_offset < self.total {
+			let query: String = url::form_urlencoded::Serializer::new(String::new())
+				.extend_pairs(
+					url::form_urlencoded::parse(req.query_string().as_bytes()).filter(|(key, _)| key != "offset"),
+				)
+				.append_pair("offset", &next_offset.to_string())
+				.finish();
+			response.header("Link", format!("<{}?{}>; rel=\"next\"", req.path(), query));
+		}
+
+		response.json(&self.items)
+	}
+}
@@ -0,0 +1,63 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// A character @mentioned in an article
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "mentions"]
+pub struct Mention {
+  pub id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// What a notification is about. Stored in notifications.kind and
+// notification_opt_outs.kind, which have CHECK constraints listing the same values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+  // One of the user's characters was mentioned in an article
+  Mention,
+}
+
+impl NotificationKind {
+  pub const ALL: [NotificationKind; 1] = [NotificationKind::Mention];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      NotificationKind::Mention => "mention",
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "notifications"]
+pub struct Notification {
+  pub id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub kind: String,
+  pub article_id: Option<uuid::Uuid>,
+  pub character_id: Option<uuid::Uuid>,
+  pub read_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "notification_opt_outs"]
+pub struct NotificationOptOut {
+  pub id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub kind: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// Whether a user gets notifications of a kind
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationSetting {
+  pub kind: NotificationKind,
+  pub enabled: bool,
+}
@@ -0,0 +1,131 @@
+use super::super::schema::*;
+use super::sheets::SheetView;
+use super::users::LoggedUser;
+use serde::{Deserialize, Serialize};
+
+// Who sees a profile field. Stored in the *_visibility columns of
+// character_profiles, which have a CHECK constraint listing the same values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+  // Anyone, signed in or not
+  Public,
+  // Signed in players
+  Party,
+  // The character's owner and GMs
+  Owner,
+  // GMs only
+  Gm,
+}
+
+impl Visibility {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Visibility::Public => "public",
+      Visibility::Party => "party",
+      Visibility::Owner => "owner",
+      Visibility::Gm => "gm",
+    }
+  }
+
+  // Reads a stored value; anything unknown is kept to GMs
+  pub fn stored(value: &str) -> Visibility {
+    match value {
+      "public" => Visibility::Public,
+      "party" => Visibility::Party,
+      "owner" => Visibility::Owner,
+      _ => Visibility::Gm,
+    }
+  }
+
+  // GMs see everything
+  pub fn allows(&self, owner_id: uuid::Uuid, logged_user: &Option<LoggedUser>) -> bool {
+    match logged_user {
+      None => *self == Visibility::Public,
+      Some(user) if user.isadmin => true,
+      Some(user) => match self {
+        Visibility::Public | Visibility::Party => true,
+        Visibility::Owner => user.id == owner_id,
+        Visibility::Gm => false,
+      },
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "character_profiles"]
+pub struct CharacterProfile {
+  pub id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub portrait_url: String,
+  pub bio: String,
+  pub affiliations: Vec<String>,
+  pub gm_notes: String,
+  pub portrait_visibility: String,
+  pub bio_visibility: String,
+  pub affiliations_visibility: String,
+  pub sheet_visibility: String,
+  pub gm_notes_visibility: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl CharacterProfile {
+  // The profile of a character nobody has written one for: the persona is
+  // public, the sheet the owner's and the notes the GMs'
+  pub fn new(character_id: uuid::Uuid, updated_by: String) -> CharacterProfile {
+    CharacterProfile {
+      id: uuid::Uuid::new_v4(),
+      character_id,
+      portrait_url: String::new(),
+      bio: String::new(),
+      affiliations: Vec::new(),
+      gm_notes: String::new(),
+      portrait_visibility: Visibility::Public.as_str().to_string(),
+      bio_visibility: Visibility::Public.as_str().to_string(),
+      affiliations_visibility: Visibility::Public.as_str().to_string(),
+      sheet_visibility: Visibility::Owner.as_str().to_string(),
+      gm_notes_visibility: Visibility::Gm.as_str().to_string(),
+      created_at: chrono::Local::now().naive_local(),
+      updated_by,
+      updated_at: chrono::Local::now().naive_local(),
+    }
+  }
+
+  pub fn visibility(&self) -> ProfileVisibility {
+    ProfileVisibility {
+      portrait: Visibility::stored(&self.portrait_visibility),
+      bio: Visibility::stored(&self.bio_visibility),
+      affiliations: Visibility::stored(&self.affiliations_visibility),
+      sheet: Visibility::stored(&self.sheet_visibility),
+      gm_notes: Visibility::stored(&self.gm_notes_visibility),
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileVisibility {
+  pub portrait: Visibility,
+  pub bio: Visibility,
+  pub affiliations: Visibility,
+  pub sheet: Visibility,
+  pub gm_notes: Visibility,
+}
+
+// A profile as the viewer may see it. Fields the viewer may not see are null;
+// visibility is shown to the owner and GMs only.
+#[derive(Debug, Serialize)]
+pub struct ProfileView {
+  pub character_id: uuid::Uuid,
+  pub name: String,
+  pub slug: String,
+  pub status: String,
+  pub died_at: Option<chrono::NaiveDateTime>,
+  pub portrait_url: Option<String>,
+  pub bio: Option<String>,
+  pub affiliations: Option<Vec<String>>,
+  pub sheet: Option<SheetView>,
+  pub gm_notes: Option<String>,
+  pub visibility: Option<ProfileVisibility>,
+}
@@ -0,0 +1,101 @@
+use super::super::schema::*;
+use super::articles::Article;
+use diesel::sql_types::{BigInt, Text, Uuid};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The fixed set of reactions. Stored in article_reactions.reaction, which has a
+// CHECK constraint listing the same values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionType {
+  Like,
+  Love,
+  Laugh,
+  Wow,
+  Sad,
+  Angry,
+}
+
+impl ReactionType {
+  pub const ALL: [ReactionType; 6] = [
+    ReactionType::Like,
+    ReactionType::Love,
+    ReactionType::Laugh,
+    ReactionType::Wow,
+    ReactionType::Sad,
+    ReactionType::Angry,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ReactionType::Like => "like",
+      ReactionType::Love => "love",
+      ReactionType::Laugh => "laugh",
+      ReactionType::Wow => "wow",
+      ReactionType::Sad => "sad",
+      ReactionType::Angry => "angry",
+    }
+  }
+
+  pub fn emoji(&self) -> &'static str {
+    match self {
+      ReactionType::Like => "👍",
+      ReactionType::Love => "❤️",
+      ReactionType::Laugh => "😂",
+      ReactionType::Wow => "😮",
+      ReactionType::Sad => "😢",
+      ReactionType::Angry => "😠",
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReactionKind {
+  pub reaction: ReactionType,
+  pub emoji: &'static str,
+}
+
+// character_id is set when the reaction was given in-world as a character
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "article_reactions"]
+pub struct ArticleReaction {
+  pub id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub character_id: Option<uuid::Uuid>,
+  pub reaction: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "favorites"]
+pub struct Favorite {
+  pub id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// Number of reactions of one kind, or of favorites when reaction is "favorite"
+#[derive(Debug, QueryableByName)]
+pub struct ReactionCount {
+  #[sql_type = "Uuid"]
+  pub article_id: uuid::Uuid,
+  #[sql_type = "Text"]
+  pub reaction: String,
+  #[sql_type = "BigInt"]
+  pub count: i64,
+}
+
+// An article as returned by the API, with its reaction and favorite counts.
+// Reactions nobody has given are left out of the map.
+#[derive(Debug, Serialize)]
+pub struct ArticleWithReactions {
+  #[serde(flatten)]
+  pub article: Article,
+  pub reactions: BTreeMap<String, i64>,
+  pub favorites: i64,
+}
@@ -0,0 +1,69 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// Stored in relationships.kind, which has a CHECK constraint listing the same values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipKind {
+  Ally,
+  Rival,
+  Contact,
+  Family,
+}
+
+impl RelationshipKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      RelationshipKind::Ally => "ally",
+      RelationshipKind::Rival => "rival",
+      RelationshipKind::Contact => "contact",
+      RelationshipKind::Family => "family",
+    }
+  }
+}
+
+// A tie from one character to another. visibility takes the values of
+// profiles::Visibility.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "relationships"]
+pub struct Relationship {
+  pub id: uuid::Uuid,
+  pub from_character_id: uuid::Uuid,
+  pub to_character_id: uuid::Uuid,
+  pub kind: String,
+  pub directed: bool,
+  pub notes: String,
+  pub visibility: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// What the owner may change of a relationship; its characters stay the same
+#[derive(Debug)]
+pub struct RelationshipChanges {
+  pub kind: RelationshipKind,
+  pub directed: bool,
+  pub notes: String,
+  pub visibility: String,
+  pub updated_by: String,
+}
+
+// A character in a relationship graph
+#[derive(Debug, Serialize)]
+pub struct RelationshipNode {
+  pub character_id: uuid::Uuid,
+  pub name: String,
+  pub slug: String,
+  pub status: String,
+}
+
+// The characters within depth relationships of a character and the
+// relationships between them that the viewer may see
+#[derive(Debug, Serialize)]
+pub struct Neighborhood {
+  pub character_id: uuid::Uuid,
+  pub depth: u32,
+  pub nodes: Vec<RelationshipNode>,
+  pub relationships: Vec<Relationship>,
+}
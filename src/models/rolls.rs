@@ -0,0 +1,79 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+// An entry of a campaign's roll log. result holds the dice as rolled,
+// character_name the name of the character when it rolled.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "rolls"]
+pub struct Roll {
+  pub id: uuid::Uuid,
+  pub campaign: String,
+  pub sequence: i64,
+  pub user_id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub character_name: String,
+  pub expression: String,
+  pub seed: i64,
+  pub result: serde_json::Value,
+  pub total: i64,
+  pub reason: String,
+  pub prev_hash: String,
+  pub hash: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// A roll to be logged. The log gives it its sequence and hashes.
+#[derive(Debug)]
+pub struct NewRoll {
+  pub campaign: String,
+  pub user_id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub character_name: String,
+  pub expression: String,
+  pub seed: i64,
+  pub result: serde_json::Value,
+  pub total: i64,
+  pub reason: String,
+  pub updated_by: String,
+}
+
+// The outcome of checking a campaign's roll log. broken_at is the sequence of
+// the first roll that does not match the chain or its seed.
+#[derive(Debug, Serialize)]
+pub struct RollLogCheck {
+  pub campaign: String,
+  pub rolls: i64,
+  pub valid: bool,
+  pub broken_at: Option<i64>,
+  pub problem: Option<String>,
+}
+
+// A value added to a check, from the sheet or from the situation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckModifier {
+  pub name: String,
+  pub value: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CheckOutcome {
+  pub success: bool,
+  pub degree: i64,
+  pub critical: bool,
+}
+
+// A resolved check, logged as the result of its roll. natural is the roll of
+// the dice alone.
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+  pub check: String,
+  pub difficulty: i64,
+  pub modifiers: Vec<CheckModifier>,
+  pub natural: i64,
+  pub total: i64,
+  #[serde(flatten)]
+  pub outcome: CheckOutcome,
+  pub roll: crate::dice::RollResult,
+}
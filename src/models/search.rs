@@ -0,0 +1,31 @@
+use diesel::sql_types::{BigInt, Float4, Text, Uuid};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, QueryableByName)]
+pub struct SearchHit {
+	#[sql_type = "Text"]
+	pub content_type: String,
+	#[sql_type = "Uuid"]
+	pub id: uuid::Uuid,
+	#[sql_type = "Text"]
+	pub title: String,
+	#[sql_type = "Text"]
+	pub snippet: String,
+	#[sql_type = "Float4"]
+	pub rank: f32,
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct SearchCount {
+	#[sql_type = "BigInt"]
+	pub total: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+	pub query: String,
+	pub total: i64,
+	pub limit: i64,
+	pub offset: i64,
+	pub results: Vec<SearchHit>,
+}
@@ -0,0 +1,64 @@
+use super::super::schema::*;
+use super::reactions::ArticleWithReactions;
+use serde::{Deserialize, Serialize};
+
+// An ordered collection of articles telling one in-world story
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "series"]
+pub struct Series {
+  pub id: uuid::Uuid,
+  pub user_id: uuid::Uuid,
+  pub title: String,
+  pub description: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "series_articles"]
+pub struct SeriesArticle {
+  pub id: uuid::Uuid,
+  pub series_id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub position: i16,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// An article as listed in a series. position counts from 0 in reading order.
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct SeriesEntry {
+  pub series_id: uuid::Uuid,
+  pub article_id: uuid::Uuid,
+  pub slug: String,
+  pub title: String,
+  pub in_world_at: Option<chrono::NaiveDateTime>,
+  pub position: i16,
+}
+
+// A series with its articles, the series index
+#[derive(Debug, Serialize)]
+pub struct SeriesIndex {
+  #[serde(flatten)]
+  pub series: Series,
+  pub articles: Vec<SeriesEntry>,
+}
+
+// Where an article is in one of its series
+#[derive(Debug, Serialize)]
+pub struct SeriesNavigation {
+  pub series_id: uuid::Uuid,
+  pub series_title: String,
+  pub position: i16,
+  pub total: usize,
+  pub previous: Option<SeriesEntry>,
+  pub next: Option<SeriesEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArticleWithSeries {
+  #[serde(flatten)]
+  pub article: ArticleWithReactions,
+  pub series: Vec<SeriesNavigation>,
+}
@@ -0,0 +1,44 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A character's sheet. data holds SheetData as JSON, validated against the
+// ruleset named here when it was saved.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "character_sheets"]
+pub struct CharacterSheet {
+  pub id: uuid::Uuid,
+  pub character_id: uuid::Uuid,
+  pub ruleset: String,
+  pub ruleset_version: i32,
+  pub data: serde_json::Value,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+// Values of a sheet by the keys of the ruleset
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SheetData {
+  #[serde(default)]
+  pub attributes: BTreeMap<String, serde_json::Value>,
+  #[serde(default)]
+  pub skills: BTreeMap<String, i64>,
+  #[serde(default)]
+  pub sections: BTreeMap<String, String>,
+}
+
+// A sheet as shown, with values derived under the current ruleset. outdated
+// tells that the sheet was saved under another ruleset version.
+#[derive(Debug, Serialize)]
+pub struct SheetView {
+  pub character_id: uuid::Uuid,
+  pub ruleset: String,
+  pub ruleset_version: i32,
+  pub outdated: bool,
+  #[serde(flatten)]
+  pub data: SheetData,
+  pub derived: BTreeMap<String, i64>,
+  pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,37 @@
+use super::super::schema::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "slug_history"]
+pub struct SlugHistory {
+  pub id: uuid::Uuid,
+  pub content_type: String,
+  pub content_id: uuid::Uuid,
+  pub slug: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// Result of looking content up by UUID or slug. Renamed carries the current
+// slug when an old one was used, so that the caller can redirect.
+#[derive(Debug)]
+pub enum Resolved<T> {
+  Current(T),
+  Renamed(String),
+}
+
+// Kinds of content that have slugs, stored in slug_history.content_type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlugOwner {
+  Article,
+  Character,
+}
+
+impl SlugOwner {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SlugOwner::Article => "article",
+      SlugOwner::Character => "character",
+    }
+  }
+}
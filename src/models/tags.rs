@@ -1,5 +1,40 @@
 use super::super::schema::*;
+use crate::errors::ServiceError;
+use diesel::sql_types::{BigInt, Nullable, Text, Uuid};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// Kinds of content that can be tagged. Stored in contenttags.content_type;
+// adding a kind also needs a typed reference column in the contenttags table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentType {
+  Article,
+  Character,
+  User,
+}
+
+impl ContentType {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ContentType::Article => "article",
+      ContentType::Character => "character",
+      ContentType::User => "user",
+    }
+  }
+}
+
+impl FromStr for ContentType {
+  type Err = ServiceError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "article" => Ok(ContentType::Article),
+      "character" => Ok(ContentType::Character),
+      "user" => Ok(ContentType::User),
+      _ => Err(ServiceError::BadRequest(format!("Unknown content type: {}", s))),
+    }
+  }
+}
 
 #[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
 #[table_name = "tags"]
@@ -8,6 +43,59 @@ pub struct Tag {
   pub title: String,
   pub created_at: chrono::NaiveDateTime,
   pub updated_by: String,
+  pub updated_at: chrono::NaiveDateTime,
+  pub parent_id: Option<uuid::Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "tag_aliases"]
+pub struct TagAlias {
+  pub id: uuid::Uuid,
+  pub tag_id: uuid::Uuid,
+  pub alias: String,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_by: String,
+}
+
+// A tag with the number of content links it has, or in related tag listings
+// the number of content items it shares with the other tag
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct TagCount {
+  #[sql_type = "Uuid"]
+  pub id: uuid::Uuid,
+  #[sql_type = "Text"]
+  pub title: String,
+  #[sql_type = "BigInt"]
+  pub count: i64,
+}
+
+// An autocomplete match. alias is set when the tag was found by one of its aliases.
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct TagSuggestion {
+  #[sql_type = "Uuid"]
+  pub id: uuid::Uuid,
+  #[sql_type = "Text"]
+  pub title: String,
+  #[sql_type = "Nullable<Text>"]
+  pub alias: Option<String>,
+  #[sql_type = "BigInt"]
+  pub count: i64,
+}
+
+// Outcome of deleting a tag. A tag still linked to content is only deleted,
+// together with its links, when a cascade was asked for.
+#[derive(Debug, Serialize)]
+pub struct TagDeletion {
+  pub tag_id: uuid::Uuid,
+  pub link_count: i64,
+  pub deleted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagMerge {
+  pub tag: Tag,
+  pub moved_links: usize,
+  pub dropped_links: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
@@ -15,6 +103,7 @@ pub struct Tag {
 pub struct ContentTag {
   pub id: uuid::Uuid,
   pub tag_id: uuid::Uuid,
+  pub content_type: String,
   pub content_id: uuid::Uuid,
   pub created_at: chrono::NaiveDateTime,
   pub updated_by: String,
@@ -23,9 +112,9 @@ pub struct ContentTag {
 #[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
 #[table_name = "rich_contenttags"]
 pub struct RichContentTag {
-  pub idx: i32,
   pub contenttag_id: uuid::Uuid,
   pub tag_id: uuid::Uuid,
+  pub content_type: String,
   pub content_id: uuid::Uuid,
   pub tag_title: String,
 }
\ No newline at end of file
@@ -20,6 +20,7 @@ pub struct User {
 	pub username: String,
 	pub hash: String,
 	pub created_at: chrono::NaiveDateTime,
+	pub updated_at: chrono::NaiveDateTime,
 }
 
 #[derive(Identifiable, Queryable, Serialize, Deserialize, Associations, PartialEq, Debug, Insertable)]
@@ -52,6 +53,7 @@ impl User {
 			username: username.into(),
 			hash: pwd.into(),
 			created_at: chrono::Local::now().naive_local(),
+			updated_at: chrono::Local::now().naive_local(),
 		}
 	}
 }
@@ -0,0 +1,660 @@
+// Character sheet rules. Attributes, skills, derived values and sections come
+// from a versioned ruleset file instead of the database, so that the game system
+// can change without a migration per stat. Loaded from the TOML or JSON file
+// named by CHARACTER_RULESET, rulesets/hki2050.toml by default.
+use crate::dice;
+use crate::errors::ServiceError;
+use crate::models::rolls::{CheckModifier, CheckOutcome};
+use crate::models::sheets::SheetData;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const DEFAULT_RULESET: &str = include_str!("../rulesets/hki2050.toml");
+
+lazy_static::lazy_static! {
+pub static ref RULESET: Ruleset = match std::env::var("CHARACTER_RULESET") {
+	Ok(path) => Ruleset::load(&path).unwrap_or_else(|err| panic!("Invalid CHARACTER_RULESET {}: {}", path, err)),
+	Err(_) => Ruleset::parse(DEFAULT_RULESET, true).unwrap_or_else(|err| panic!("Invalid default ruleset: {}", err)),
+};
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ruleset {
+	pub name: String,
+	pub version: i32,
+	#[serde(default)]
+	pub attributes: Vec<AttributeRule>,
+	#[serde(default)]
+	pub skills: Vec<SkillRule>,
+	// Evaluated in order, so a formula can use the values above it
+	#[serde(default)]
+	pub derived: Vec<DerivedRule>,
+	#[serde(default)]
+	pub sections: Vec<SectionRule>,
+	// Without check rules characters cannot make checks
+	pub checks: Option<CheckRule>,
+	// Without advancement rules experience cannot be spent on the sheet
+	pub advancement: Option<AdvancementRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeType {
+	Integer,
+	Text,
+	Boolean,
+	Choice,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeRule {
+	pub key: String,
+	pub name: String,
+	#[serde(rename = "type")]
+	pub kind: AttributeType,
+	pub min: Option<i64>,
+	pub max: Option<i64>,
+	pub max_length: Option<usize>,
+	#[serde(default)]
+	pub choices: Vec<String>,
+	// Attributes without a default must be given on every sheet
+	pub default: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillRule {
+	pub key: String,
+	pub name: String,
+	// The attribute the skill is usually rolled with
+	pub attribute: Option<String>,
+	pub max: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedRule {
+	pub key: String,
+	pub name: String,
+	pub formula: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionRule {
+	pub key: String,
+	pub name: String,
+	pub max_length: Option<usize>,
+}
+
+// How checks are rolled. The dice plus the modifiers are compared to the
+// difficulty; criticals go by the dice alone and decide the check whatever
+// the modifiers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckRule {
+	pub dice: String,
+	pub critical_success: Option<i64>,
+	pub critical_failure: Option<i64>,
+	#[serde(default = "default_degree_step")]
+	pub degree_step: i64,
+}
+
+// Experience costs of raising a skill or an integer attribute by one, as
+// formulas of current and new, the values before and after
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvancementRule {
+	pub skill_cost: String,
+	pub attribute_cost: String,
+}
+
+// A sheet value raised by one, with the sheet as it is after the raise
+#[derive(Debug)]
+pub struct Advancement {
+	pub key: String,
+	pub current: i64,
+	pub new: i64,
+	pub cost: i64,
+	pub data: SheetData,
+}
+
+fn default_degree_step() -> i64 {
+	1
+}
+
+impl CheckRule {
+	// The degree is 1 for a bare success or failure and grows by one for
+	// every full degree_step the total is past the difficulty
+	pub fn outcome(&self, natural: i64, total: i64, difficulty: i64) -> CheckOutcome {
+		let margin = total - difficulty;
+		let degree = if margin >= 0 { margin } else { -margin - 1 } / self.degree_step + 1;
+		if self.critical_success.is_some_and(|critical| natural >= critical) {
+			return CheckOutcome {
+				success: true,
+				degree: if margin >= 0 { degree } else { 1 },
+				critical: true,
+			};
+		}
+		if self.critical_failure.is_some_and(|critical| natural <= critical) {
+			return CheckOutcome {
+				success: false,
+				degree: if margin < 0 { degree } else { 1 },
+				critical: true,
+			};
+		}
+		CheckOutcome {
+			success: margin >= 0,
+			degree,
+			critical: false,
+		}
+	}
+}
+
+impl Ruleset {
+	pub fn load(path: &str) -> Result<Ruleset, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+		Ruleset::parse(&text, path.ends_with(".toml"))
+	}
+
+	pub fn parse(text: &str, is_toml: bool) -> Result<Ruleset, String> {
+		let ruleset: Ruleset = if is_toml {
+			toml::from_str(text).map_err(|err| err.to_string())?
+		} else {
+			serde_json::from_str(text).map_err(|err| err.to_string())?
+		};
+		ruleset.check_rules()?;
+		Ok(ruleset)
+	}
+
+	fn check_rules(&self) -> Result<(), String> {
+		let mut keys: Vec<&str> = Vec::new();
+		let all_keys = self
+			.attributes
+			.iter()
+			.map(|rule| &rule.key)
+			.chain(self.skills.iter().map(|rule| &rule.key))
+			.chain(self.derived.iter().map(|rule| &rule.key))
+			.chain(self.sections.iter().map(|rule| &rule.key));
+		for key in all_keys {
+			if key.is_empty() || keys.contains(&key.as_str()) {
+				return Err(format!("key \"{}\" is empty or used twice", key));
+			}
+			keys.push(key);
+		}
+
+		let mut numbers: Vec<&str> = Vec::new();
+		for rule in &self.attributes {
+			if rule.kind == AttributeType::Choice && rule.choices.is_empty() {
+				return Err(format!("choice attribute {} has no choices", rule.key));
+			}
+			if let Some(ref default) = rule.default {
+				check_attribute(rule, default)?;
+			}
+			if rule.kind == AttributeType::Integer {
+				numbers.push(&rule.key);
+			}
+		}
+		for rule in &self.skills {
+			if let Some(ref attribute) = rule.attribute {
+				if !numbers.contains(&attribute.as_str()) {
+					return Err(format!("skill {} uses {}, which is not an integer attribute", rule.key, attribute));
+				}
+			}
+			numbers.push(&rule.key);
+		}
+		for rule in &self.derived {
+			let formula = Formula::parse(&rule.formula).map_err(|err| format!("formula of {}: {}", rule.key, err))?;
+			let mut names = Vec::new();
+			formula.names(&mut names);
+			if let Some(unknown) = names.iter().find(|name| !numbers.contains(&name.as_str())) {
+				return Err(format!("formula of {} uses unknown value {}", rule.key, unknown));
+			}
+			numbers.push(&rule.key);
+		}
+		if let Some(ref checks) = self.checks {
+			let expression = dice::parse(&checks.dice).map_err(|err| format!("check dice: {}", err))?;
+			if expression.terms.iter().any(|(_, term)| matches!(term, dice::Term::Dice(d) if d.target.is_some())) {
+				return Err(String::from("check dice cannot count successes"));
+			}
+			if checks.degree_step < 1 {
+				return Err(String::from("check degree_step must be at least 1"));
+			}
+		}
+		if let Some(ref advancement) = self.advancement {
+			for (key, cost) in &[("skill_cost", &advancement.skill_cost), ("attribute_cost", &advancement.attribute_cost)] {
+				let formula = Formula::parse(cost).map_err(|err| format!("advancement {}: {}", key, err))?;
+				let mut names = Vec::new();
+				formula.names(&mut names);
+				if let Some(unknown) = names.iter().find(|name| *name != "current" && *name != "new") {
+					return Err(format!("advancement {} uses unknown value {}", key, unknown));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	// Checks a sheet and fills in defaults, zero skills and empty sections.
+	// Every problem is reported at once.
+	pub fn validate(&self, data: SheetData) -> Result<SheetData, ServiceError> {
+		let mut errors: Vec<String> = Vec::new();
+		let mut sheet = SheetData::default();
+
+		for key in data.attributes.keys().filter(|key| !self.attributes.iter().any(|rule| &rule.key == *key)) {
+			errors.push(format!("unknown attribute {}", key));
+		}
+		for key in data.skills.keys().filter(|key| !self.skills.iter().any(|rule| &rule.key == *key)) {
+			errors.push(format!("unknown skill {}", key));
+		}
+		for key in data.sections.keys().filter(|key| !self.sections.iter().any(|rule| &rule.key == *key)) {
+			errors.push(format!("unknown section {}", key));
+		}
+
+		for rule in &self.attributes {
+			match data.attributes.get(&rule.key).or(rule.default.as_ref()) {
+				Some(value) => match check_attribute(rule, value) {
+					Ok(()) => {
+						sheet.attributes.insert(rule.key.clone(), value.clone());
+					}
+					Err(err) => errors.push(err),
+				},
+				None => errors.push(format!("{} is required", rule.key)),
+			}
+		}
+		for rule in &self.skills {
+			let value = data.skills.get(&rule.key).copied().unwrap_or(0);
+			if value < 0 || value > rule.max {
+				errors.push(format!("{} must be between 0 and {}", rule.key, rule.max));
+			}
+			sheet.skills.insert(rule.key.clone(), value);
+		}
+		for rule in &self.sections {
+			let text = data.sections.get(&rule.key).cloned().unwrap_or_default();
+			if rule.max_length.is_some_and(|max| text.chars().count() > max) {
+				errors.push(format!("{} is too long", rule.key));
+			}
+			sheet.sections.insert(rule.key.clone(), text);
+		}
+
+		if !errors.is_empty() {
+			return Err(ServiceError::BadRequest(format!("Invalid character sheet: {}", errors.join("; "))));
+		}
+		Ok(sheet)
+	}
+
+	// Derived values of a sheet. Values the sheet lacks count as zero, so that
+	// sheets saved under older rules can still be shown.
+	pub fn derive(&self, data: &SheetData) -> BTreeMap<String, i64> {
+		let mut values: BTreeMap<String, i64> = data
+			.attributes
+			.iter()
+			.filter_map(|(key, value)| Some((key.clone(), value.as_i64()?)))
+			.chain(data.skills.iter().map(|(key, value)| (key.clone(), *value)))
+			.collect();
+		let mut derived = BTreeMap::new();
+		for rule in &self.derived {
+			let value = Formula::parse(&rule.formula).map_or(0, |formula| formula.evaluate(&values));
+			values.insert(rule.key.clone(), value);
+			derived.insert(rule.key.clone(), value);
+		}
+		derived
+	}
+
+	// What a sheet adds to a check of the given skill, integer attribute or
+	// derived value. A skill is rolled with its attribute.
+	pub fn check_modifiers(&self, key: &str, data: &SheetData) -> Option<Vec<CheckModifier>> {
+		let attribute = |key: &str| CheckModifier {
+			name: key.to_string(),
+			value: data.attributes.get(key).and_then(|value| value.as_i64()).unwrap_or(0),
+		};
+		if let Some(rule) = self.skills.iter().find(|rule| rule.key == key) {
+			let mut modifiers = vec![CheckModifier {
+				name: rule.key.clone(),
+				value: data.skills.get(key).copied().unwrap_or(0),
+			}];
+			modifiers.extend(rule.attribute.as_deref().map(attribute));
+			return Some(modifiers);
+		}
+		if self.attributes.iter().any(|rule| rule.key == key && rule.kind == AttributeType::Integer) {
+			return Some(vec![attribute(key)]);
+		}
+		if self.derived.iter().any(|rule| rule.key == key) {
+			return Some(vec![CheckModifier {
+				name: key.to_string(),
+				value: self.derive(data).get(key).copied().unwrap_or(0),
+			}]);
+		}
+		None
+	}
+
+	// The skills and integer attributes that are higher on the new sheet than on
+	// the old one. Values the old sheet lacks are not compared.
+	pub fn raised(&self, old: &SheetData, new: &SheetData) -> Vec<String> {
+		let skills = self.skills.iter().filter(|rule| {
+			new.skills.get(&rule.key).copied().unwrap_or(0) > old.skills.get(&rule.key).copied().unwrap_or(0)
+		});
+		let attributes = self.attributes.iter().filter(|rule| {
+			let value = |data: &SheetData| data.attributes.get(&rule.key).and_then(|value| value.as_i64());
+			rule.kind == AttributeType::Integer && matches!((value(old), value(new)), (Some(old), Some(new)) if new > old)
+		});
+		skills.map(|rule| rule.key.clone()).chain(attributes.map(|rule| rule.key.clone())).collect()
+	}
+
+	// Raises a skill or an integer attribute of a valid sheet by one and works
+	// out what it costs
+	pub fn advance(&self, key: &str, data: &SheetData) -> Result<Advancement, ServiceError> {
+		let rule = self
+			.advancement
+			.as_ref()
+			.ok_or_else(|| ServiceError::BadRequest("The ruleset has no advancement".into()))?;
+		let mut raised = data.clone();
+		let (current, cost) = if self.skills.iter().any(|skill| skill.key == key) {
+			let current = data.skills.get(key).copied().unwrap_or(0);
+			raised.skills.insert(key.to_string(), current + 1);
+			(current, &rule.skill_cost)
+		} else if self.attributes.iter().any(|attribute| attribute.key == key && attribute.kind == AttributeType::Integer) {
+			let current = data.attributes.get(key).and_then(|value| value.as_i64()).unwrap_or(0);
+			raised.attributes.insert(key.to_string(), serde_json::Value::from(current + 1));
+			(current, &rule.attribute_cost)
+		} else {
+			return Err(ServiceError::BadRequest(format!("{} cannot be advanced", key)));
+		};
+
+		let data = self.validate(raised)?;
+		let values = [(String::from("current"), current), (String::from("new"), current + 1)].iter().cloned().collect();
+		let cost = Formula::parse(cost).map_or(0, |formula| formula.evaluate(&values)).max(0);
+		Ok(Advancement {
+			key: key.to_string(),
+			current,
+			new: current + 1,
+			cost,
+			data,
+		})
+	}
+}
+
+fn check_attribute(rule: &AttributeRule, value: &serde_json::Value) -> Result<(), String> {
+	let valid = match rule.kind {
+		AttributeType::Integer => value.as_i64().is_some_and(|number| {
+			rule.min.is_none_or(|min| number >= min) && rule.max.is_none_or(|max| number <= max)
+		}),
+		AttributeType::Text => value
+			.as_str()
+			.is_some_and(|text| rule.max_length.is_none_or(|max| text.chars().count() <= max)),
+		AttributeType::Boolean => value.is_boolean(),
+		AttributeType::Choice => value.as_str().is_some_and(|choice| rule.choices.iter().any(|c| c == choice)),
+	};
+	if valid {
+		return Ok(());
+	}
+	Err(match rule.kind {
+		AttributeType::Integer => format!(
+			"{} must be an integer between {} and {}",
+			rule.key,
+			rule.min.map_or(String::from("-"), |min| min.to_string()),
+			rule.max.map_or(String::from("-"), |max| max.to_string())
+		),
+		AttributeType::Text => format!("{} must be text of at most {} characters", rule.key, rule.max_length.unwrap_or(0)),
+		AttributeType::Boolean => format!("{} must be true or false", rule.key),
+		AttributeType::Choice => format!("{} must be one of {}", rule.key, rule.choices.join(", ")),
+	})
+}
+
+// Integer arithmetic over named values: + - * / and parentheses
+#[derive(Debug, PartialEq)]
+enum Formula {
+	Number(i64),
+	Name(String),
+	Negate(Box<Formula>),
+	Operation(Box<Formula>, char, Box<Formula>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+	Number(i64),
+	Name(String),
+	Symbol(char),
+}
+
+impl Formula {
+	fn parse(text: &str) -> Result<Formula, String> {
+		let tokens = tokenize(text)?;
+		let mut position = 0;
+		let formula = parse_sum(&tokens, &mut position)?;
+		if position < tokens.len() {
+			return Err(format!("unexpected {:?}", tokens[position]));
+		}
+		Ok(formula)
+	}
+
+	fn names(&self, names: &mut Vec<String>) {
+		match self {
+			Formula::Number(_) => {}
+			Formula::Name(name) => names.push(name.clone()),
+			Formula::Negate(inner) => inner.names(names),
+			Formula::Operation(left, _, right) => {
+				left.names(names);
+				right.names(names);
+			}
+		}
+	}
+
+	// Division rounds down and gives zero when dividing by zero
+	fn evaluate(&self, values: &BTreeMap<String, i64>) -> i64 {
+		match self {
+			Formula::Number(number) => *number,
+			Formula::Name(name) => values.get(name).copied().unwrap_or(0),
+			Formula::Negate(inner) => inner.evaluate(values).saturating_neg(),
+			Formula::Operation(left, operator, right) => {
+				let (left, right) = (left.evaluate(values), right.evaluate(values));
+				match operator {
+					'+' => left.saturating_add(right),
+					'-' => left.saturating_sub(right),
+					'*' => left.saturating_mul(right),
+					_ => left.checked_div_euclid(right).unwrap_or(0),
+				}
+			}
+		}
+	}
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+	let mut tokens = Vec::new();
+	let mut chars = text.chars().peekable();
+	while let Some(&c) = chars.peek() {
+		if c.is_whitespace() {
+			chars.next();
+		} else if c.is_ascii_digit() {
+			let mut digits = String::new();
+			while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+				digits.push(d);
+				chars.next();
+			}
+			tokens.push(Token::Number(digits.parse().map_err(|_| format!("number {} is too large", digits))?));
+		} else if c.is_ascii_alphabetic() || c == '_' {
+			let mut name = String::new();
+			while let Some(&n) = chars.peek().filter(|n| n.is_ascii_alphanumeric() || **n == '_') {
+				name.push(n);
+				chars.next();
+			}
+			tokens.push(Token::Name(name));
+		} else if "+-*/()".contains(c) {
+			tokens.push(Token::Symbol(c));
+			chars.next();
+		} else {
+			return Err(format!("unexpected character {}", c));
+		}
+	}
+	Ok(tokens)
+}
+
+fn parse_sum(tokens: &[Token], position: &mut usize) -> Result<Formula, String> {
+	let mut formula = parse_product(tokens, position)?;
+	while let Some(Token::Symbol(operator @ ('+' | '-'))) = tokens.get(*position) {
+		*position += 1;
+		formula = Formula::Operation(Box::new(formula), *operator, Box::new(parse_product(tokens, position)?));
+	}
+	Ok(formula)
+}
+
+fn parse_product(tokens: &[Token], position: &mut usize) -> Result<Formula, String> {
+	let mut formula = parse_unary(tokens, position)?;
+	while let Some(Token::Symbol(operator @ ('*' | '/'))) = tokens.get(*position) {
+		*position += 1;
+		formula = Formula::Operation(Box::new(formula), *operator, Box::new(parse_unary(tokens, position)?));
+	}
+	Ok(formula)
+}
+
+fn parse_unary(tokens: &[Token], position: &mut usize) -> Result<Formula, String> {
+	*position += 1;
+	match tokens.get(*position - 1) {
+		Some(Token::Number(number)) => Ok(Formula::Number(*number)),
+		Some(Token::Name(name)) => Ok(Formula::Name(name.clone())),
+		Some(Token::Symbol('-')) => Ok(Formula::Negate(Box::new(parse_unary(tokens, position)?))),
+		Some(Token::Symbol('(')) => {
+			let formula = parse_sum(tokens, position)?;
+			if tokens.get(*position) != Some(&Token::Symbol(')')) {
+				return Err(String::from("missing )"));
+			}
+			*position += 1;
+			Ok(formula)
+		}
+		Some(token) => Err(format!("unexpected {:?}", token)),
+		None => Err(String::from("unexpected end")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	fn evaluate(text: &str, values: &[(&str, i64)]) -> i64 {
+		let values = values.iter().map(|(key, value)| (key.to_string(), *value)).collect();
+		Formula::parse(text).unwrap().evaluate(&values)
+	}
+
+	#[test]
+	fn formulas_follow_precedence_and_round_down() {
+		assert_eq!(evaluate("8 + body * 2", &[("body", 3)]), 14);
+		assert_eq!(evaluate("(agility + melee) / 2 + 1", &[("agility", 3), ("melee", 2)]), 3);
+		assert_eq!(evaluate("-mind / 2", &[("mind", 3)]), -2);
+		assert_eq!(evaluate("body / 0 + missing", &[("body", 3)]), 0);
+		assert!(Formula::parse("(body + 1").is_err());
+		assert!(Formula::parse("body +").is_err());
+		assert!(Formula::parse("body % 2").is_err());
+	}
+
+	#[test]
+	fn default_ruleset_fills_defaults_and_derives() {
+		let ruleset = Ruleset::parse(DEFAULT_RULESET, true).unwrap();
+		let mut data = SheetData::default();
+		data.attributes.insert(String::from("archetype"), json!("hakkeri"));
+		data.attributes.insert(String::from("body"), json!(3));
+		data.skills.insert(String::from("melee"), 2);
+
+		let sheet = ruleset.validate(data).unwrap();
+		assert_eq!(sheet.attributes["agility"], json!(2));
+		assert_eq!(sheet.skills["hacking"], 0);
+		assert_eq!(sheet.sections["background"], "");
+
+		let derived = ruleset.derive(&sheet);
+		assert_eq!(derived["health"], 14);
+		assert_eq!(derived["defense"], 3);
+	}
+
+	#[test]
+	fn validate_reports_every_problem() {
+		let ruleset = Ruleset::parse(DEFAULT_RULESET, true).unwrap();
+		let mut data = SheetData::default();
+		data.attributes.insert(String::from("body"), json!(7));
+		data.attributes.insert(String::from("luck"), json!(1));
+		data.skills.insert(String::from("hacking"), -1);
+
+		match ruleset.validate(data) {
+			Err(ServiceError::BadRequest(message)) => {
+				assert!(message.contains("unknown attribute luck"));
+				assert!(message.contains("body must be an integer between 1 and 6"));
+				assert!(message.contains("archetype is required"));
+				assert!(message.contains("hacking must be between 0 and 6"));
+			}
+			other => panic!("expected a bad request, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn checks_add_the_skill_and_its_attribute() {
+		let ruleset = Ruleset::parse(DEFAULT_RULESET, true).unwrap();
+		let mut data = SheetData::default();
+		data.attributes.insert(String::from("mind"), json!(4));
+		data.skills.insert(String::from("hacking"), 3);
+
+		let modifiers = ruleset.check_modifiers("hacking", &data).unwrap();
+		assert_eq!(modifiers.iter().map(|m| (m.name.as_str(), m.value)).collect::<Vec<_>>(), vec![("hacking", 3), ("mind", 4)]);
+		assert_eq!(ruleset.check_modifiers("health", &data).unwrap()[0].value, 8);
+		assert!(ruleset.check_modifiers("archetype", &data).is_none());
+		assert!(ruleset.check_modifiers("luck", &data).is_none());
+	}
+
+	#[test]
+	fn check_outcomes_have_degrees_and_criticals() {
+		let checks = Ruleset::parse(DEFAULT_RULESET, true).unwrap().checks.unwrap();
+		let outcome = |natural, total| {
+			let o = checks.outcome(natural, total, 12);
+			(o.success, o.degree, o.critical)
+		};
+		assert_eq!(outcome(7, 12), (true, 1, false));
+		assert_eq!(outcome(7, 18), (true, 3, false));
+		assert_eq!(outcome(7, 11), (false, 1, false));
+		assert_eq!(outcome(5, 8), (false, 2, false));
+		assert_eq!(outcome(12, 11), (true, 1, true));
+		assert_eq!(outcome(2, 15), (false, 1, true));
+	}
+
+	#[test]
+	fn advancement_raises_by_one_up_to_the_maximum() {
+		let ruleset = Ruleset::parse(DEFAULT_RULESET, true).unwrap();
+		let mut data = SheetData::default();
+		data.attributes.insert(String::from("archetype"), json!("hakkeri"));
+		data.skills.insert(String::from("hacking"), 2);
+		let data = ruleset.validate(data).unwrap();
+
+		let advancement = ruleset.advance("hacking", &data).unwrap();
+		assert_eq!((advancement.current, advancement.new, advancement.cost), (2, 3, 6));
+		assert_eq!(advancement.data.skills["hacking"], 3);
+		assert_eq!(ruleset.advance("mind", &data).unwrap().cost, 15);
+		assert!(ruleset.advance("concept", &data).is_err());
+
+		let mut maxed = data.clone();
+		maxed.skills.insert(String::from("hacking"), 6);
+		assert!(ruleset.advance("hacking", &maxed).is_err());
+	}
+
+	#[test]
+	fn raised_lists_higher_skills_and_integer_attributes() {
+		let ruleset = Ruleset::parse(DEFAULT_RULESET, true).unwrap();
+		let mut data = SheetData::default();
+		data.attributes.insert(String::from("archetype"), json!("hakkeri"));
+		data.skills.insert(String::from("hacking"), 2);
+		let old = ruleset.validate(data).unwrap();
+
+		let mut new = old.clone();
+		new.skills.insert(String::from("hacking"), 3);
+		new.skills.insert(String::from("melee"), 0);
+		new.attributes.insert(String::from("mind"), json!(3));
+		new.attributes.insert(String::from("body"), json!(1));
+		new.attributes.insert(String::from("archetype"), json!("katusamurai"));
+		assert_eq!(ruleset.raised(&old, &new), vec!["hacking", "mind"]);
+		assert_eq!(ruleset.raised(&new, &old), vec!["body"]);
+	}
+
+	#[test]
+	fn rulesets_with_unknown_formula_values_are_rejected() {
+		let json = r#"{
+			"name": "test",
+			"version": 1,
+			"attributes": [{ "key": "body", "name": "Body", "type": "integer" }],
+			"derived": [{ "key": "health", "name": "Health", "formula": "body + luck" }]
+		}"#;
+		assert_eq!(
+			Ruleset::parse(json, false).unwrap_err(),
+			"formula of health uses unknown value luck"
+		);
+	}
+}
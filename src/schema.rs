@@ -6,6 +6,42 @@ table! {
         description -> Varchar,
         created_at -> Timestamp,
         updated_by -> Varchar,
+        updated_at -> Timestamp,
+        slug -> Varchar,
+        status -> Varchar,
+        died_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    character_profiles (id) {
+        id -> Uuid,
+        character_id -> Uuid,
+        portrait_url -> Varchar,
+        bio -> Varchar,
+        affiliations -> Array<Text>,
+        gm_notes -> Varchar,
+        portrait_visibility -> Varchar,
+        bio_visibility -> Varchar,
+        affiliations_visibility -> Varchar,
+        sheet_visibility -> Varchar,
+        gm_notes_visibility -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    character_sheets (id) {
+        id -> Uuid,
+        character_id -> Uuid,
+        ruleset -> Varchar,
+        ruleset_version -> Int4,
+        data -> Jsonb,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
     }
 }
 
@@ -56,6 +92,7 @@ table! {
         username -> Varchar,
         hash -> Varchar,
         created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -69,6 +106,139 @@ table! {
         body -> Varchar,
         created_at -> Timestamp,
         updated_by -> Varchar,
+        updated_at -> Timestamp,
+        comments_locked -> Bool,
+        slug -> Varchar,
+        in_world_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    slug_history (id) {
+        id -> Uuid,
+        content_type -> Varchar,
+        content_id -> Uuid,
+        slug -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    comments (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        parent_id -> Nullable<Uuid>,
+        user_id -> Uuid,
+        character_id -> Nullable<Uuid>,
+        body -> Varchar,
+        hidden -> Bool,
+        locked -> Bool,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    comment_revisions (id) {
+        id -> Uuid,
+        comment_id -> Uuid,
+        body -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    article_authors (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        character_id -> Uuid,
+        user_id -> Uuid,
+        position -> Int2,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    series (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        title -> Varchar,
+        description -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    series_articles (id) {
+        id -> Uuid,
+        series_id -> Uuid,
+        article_id -> Uuid,
+        position -> Int2,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    mentions (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        character_id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    notifications (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        kind -> Varchar,
+        article_id -> Nullable<Uuid>,
+        character_id -> Nullable<Uuid>,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    notification_opt_outs (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        kind -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    article_reactions (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        user_id -> Uuid,
+        character_id -> Nullable<Uuid>,
+        reaction -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    favorites (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
     }
 }
 
@@ -78,6 +248,31 @@ table! {
         title -> Varchar,
         created_at -> Timestamp,
         updated_by -> Varchar,
+        updated_at -> Timestamp,
+        parent_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    tag_aliases (id) {
+        id -> Uuid,
+        tag_id -> Uuid,
+        alias -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+    }
+}
+
+table! {
+    content_links (id) {
+        id -> Uuid,
+        article_id -> Uuid,
+        link_text -> Varchar,
+        target_type -> Nullable<Varchar>,
+        target_slug -> Varchar,
+        target_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
     }
 }
 
@@ -85,6 +280,7 @@ table! {
     contenttags (id) {
         id -> Uuid,
         tag_id -> Uuid,
+        content_type -> Varchar,
         content_id -> Uuid,
         created_at -> Timestamp,
         updated_by -> Varchar,
@@ -92,29 +288,206 @@ table! {
 }
 
 table! {
-    rich_contenttags (idx) {
-        idx -> Int4,
+    rich_contenttags (contenttag_id) {
         contenttag_id -> Uuid,
         tag_id -> Uuid,
+        content_type -> Varchar,
         content_id -> Uuid,
         tag_title -> Varchar,
     }
 }
 
+table! {
+    rolls (id) {
+        id -> Uuid,
+        campaign -> Varchar,
+        sequence -> Int8,
+        user_id -> Uuid,
+        character_id -> Uuid,
+        character_name -> Varchar,
+        expression -> Varchar,
+        seed -> Int8,
+        result -> Jsonb,
+        total -> Int8,
+        reason -> Text,
+        prev_hash -> Varchar,
+        hash -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    experience (id) {
+        id -> Uuid,
+        character_id -> Uuid,
+        user_id -> Uuid,
+        kind -> Varchar,
+        amount -> Int4,
+        reason -> Text,
+        advanced_key -> Nullable<Varchar>,
+        advanced_from -> Nullable<Int4>,
+        advanced_to -> Nullable<Int4>,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    items (id) {
+        id -> Uuid,
+        name -> Varchar,
+        category -> Varchar,
+        description -> Text,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    inventory_items (id) {
+        id -> Uuid,
+        character_id -> Uuid,
+        item_id -> Uuid,
+        quantity -> Int4,
+        equipped -> Bool,
+        notes -> Text,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    item_transfers (id) {
+        id -> Uuid,
+        item_id -> Uuid,
+        item_name -> Varchar,
+        from_character_id -> Uuid,
+        to_character_id -> Uuid,
+        quantity -> Int4,
+        note -> Text,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    currency_accounts (id) {
+        id -> Uuid,
+        kind -> Varchar,
+        character_id -> Nullable<Uuid>,
+        name -> Varchar,
+        credit_limit -> Int8,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    currency_transactions (id) {
+        id -> Uuid,
+        kind -> Varchar,
+        payer_account_id -> Uuid,
+        payee_account_id -> Uuid,
+        amount -> Int8,
+        memo -> Text,
+        in_world_at -> Nullable<Timestamp>,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    currency_entries (id) {
+        id -> Uuid,
+        transaction_id -> Uuid,
+        account_id -> Uuid,
+        amount -> Int8,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    relationships (id) {
+        id -> Uuid,
+        from_character_id -> Uuid,
+        to_character_id -> Uuid,
+        kind -> Varchar,
+        directed -> Bool,
+        notes -> Varchar,
+        visibility -> Varchar,
+        created_at -> Timestamp,
+        updated_by -> Varchar,
+        updated_at -> Timestamp,
+    }
+}
+
 joinable!(articles -> characters (character_id));
+joinable!(character_profiles -> characters (character_id));
+joinable!(character_sheets -> characters (character_id));
 joinable!(characters -> users (user_id));
-joinable!(contenttags -> articles (content_id));
+joinable!(comment_revisions -> comments (comment_id));
+joinable!(content_links -> articles (article_id));
+joinable!(comments -> articles (article_id));
+joinable!(currency_entries -> currency_accounts (account_id));
+joinable!(currency_entries -> currency_transactions (transaction_id));
+joinable!(experience -> characters (character_id));
+joinable!(article_authors -> articles (article_id));
+joinable!(article_authors -> characters (character_id));
+joinable!(article_reactions -> articles (article_id));
+joinable!(favorites -> articles (article_id));
+joinable!(series_articles -> articles (article_id));
+joinable!(series_articles -> series (series_id));
 joinable!(contenttags -> tags (tag_id));
+joinable!(tag_aliases -> tags (tag_id));
+joinable!(inventory_items -> characters (character_id));
+joinable!(inventory_items -> items (item_id));
 joinable!(invitations -> reset_requests (reset_request_id));
+joinable!(mentions -> articles (article_id));
+joinable!(mentions -> characters (character_id));
 joinable!(sessions -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    article_authors,
+    article_reactions,
     articles,
+    character_profiles,
+    character_sheets,
     characters,
+    comment_revisions,
+    comments,
+    content_links,
     contenttags,
+    currency_accounts,
+    currency_entries,
+    currency_transactions,
+    experience,
+    favorites,
+    inventory_items,
     invitations,
+    item_transfers,
+    items,
+    mentions,
+    notification_opt_outs,
+    notifications,
+    relationships,
     reset_requests,
+    rolls,
+    series,
+    series_articles,
     sessions,
+    slug_history,
+    tag_aliases,
     tags,
     users,
 );
@@ -6,3 +6,17 @@ pub mod reset_requests_storage;
 pub mod characters_storage;
 pub mod articles_storage;
 pub mod tags_storage;
+pub mod search_storage;
+pub mod comments_storage;
+pub mod reactions_storage;
+pub mod slugs_storage;
+pub mod series_storage;
+pub mod links_storage;
+pub mod notifications_storage;
+pub mod sheets_storage;
+pub mod rolls_storage;
+pub mod experience_storage;
+pub mod items_storage;
+pub mod currency_storage;
+pub mod profiles_storage;
+pub mod relationships_storage;
\ No newline at end of file
@@ -1,98 +1,321 @@
 use actix_web::web;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::Error::NotFound;
 use diesel::PgConnection;
+use std::collections::HashMap;
 
-use crate::models::articles::Article;
+use crate::models::articles::{Article, ArticleAuthor, ArticleChanges, Byline};
+use crate::models::listing::{ListParams, Page, SortDirection, SortField};
+use crate::models::slugs::{Resolved, SlugOwner};
+use crate::models::tags::ContentType;
 use crate::models::users::Pool;
+use crate::schema::{articles, contenttags};
+use crate::storage::{links_storage, notifications_storage, slugs_storage};
+use crate::utils::slugify;
 use diesel::result::Error;
 
 pub fn create_article(
 	q_user_id: uuid::Uuid,
-	q_title: String,
-	q_ingress: String,
-	q_body: String,
-	q_character_id: uuid::Uuid,
-	q_email: String,
+	changes: ArticleChanges,
 	pool: &web::Data<Pool>,
 ) -> Result<Article, Error> {
 	use crate::schema::articles::dsl::articles;
 	let conn: &PgConnection = &pool.get().unwrap();
 
+	let q_id = uuid::Uuid::new_v4();
 	let new_article = Article {
-		id: uuid::Uuid::new_v4(),
+		id: q_id,
+		character_id: changes.character_id,
 		user_id: q_user_id,
-		character_id: q_character_id,
-		title: q_title,
-		ingress: q_ingress,
-		body: q_body,
+		slug: slugs_storage::unique_slug(&changes.title, SlugOwner::Article, q_id, conn)?,
+		title: changes.title,
+		ingress: changes.ingress,
+		body: changes.body,
 		created_at: chrono::Local::now().naive_local(),
-		updated_by: q_email,
+		updated_by: changes.updated_by,
+		updated_at: chrono::Local::now().naive_local(),
+		comments_locked: false,
+		in_world_at: changes.in_world_at,
 	};
 
-	let article = diesel::insert_into(articles)
-		.values(&new_article)
-		.get_result::<Article>(conn)?;
+	conn.transaction::<_, Error, _>(|| {
+		let article = diesel::insert_into(articles)
+			.values(&new_article)
+			.get_result::<Article>(conn)?;
+		insert_bylines(article.id, &[article.character_id], &article.updated_by, conn)?;
+		links_storage::store_links(article.id, &article.body, &article.updated_by, conn)?;
+		notifications_storage::store_mentions(article.id, &article.body, &article.updated_by, conn)?;
+		links_storage::resolve_broken_links(SlugOwner::Article, article.id, &article.slug, conn)?;
 
-	Ok(article)
+		Ok(article)
+	})
 }
 
-pub fn query_articles(
-	pool: &web::Data<Pool>,
-) -> Result<Vec<Article>, Error> {
-	use crate::schema::articles::dsl::{articles};
+// Bylines in the given order, each owned by the owner of its character
+fn insert_bylines(
+	q_article_id: uuid::Uuid,
+	q_character_ids: &[uuid::Uuid],
+	q_email: &str,
+	conn: &PgConnection,
+) -> Result<Vec<ArticleAuthor>, Error> {
+	use crate::schema::article_authors::dsl::article_authors;
+	use crate::schema::characters::dsl as ch;
+
+	let owners: HashMap<uuid::Uuid, uuid::Uuid> = ch::characters
+		.select((ch::id, ch::user_id))
+		.filter(ch::id.eq_any(q_character_ids))
+		.load::<(uuid::Uuid, uuid::Uuid)>(conn)?
+		.into_iter()
+		.collect();
+
+	let new_authors = q_character_ids
+		.iter()
+		.enumerate()
+		.map(|(position, q_character_id)| {
+			Ok(ArticleAuthor {
+				id: uuid::Uuid::new_v4(),
+				article_id: q_article_id,
+				character_id: *q_character_id,
+				user_id: *owners.get(q_character_id).ok_or(NotFound)?,
+				position: position as i16,
+				created_at: chrono::Local::now().naive_local(),
+				updated_by: q_email.to_string(),
+			})
+		})
+		.collect::<Result<Vec<ArticleAuthor>, Error>>()?;
+
+	diesel::insert_into(article_authors)
+		.values(&new_authors)
+		.get_results::<ArticleAuthor>(conn)
+}
+
+pub fn query_article_authors(q_article_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<ArticleAuthor>, Error> {
+	use crate::schema::article_authors::dsl::*;
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let articles_res = articles
-		.load::<Article>(conn)?;
+	let authors = article_authors
+		.filter(article_id.eq(q_article_id))
+		.order(position.asc())
+		.load::<ArticleAuthor>(conn)?;
+
+	Ok(authors)
+}
+
+pub fn query_bylines(q_article_ids: Vec<uuid::Uuid>, pool: &web::Data<Pool>) -> Result<Vec<Byline>, Error> {
+	use crate::schema::article_authors::dsl as aa;
+	use crate::schema::characters::dsl as ch;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let bylines = aa::article_authors
+		.inner_join(ch::characters)
+		.select((aa::article_id, aa::character_id, ch::name, ch::slug, ch::status, aa::user_id, aa::position))
+		.filter(aa::article_id.eq_any(q_article_ids))
+		.order((aa::article_id.asc(), aa::position.asc()))
+		.load::<Byline>(conn)?;
+
+	Ok(bylines)
+}
 
-	Ok(articles_res)
+pub fn is_article_author(q_article_id: uuid::Uuid, q_user_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<bool, Error> {
+	use crate::schema::article_authors::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let count = article_authors
+		.filter(article_id.eq(q_article_id))
+		.filter(user_id.eq(q_user_id))
+		.count()
+		.get_result::<i64>(conn)?;
+
+	Ok(count > 0)
 }
 
-pub fn query_articles_by_article_uuid(
+// Replaces the bylines; the first one becomes the article's lead character and user
+pub fn set_article_authors(
 	q_article_id: uuid::Uuid,
+	q_character_ids: Vec<uuid::Uuid>,
+	q_email: String,
 	pool: &web::Data<Pool>,
-) -> Result<Vec<Article>, Error> {
-	use crate::schema::articles::dsl::{id, articles};
+) -> Result<Vec<ArticleAuthor>, Error> {
+	use crate::schema::article_authors::dsl as aa;
+	use crate::schema::articles::dsl::{articles, character_id, id, updated_by, user_id};
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let articles_res = articles
-		.filter(id.eq(&q_article_id))
-		.load::<Article>(conn)?;
+	conn.transaction::<_, Error, _>(|| {
+		articles.filter(id.eq(q_article_id)).for_update().get_result::<Article>(conn)?;
+
+		diesel::delete(aa::article_authors.filter(aa::article_id.eq(q_article_id))).execute(conn)?;
+		let authors = insert_bylines(q_article_id, &q_character_ids, &q_email, conn)?;
+		let lead = authors.first().ok_or(NotFound)?;
 
-	Ok(articles_res)
+		diesel::update(articles)
+			.filter(id.eq(q_article_id))
+			.set((
+				character_id.eq(lead.character_id),
+				user_id.eq(lead.user_id),
+				updated_by.eq(q_email.clone()),
+			))
+			.execute(conn)?;
+
+		Ok(authors)
+	})
+}
+
+// favorited_by limits the articles to those bookmarked by the given user
+fn filtered_articles(params: &ListParams, favorited_by: Option<uuid::Uuid>) -> articles::BoxedQuery<'static, Pg> {
+	use crate::schema::articles::dsl::*;
+
+	let mut query = articles.into_boxed();
+	if let Some(q_user_id) = favorited_by {
+		use crate::schema::favorites::dsl as fav;
+		query = query.filter(id.eq_any(fav::favorites.select(fav::article_id).filter(fav::user_id.eq(q_user_id))));
+	}
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+	}
+	if let Some(from) = params.updated_from {
+		query = query.filter(updated_at.ge(from));
+	}
+	if let Some(to) = params.updated_to {
+		query = query.filter(updated_at.lt(to));
+	}
+	if let Some(from) = params.in_world_from {
+		query = query.filter(in_world_at.ge(from));
+	}
+	if let Some(to) = params.in_world_to {
+		query = query.filter(in_world_at.lt(to));
+	}
+	// Co-authored articles belong to every author
+	if let Some(q_user_id) = params.user_id {
+		use crate::schema::article_authors::dsl as aa;
+		query = query.filter(id.eq_any(aa::article_authors.select(aa::article_id).filter(aa::user_id.eq(q_user_id))));
+	}
+	if let Some(q_character_id) = params.character_id {
+		use crate::schema::article_authors::dsl as aa;
+		query = query.filter(
+			id.eq_any(aa::article_authors.select(aa::article_id).filter(aa::character_id.eq(q_character_id))),
+		);
+	}
+	if let Some(ref q_tag_ids) = params.tags_all {
+		for q_tag_id in q_tag_ids {
+			query = query.filter(id.eq_any(tagged_with(vec![*q_tag_id])));
+		}
+	}
+	if let Some(ref q_tag_ids) = params.tags_any {
+		query = query.filter(id.eq_any(tagged_with(q_tag_ids.clone())));
+	}
+	if let Some(ref q_tag_ids) = params.tags_none {
+		query = query.filter(diesel::dsl::not(id.eq_any(tagged_with(q_tag_ids.clone()))));
+	}
+	query
 }
 
-pub fn query_articles_by_user_uuid(
+type TaggedWith = diesel::dsl::Filter<
+	diesel::dsl::Filter<
+		diesel::dsl::Select<contenttags::table, contenttags::content_id>,
+		diesel::dsl::Eq<contenttags::content_type, &'static str>,
+	>,
+	diesel::dsl::EqAny<contenttags::tag_id, Vec<uuid::Uuid>>,
+>;
+
+// Ids of the articles tagged with any of the given tags
+fn tagged_with(q_tag_ids: Vec<uuid::Uuid>) -> TaggedWith {
+	use crate::schema::contenttags::dsl::{content_id, content_type, contenttags, tag_id};
+
+	contenttags
+		.select(content_id)
+		.filter(content_type.eq(ContentType::Article.as_str()))
+		.filter(tag_id.eq_any(q_tag_ids))
+}
+
+pub fn query_articles(
+	params: &ListParams,
+	pool: &web::Data<Pool>,
+) -> Result<Page<Article>, Error> {
+	load_articles(params, None, pool)
+}
+
+pub fn query_favorite_articles(
 	q_user_id: uuid::Uuid,
+	params: &ListParams,
+	pool: &web::Data<Pool>,
+) -> Result<Page<Article>, Error> {
+	load_articles(params, Some(q_user_id), pool)
+}
+
+fn load_articles(
+	params: &ListParams,
+	favorited_by: Option<uuid::Uuid>,
 	pool: &web::Data<Pool>,
-) -> Result<Vec<Article>, Error> {
-	use crate::schema::articles::dsl::{user_id, articles};
+) -> Result<Page<Article>, Error> {
+	use crate::schema::articles::dsl::*;
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let articles_res = articles
-		.filter(user_id.eq(&q_user_id))
+	let total = filtered_articles(params, favorited_by).count().get_result::<i64>(conn)?;
+
+	let query = filtered_articles(params, favorited_by);
+	let query = match params.sort_or(SortField::CreatedAt, SortDirection::Desc) {
+		(SortField::CreatedAt, SortDirection::Asc) => query.order(created_at.asc()),
+		(SortField::CreatedAt, SortDirection::Desc) => query.order(created_at.desc()),
+		(SortField::UpdatedAt, SortDirection::Asc) => query.order(updated_at.asc()),
+		(SortField::UpdatedAt, SortDirection::Desc) => query.order(updated_at.desc()),
+		(SortField::Title, SortDirection::Asc) => query.order(title.asc()),
+		(SortField::Title, SortDirection::Desc) => query.order(title.desc()),
+		// Articles without an in-world date come last either way
+		(SortField::InWorldAt, SortDirection::Asc) => query.order(in_world_at.asc().nulls_last()),
+		(SortField::InWorldAt, SortDirection::Desc) => query.order(in_world_at.desc().nulls_last()),
+	};
+
+	let articles_res = query
+		.then_order_by(id.asc())
+		.limit(params.limit())
+		.offset(params.offset())
 		.load::<Article>(conn)?;
 
-	Ok(articles_res)
+	Ok(Page {
+		items: articles_res,
+		total,
+	})
+}
+
+pub fn get_article(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Article, Error> {
+	use crate::schema::articles::dsl::{articles, id};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let article = articles.filter(id.eq(q_id)).get_result::<Article>(conn)?;
+
+	Ok(article)
+}
+
+// Looks an article up by UUID, current slug or earlier slug
+pub fn resolve_article(q_key: String, pool: &web::Data<Pool>) -> Result<Resolved<Article>, Error> {
+	use crate::schema::articles::dsl::{articles, id, slug};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	if let Ok(q_id) = uuid::Uuid::parse_str(&q_key) {
+		return Ok(Resolved::Current(articles.filter(id.eq(q_id)).get_result::<Article>(conn)?));
+	}
+	if let Some(article) = articles.filter(slug.eq(&q_key)).get_result::<Article>(conn).optional()? {
+		return Ok(Resolved::Current(article));
+	}
+
+	let old = slugs_storage::query_slug_history(SlugOwner::Article, &q_key, conn)?;
+	let article = articles.filter(id.eq(old.content_id)).get_result::<Article>(conn)?;
+	Ok(Resolved::Renamed(article.slug))
 }
 
-/*
 pub fn query_articles_by_tag_uuid(
 	q_tag_id: uuid::Uuid,
+	params: &mut ListParams,
 	pool: &web::Data<Pool>,
-) -> Result<Vec<Article>, Error> {
-	use crate::schema::contenttags::dsl::{id, tag_id, contenttags};
-	use crate::schema::articles::dsl::{id as article_id, articles};
-	let conn: &PgConnection = &pool.get().unwrap();
-
-	let articles_res = articles
-		.filter(tag_id.eq(&q_tag_id))
-		.load::<Article>(conn)?;
+) -> Result<Page<Article>, Error> {
+	params.tags_all.get_or_insert_with(Vec::new).push(q_tag_id);
 
-	Ok(articles_res)
+	query_articles(params, pool)
 }
-*/
 
 pub fn delete_article(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
 	let conn: &PgConnection = &pool.get().unwrap();
@@ -106,12 +329,73 @@ pub fn delete_article(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Er
 	Err(NotFound)
 }
 
-pub fn update_article(
+// The article's user follows from its lead byline
+pub fn update_article(q_uuid_path: uuid::Uuid, changes: ArticleChanges, pool: &web::Data<Pool>) -> Result<Article, Error> {
+	use crate::schema::articles::dsl::*;
+	use crate::schema::articles::dsl::{id, updated_by};
+	let conn: &PgConnection = &pool.get().unwrap();
+	let ArticleChanges {
+		character_id: q_character_id,
+		title: q_title,
+		ingress: q_ingress,
+		body: q_body,
+		in_world_at: q_in_world_at,
+		updated_by: q_email,
+	} = changes;
+
+	conn.transaction::<_, Error, _>(|| {
+		let old = articles.filter(id.eq(q_uuid_path)).for_update().get_result::<Article>(conn)?;
+
+		// The slug only follows the title when the title changes in a way that shows in it
+		let new_slug = if slugify(&q_title) == slugify(&old.title) {
+			old.slug
+		} else {
+			let new_slug = slugs_storage::unique_slug(&q_title, SlugOwner::Article, old.id, conn)?;
+			slugs_storage::record_slug_change(SlugOwner::Article, old.id, old.slug, &new_slug, q_email.clone(), conn)?;
+			links_storage::resolve_broken_links(SlugOwner::Article, old.id, &new_slug, conn)?;
+			new_slug
+		};
+
+		// Choosing another co-author's character as the article's character makes it the lead byline
+		let lead_user_id = if q_character_id == old.character_id {
+			old.user_id
+		} else {
+			use crate::schema::article_authors::dsl as aa;
+			let mut order = aa::article_authors
+				.select(aa::character_id)
+				.filter(aa::article_id.eq(q_uuid_path))
+				.order(aa::position.asc())
+				.load::<uuid::Uuid>(conn)?;
+			order.retain(|q_id| *q_id != q_character_id);
+			order.insert(0, q_character_id);
+
+			diesel::delete(aa::article_authors.filter(aa::article_id.eq(q_uuid_path))).execute(conn)?;
+			insert_bylines(q_uuid_path, &order, &q_email, conn)?[0].user_id
+		};
+
+		let article = diesel::update(articles)
+			.filter(id.eq(q_uuid_path))
+			.set((
+				character_id.eq(q_character_id),
+				user_id.eq(lead_user_id),
+				title.eq(q_title),
+				ingress.eq(q_ingress),
+				body.eq(q_body),
+				slug.eq(new_slug),
+				in_world_at.eq(q_in_world_at.or(old.in_world_at)),
+				updated_by.eq(q_email),
+			))
+			.get_result::<Article>(conn)?;
+		links_storage::store_links(article.id, &article.body, &article.updated_by, conn)?;
+		notifications_storage::store_mentions(article.id, &article.body, &article.updated_by, conn)?;
+
+		Ok(article)
+	})
+}
+
+pub fn set_comments_locked(
 	q_uuid_path: uuid::Uuid,
-	q_title: String,
-	q_ingress: String,
-	q_body: String,
-	q_character_id: uuid::Uuid,
+	q_locked: bool,
 	q_email: String,
 	pool: &web::Data<Pool>,
 ) -> Result<Article, Error> {
@@ -119,16 +403,10 @@ pub fn update_article(
 	use crate::schema::articles::dsl::{id, updated_by};
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let user_article = diesel::update(articles)
+	let article = diesel::update(articles)
 		.filter(id.eq(q_uuid_path))
-		.set((
-			character_id.eq(q_character_id),
-			title.eq(q_title),
-			ingress.eq(q_ingress),
-			body.eq(q_body),
-			updated_by.eq(q_email),
-		))
+		.set((comments_locked.eq(q_locked), updated_by.eq(q_email)))
 		.get_result::<Article>(conn)?;
 
-	Ok(user_article)
+	Ok(article)
 }
\ No newline at end of file
@@ -1,10 +1,16 @@
 use actix_web::web;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::Error::NotFound;
 use diesel::PgConnection;
 
-use crate::models::characters::Character;
+use crate::models::characters::{Character, CharacterStatus};
+use crate::models::listing::{ListParams, Page, SortDirection, SortField};
+use crate::models::slugs::{Resolved, SlugOwner};
 use crate::models::users::Pool;
+use crate::schema::characters;
+use crate::storage::{links_storage, slugs_storage};
+use crate::utils::slugify;
 use diesel::result::Error;
 
 pub fn create_character(
@@ -17,48 +23,129 @@ pub fn create_character(
 	use crate::schema::characters::dsl::characters;
 	let conn: &PgConnection = &pool.get().unwrap();
 
+	let new_id = uuid::Uuid::new_v4();
+	let new_slug = slugs_storage::unique_slug(&q_name, SlugOwner::Character, new_id, conn)?;
 	let new_character = Character {
-		id: uuid::Uuid::new_v4(),
+		id: new_id,
 		user_id: q_user_id,
 		name: q_name,
 		description: q_description,
 		created_at: chrono::Local::now().naive_local(),
 		updated_by: q_email,
+		updated_at: chrono::Local::now().naive_local(),
+		slug: new_slug,
+		status: CharacterStatus::Active.as_str().to_string(),
+		died_at: None,
 	};
 
-	let character = diesel::insert_into(characters)
-		.values(&new_character)
-		.get_result::<Character>(conn)?;
+	conn.transaction::<_, Error, _>(|| {
+		let character = diesel::insert_into(characters)
+			.values(&new_character)
+			.get_result::<Character>(conn)?;
+		links_storage::resolve_broken_links(SlugOwner::Character, character.id, &character.slug, conn)?;
+
+		Ok(character)
+	})
+}
+
+pub fn get_character(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Character, Error> {
+	use crate::schema::characters::dsl::{characters, id};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let character = characters.filter(id.eq(q_id)).get_result::<Character>(conn)?;
 
 	Ok(character)
 }
 
-pub fn query_characters_by_character_uuid(
-	q_user_id: uuid::Uuid,
-	pool: &web::Data<Pool>,
-) -> Result<Vec<Character>, Error> {
-	use crate::schema::characters::dsl::{user_id, characters};
+// The characters that exist of the given, in no particular order
+pub fn get_characters(q_ids: &[uuid::Uuid], pool: &web::Data<Pool>) -> Result<Vec<Character>, Error> {
+	use crate::schema::characters::dsl::{characters, id};
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let characters_res = characters
-		.filter(user_id.eq(&q_user_id))
-		.load::<Character>(conn)?;
+	let found = characters.filter(id.eq_any(q_ids)).load::<Character>(conn)?;
+
+	Ok(found)
+}
+
+// Looks a character up by UUID, current slug or earlier slug
+pub fn resolve_character(q_key: String, pool: &web::Data<Pool>) -> Result<Resolved<Character>, Error> {
+	use crate::schema::characters::dsl::{characters, id, slug};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	if let Ok(q_id) = uuid::Uuid::parse_str(&q_key) {
+		return Ok(Resolved::Current(characters.filter(id.eq(q_id)).get_result::<Character>(conn)?));
+	}
+	if let Some(character) = characters.filter(slug.eq(&q_key)).get_result::<Character>(conn).optional()? {
+		return Ok(Resolved::Current(character));
+	}
+
+	let old = slugs_storage::query_slug_history(SlugOwner::Character, &q_key, conn)?;
+	let character = characters.filter(id.eq(old.content_id)).get_result::<Character>(conn)?;
+	Ok(Resolved::Renamed(character.slug))
+}
+
+fn filtered_characters(q_status: Option<CharacterStatus>, params: &ListParams) -> characters::BoxedQuery<'static, Pg> {
+	use crate::schema::characters::dsl::*;
 
-	Ok(characters_res)
+	let mut query = characters.into_boxed();
+	if let Some(q_status) = q_status {
+		query = query.filter(status.eq(q_status.as_str()));
+	}
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+	}
+	if let Some(from) = params.updated_from {
+		query = query.filter(updated_at.ge(from));
+	}
+	if let Some(to) = params.updated_to {
+		query = query.filter(updated_at.lt(to));
+	}
+	if let Some(q_user_id) = params.user_id {
+		query = query.filter(user_id.eq(q_user_id));
+	}
+	if let Some(q_character_id) = params.character_id {
+		query = query.filter(id.eq(q_character_id));
+	}
+	query
 }
 
 pub fn query_characters_by_user_uuid(
 	q_user_id: uuid::Uuid,
+	q_status: Option<CharacterStatus>,
+	params: &ListParams,
 	pool: &web::Data<Pool>,
-) -> Result<Vec<Character>, Error> {
-	use crate::schema::characters::dsl::{user_id, characters};
+) -> Result<Page<Character>, Error> {
+	use crate::schema::characters::dsl::*;
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let characters_res = characters
+	let total = filtered_characters(q_status, params)
 		.filter(user_id.eq(&q_user_id))
+		.count()
+		.get_result::<i64>(conn)?;
+
+	let query = filtered_characters(q_status, params).filter(user_id.eq(&q_user_id));
+	let query = match params.sort_or(SortField::Title, SortDirection::Asc) {
+		(SortField::CreatedAt, SortDirection::Asc) | (SortField::InWorldAt, SortDirection::Asc) => query.order(created_at.asc()),
+		(SortField::CreatedAt, SortDirection::Desc) | (SortField::InWorldAt, SortDirection::Desc) => query.order(created_at.desc()),
+		(SortField::UpdatedAt, SortDirection::Asc) => query.order(updated_at.asc()),
+		(SortField::UpdatedAt, SortDirection::Desc) => query.order(updated_at.desc()),
+		(SortField::Title, SortDirection::Asc) => query.order(name.asc()),
+		(SortField::Title, SortDirection::Desc) => query.order(name.desc()),
+	};
+
+	let characters_res = query
+		.then_order_by(id.asc())
+		.limit(params.limit())
+		.offset(params.offset())
 		.load::<Character>(conn)?;
 
-	Ok(characters_res)
+	Ok(Page {
+		items: characters_res,
+		total,
+	})
 }
 
 pub fn delete_character(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
@@ -84,14 +171,68 @@ pub fn update_character(
 	use crate::schema::characters::dsl::{id, updated_by};
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let user_character = diesel::update(characters)
-		.filter(id.eq(q_uuid_path))
-		.set((
-			name.eq(q_name),
-			description.eq(q_description),
-			updated_by.eq(q_email),
-		))
-		.get_result::<Character>(conn)?;
-
-	Ok(user_character)
-}
\ No newline at end of file
+	conn.transaction::<_, Error, _>(|| {
+		let old = characters.filter(id.eq(q_uuid_path)).for_update().get_result::<Character>(conn)?;
+
+		let new_slug = if slugify(&q_name) == slugify(&old.name) {
+			old.slug
+		} else {
+			let new_slug = slugs_storage::unique_slug(&q_name, SlugOwner::Character, old.id, conn)?;
+			slugs_storage::record_slug_change(SlugOwner::Character, old.id, old.slug, &new_slug, q_email.clone(), conn)?;
+			links_storage::resolve_broken_links(SlugOwner::Character, old.id, &new_slug, conn)?;
+			new_slug
+		};
+
+		diesel::update(characters)
+			.filter(id.eq(q_uuid_path))
+			.set((
+				name.eq(q_name),
+				description.eq(q_description),
+				slug.eq(new_slug),
+				updated_by.eq(q_email),
+			))
+			.get_result::<Character>(conn)
+	})
+}
+
+// Moves the character to another status and owner. The character must still
+// have the status the change was worked out from, otherwise NotFound. The
+// owner copied to its bylines, lead articles and mentions moves along.
+pub fn update_status(
+	q_id: uuid::Uuid,
+	q_from: String,
+	q_status: CharacterStatus,
+	q_died_at: Option<chrono::NaiveDateTime>,
+	q_user_id: uuid::Uuid,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Character, Error> {
+	use crate::schema::article_authors::dsl as aa;
+	use crate::schema::articles::dsl as ar;
+	use crate::schema::characters::dsl::*;
+	use crate::schema::mentions::dsl as me;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let character = diesel::update(characters.filter(id.eq(q_id)).filter(status.eq(q_from)))
+			.set((
+				status.eq(q_status.as_str()),
+				died_at.eq(q_died_at),
+				user_id.eq(q_user_id),
+				updated_by.eq(q_email),
+			))
+			.get_result::<Character>(conn)?;
+
+		diesel::update(aa::article_authors.filter(aa::character_id.eq(q_id)).filter(aa::user_id.ne(q_user_id)))
+			.set(aa::user_id.eq(q_user_id))
+			.execute(conn)?;
+		diesel::update(ar::articles.filter(ar::character_id.eq(q_id)).filter(ar::user_id.ne(q_user_id)))
+			.set(ar::user_id.eq(q_user_id))
+			.execute(conn)?;
+		diesel::update(me::mentions.filter(me::character_id.eq(q_id)).filter(me::user_id.ne(q_user_id)))
+			.set(me::user_id.eq(q_user_id))
+			.execute(conn)?;
+
+		Ok(character)
+	})
+}
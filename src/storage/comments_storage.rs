@@ -0,0 +1,147 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use crate::models::comments::{Comment, CommentRevision};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+pub fn query_comments_by_article_uuid(q_article_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<Comment>, Error> {
+	use crate::schema::comments::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let items = comments
+		.filter(article_id.eq(q_article_id))
+		.order((created_at.asc(), id.asc()))
+		.load::<Comment>(conn)?;
+
+	Ok(items)
+}
+
+pub fn get_comment(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Comment, Error> {
+	use crate::schema::comments::dsl::{comments, id};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let comment = comments.filter(id.eq(q_id)).get_result::<Comment>(conn)?;
+
+	Ok(comment)
+}
+
+pub fn create_comment(
+	q_article_id: uuid::Uuid,
+	q_parent_id: Option<uuid::Uuid>,
+	q_user_id: uuid::Uuid,
+	q_character_id: Option<uuid::Uuid>,
+	q_body: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Comment, Error> {
+	use crate::schema::comments::dsl::comments;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_comment = Comment {
+		id: uuid::Uuid::new_v4(),
+		article_id: q_article_id,
+		parent_id: q_parent_id,
+		user_id: q_user_id,
+		character_id: q_character_id,
+		body: q_body,
+		hidden: false,
+		locked: false,
+		deleted_at: None,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	let comment = diesel::insert_into(comments)
+		.values(&new_comment)
+		.get_result::<Comment>(conn)?;
+
+	Ok(comment)
+}
+
+// Stores the current text as a revision before replacing it
+pub fn update_comment(
+	q_id: uuid::Uuid,
+	q_body: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Comment, Error> {
+	use crate::schema::comment_revisions::dsl::comment_revisions;
+	use crate::schema::comments::dsl::{body, comments, id, updated_by};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let old = comments.filter(id.eq(q_id)).for_update().get_result::<Comment>(conn)?;
+
+		let revision = CommentRevision {
+			id: uuid::Uuid::new_v4(),
+			comment_id: old.id,
+			body: old.body,
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: q_email.clone(),
+		};
+		diesel::insert_into(comment_revisions)
+			.values(&revision)
+			.execute(conn)?;
+
+		diesel::update(comments)
+			.filter(id.eq(q_id))
+			.set((body.eq(q_body), updated_by.eq(q_email)))
+			.get_result::<Comment>(conn)
+	})
+}
+
+pub fn query_comment_revisions(q_comment_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<CommentRevision>, Error> {
+	use crate::schema::comment_revisions::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let items = comment_revisions
+		.filter(comment_id.eq(q_comment_id))
+		.order((created_at.asc(), id.asc()))
+		.load::<CommentRevision>(conn)?;
+
+	Ok(items)
+}
+
+// Comments are only marked deleted so that replies keep their place in the thread
+pub fn delete_comment(q_id: uuid::Uuid, q_email: String, pool: &web::Data<Pool>) -> Result<Comment, Error> {
+	use crate::schema::comments::dsl::{comments, deleted_at, id, updated_by};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let comment = diesel::update(comments)
+		.filter(id.eq(q_id))
+		.filter(deleted_at.is_null())
+		.set((
+			deleted_at.eq(chrono::Local::now().naive_local()),
+			updated_by.eq(q_email),
+		))
+		.get_result::<Comment>(conn)?;
+
+	Ok(comment)
+}
+
+pub fn moderate_comment(
+	q_id: uuid::Uuid,
+	q_hidden: Option<bool>,
+	q_locked: Option<bool>,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Comment, Error> {
+	use crate::schema::comments::dsl::{comments, hidden, id, locked, updated_by};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let old = comments.filter(id.eq(q_id)).for_update().get_result::<Comment>(conn)?;
+
+		diesel::update(comments)
+			.filter(id.eq(q_id))
+			.set((
+				hidden.eq(q_hidden.unwrap_or(old.hidden)),
+				locked.eq(q_locked.unwrap_or(old.locked)),
+				updated_by.eq(q_email),
+			))
+			.get_result::<Comment>(conn)
+	})
+}
@@ -0,0 +1,301 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable};
+use diesel::PgConnection;
+use std::collections::HashMap;
+
+use crate::models::currency::{
+	AccountBalance, AccountKind, CurrencyAccount, CurrencyEntry, CurrencyTransaction, LedgerLine, NewAdjustment, NewPayment,
+	TransactionKind,
+};
+use crate::models::listing::{ListParams, Page, SortDirection};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+const TREASURY_NAME: &str = "Treasury";
+
+fn balance_of(q_account_id: uuid::Uuid, conn: &PgConnection) -> Result<i64, Error> {
+	use crate::schema::currency_entries::dsl::*;
+
+	// SUM of a BIGINT is a NUMERIC, balances fit a BIGINT
+	let balance = currency_entries
+		.filter(account_id.eq(q_account_id))
+		.select(diesel::dsl::sql::<Nullable<BigInt>>("CAST(SUM(amount) AS BIGINT)"))
+		.get_result::<Option<i64>>(conn)?;
+
+	Ok(balance.unwrap_or(0))
+}
+
+fn insert_account(
+	q_kind: AccountKind,
+	q_character_id: Option<uuid::Uuid>,
+	q_name: String,
+	q_email: &str,
+	conn: &PgConnection,
+) -> Result<(), Error> {
+	use crate::schema::currency_accounts::dsl::*;
+
+	let new_account = CurrencyAccount {
+		id: uuid::Uuid::new_v4(),
+		kind: q_kind.as_str().to_string(),
+		character_id: q_character_id,
+		name: q_name,
+		credit_limit: 0,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email.to_string(),
+		updated_at: chrono::Local::now().naive_local(),
+	};
+	diesel::insert_into(currency_accounts)
+		.values(&new_account)
+		.on_conflict_do_nothing()
+		.execute(conn)?;
+
+	Ok(())
+}
+
+// The character's account, opened if the character has none yet
+fn open_account(q_character_id: uuid::Uuid, q_email: &str, conn: &PgConnection) -> Result<CurrencyAccount, Error> {
+	use crate::schema::characters::dsl as ch;
+	use crate::schema::currency_accounts::dsl::*;
+
+	let account = currency_accounts
+		.filter(character_id.eq(q_character_id))
+		.get_result::<CurrencyAccount>(conn)
+		.optional()?;
+	if let Some(account) = account {
+		return Ok(account);
+	}
+
+	let character_name = ch::characters
+		.filter(ch::id.eq(q_character_id))
+		.select(ch::name)
+		.get_result::<String>(conn)?;
+	insert_account(AccountKind::Character, Some(q_character_id), character_name, q_email, conn)?;
+	currency_accounts
+		.filter(character_id.eq(q_character_id))
+		.get_result::<CurrencyAccount>(conn)
+}
+
+fn open_treasury(q_email: &str, conn: &PgConnection) -> Result<CurrencyAccount, Error> {
+	use crate::schema::currency_accounts::dsl::*;
+
+	insert_account(AccountKind::Treasury, None, TREASURY_NAME.to_string(), q_email, conn)?;
+	currency_accounts
+		.filter(kind.eq(AccountKind::Treasury.as_str()))
+		.get_result::<CurrencyAccount>(conn)
+}
+
+// Books the transaction and its two entries
+fn record(new_transaction: CurrencyTransaction, conn: &PgConnection) -> Result<CurrencyTransaction, Error> {
+	use crate::schema::currency_entries::dsl::currency_entries;
+	use crate::schema::currency_transactions::dsl::currency_transactions;
+
+	let transaction = diesel::insert_into(currency_transactions)
+		.values(&new_transaction)
+		.get_result::<CurrencyTransaction>(conn)?;
+
+	let new_entries: Vec<CurrencyEntry> = [
+		(transaction.payer_account_id, -transaction.amount),
+		(transaction.payee_account_id, transaction.amount),
+	]
+	.iter()
+	.map(|(q_account_id, q_entry_amount)| CurrencyEntry {
+		id: uuid::Uuid::new_v4(),
+		transaction_id: transaction.id,
+		account_id: *q_account_id,
+		amount: *q_entry_amount,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: transaction.updated_by.clone(),
+		updated_at: chrono::Local::now().naive_local(),
+	})
+	.collect();
+	diesel::insert_into(currency_entries).values(&new_entries).execute(conn)?;
+
+	Ok(transaction)
+}
+
+pub fn get_balance(q_character_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<AccountBalance, Error> {
+	use crate::schema::currency_accounts::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let account = currency_accounts
+		.filter(character_id.eq(q_character_id))
+		.get_result::<CurrencyAccount>(conn)
+		.optional()?;
+
+	Ok(match account {
+		Some(account) => AccountBalance {
+			character_id: q_character_id,
+			account_id: Some(account.id),
+			balance: balance_of(account.id, conn)?,
+			credit_limit: account.credit_limit,
+		},
+		None => AccountBalance {
+			character_id: q_character_id,
+			account_id: None,
+			balance: 0,
+			credit_limit: 0,
+		},
+	})
+}
+
+// Pays from one character to another. Nothing is booked and None is returned
+// when the payment would take the payer below its credit limit.
+pub fn pay(new_payment: NewPayment, pool: &web::Data<Pool>) -> Result<Option<CurrencyTransaction>, Error> {
+	use crate::schema::currency_accounts::dsl::{currency_accounts, id};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let payer = open_account(new_payment.payer_character_id, &new_payment.updated_by, conn)?;
+		let payee = open_account(new_payment.payee_character_id, &new_payment.updated_by, conn)?;
+
+		// Locking the payer keeps two payments from both passing the check
+		let payer = currency_accounts
+			.filter(id.eq(payer.id))
+			.for_update()
+			.get_result::<CurrencyAccount>(conn)?;
+		if balance_of(payer.id, conn)? - new_payment.amount < -payer.credit_limit {
+			return Ok(None);
+		}
+
+		let new_transaction = CurrencyTransaction {
+			id: uuid::Uuid::new_v4(),
+			kind: TransactionKind::Payment.as_str().to_string(),
+			payer_account_id: payer.id,
+			payee_account_id: payee.id,
+			amount: new_payment.amount,
+			memo: new_payment.memo,
+			in_world_at: new_payment.in_world_at,
+			user_id: new_payment.user_id,
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: new_payment.updated_by,
+			updated_at: chrono::Local::now().naive_local(),
+		};
+		record(new_transaction, conn).map(Some)
+	})
+}
+
+// GMs may take a balance below the credit limit
+pub fn adjust(new_adjustment: NewAdjustment, pool: &web::Data<Pool>) -> Result<CurrencyTransaction, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let account = open_account(new_adjustment.character_id, &new_adjustment.updated_by, conn)?;
+		let treasury = open_treasury(&new_adjustment.updated_by, conn)?;
+		let (payer, payee) = if new_adjustment.amount > 0 {
+			(treasury, account)
+		} else {
+			(account, treasury)
+		};
+
+		let new_transaction = CurrencyTransaction {
+			id: uuid::Uuid::new_v4(),
+			kind: TransactionKind::Adjustment.as_str().to_string(),
+			payer_account_id: payer.id,
+			payee_account_id: payee.id,
+			amount: new_adjustment.amount.abs(),
+			memo: new_adjustment.memo,
+			in_world_at: new_adjustment.in_world_at,
+			user_id: new_adjustment.user_id,
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: new_adjustment.updated_by,
+			updated_at: chrono::Local::now().naive_local(),
+		};
+		record(new_transaction, conn)
+	})
+}
+
+pub fn set_credit_limit(
+	q_character_id: uuid::Uuid,
+	q_credit_limit: i64,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<AccountBalance, Error> {
+	use crate::schema::currency_accounts::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let account = conn.transaction::<_, Error, _>(|| {
+		let account = open_account(q_character_id, &q_email, conn)?;
+		diesel::update(currency_accounts.filter(id.eq(account.id)))
+			.set((credit_limit.eq(q_credit_limit), updated_by.eq(&q_email)))
+			.get_result::<CurrencyAccount>(conn)
+	})?;
+
+	Ok(AccountBalance {
+		character_id: q_character_id,
+		account_id: Some(account.id),
+		balance: balance_of(account.id, conn)?,
+		credit_limit: account.credit_limit,
+	})
+}
+
+// The transactions of the character's account, newest first unless asked otherwise
+pub fn query_ledger(q_character_id: uuid::Uuid, params: &ListParams, pool: &web::Data<Pool>) -> Result<Page<LedgerLine>, Error> {
+	use crate::schema::currency_accounts::dsl as ca;
+	use crate::schema::currency_entries::dsl as ce;
+	use crate::schema::currency_transactions::dsl as ct;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let account_id = ca::currency_accounts
+		.filter(ca::character_id.eq(q_character_id))
+		.select(ca::id)
+		.get_result::<uuid::Uuid>(conn)
+		.optional()?;
+	let account_id = match account_id {
+		Some(account_id) => account_id,
+		None => {
+			return Ok(Page {
+				items: Vec::new(),
+				total: 0,
+			})
+		}
+	};
+
+	let total = ce::currency_entries
+		.filter(ce::account_id.eq(account_id))
+		.count()
+		.get_result::<i64>(conn)?;
+
+	let query = ce::currency_entries
+		.inner_join(ct::currency_transactions)
+		.filter(ce::account_id.eq(account_id))
+		.select((ct::currency_transactions::all_columns(), ce::amount))
+		.into_boxed();
+	let query = match params.direction {
+		Some(SortDirection::Asc) => query.order((ct::created_at.asc(), ct::id.asc())),
+		_ => query.order((ct::created_at.desc(), ct::id.desc())),
+	};
+	let rows = query
+		.limit(params.limit())
+		.offset(params.offset())
+		.load::<(CurrencyTransaction, i64)>(conn)?;
+
+	let counterparty_ids: Vec<uuid::Uuid> = rows
+		.iter()
+		.map(|(transaction, _)| {
+			if transaction.payer_account_id == account_id {
+				transaction.payee_account_id
+			} else {
+				transaction.payer_account_id
+			}
+		})
+		.collect();
+	let names: HashMap<uuid::Uuid, String> = ca::currency_accounts
+		.filter(ca::id.eq_any(&counterparty_ids))
+		.select((ca::id, ca::name))
+		.load::<(uuid::Uuid, String)>(conn)?
+		.into_iter()
+		.collect();
+
+	let items = rows
+		.into_iter()
+		.zip(counterparty_ids)
+		.map(|((transaction, entry_amount), counterparty_id)| LedgerLine {
+			transaction,
+			entry_amount,
+			counterparty: names.get(&counterparty_id).cloned().unwrap_or_default(),
+		})
+		.collect();
+
+	Ok(Page { items, total })
+}
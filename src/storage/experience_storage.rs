@@ -0,0 +1,120 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::result::Error::NotFound;
+use diesel::PgConnection;
+
+use crate::models::experience::{ExperienceEntry, ExperienceSummary};
+use crate::models::listing::{ListParams, Page, SortDirection};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+fn current_experience(q_character_id: uuid::Uuid, conn: &PgConnection) -> Result<i64, Error> {
+	use crate::schema::experience::dsl::*;
+
+	let current = experience
+		.filter(character_id.eq(q_character_id))
+		.select(diesel::dsl::sum(amount))
+		.get_result::<Option<i64>>(conn)?;
+
+	Ok(current.unwrap_or(0))
+}
+
+pub fn query_summary(q_character_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<ExperienceSummary, Error> {
+	use crate::schema::experience::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let awarded = experience
+		.filter(character_id.eq(q_character_id))
+		.filter(amount.gt(0))
+		.select(diesel::dsl::sum(amount))
+		.get_result::<Option<i64>>(conn)?
+		.unwrap_or(0);
+	let current = current_experience(q_character_id, conn)?;
+
+	Ok(ExperienceSummary {
+		character_id: q_character_id,
+		awarded,
+		spent: awarded - current,
+		current,
+	})
+}
+
+// The character's ledger, newest entries first unless asked otherwise
+pub fn query_history(
+	q_character_id: uuid::Uuid,
+	params: &ListParams,
+	pool: &web::Data<Pool>,
+) -> Result<Page<ExperienceEntry>, Error> {
+	use crate::schema::experience::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let mut query = experience.filter(character_id.eq(q_character_id)).into_boxed();
+	let mut count = experience.filter(character_id.eq(q_character_id)).into_boxed();
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+		count = count.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+		count = count.filter(created_at.lt(to));
+	}
+
+	let total = count.count().get_result::<i64>(conn)?;
+	let query = match params.direction {
+		Some(SortDirection::Asc) => query.order((created_at.asc(), id.asc())),
+		_ => query.order((created_at.desc(), id.desc())),
+	};
+	let items = query
+		.limit(params.limit())
+		.offset(params.offset())
+		.load::<ExperienceEntry>(conn)?;
+
+	Ok(Page { items, total })
+}
+
+// Appends an entry to the ledger. Nothing is stored and None is returned when
+// the entry would take the character's experience below zero. An advancement
+// also replaces the sheet data, which must still be what the advancement was
+// worked out from.
+pub fn add_entry(
+	new_entry: ExperienceEntry,
+	q_sheet: Option<(serde_json::Value, serde_json::Value)>,
+	pool: &web::Data<Pool>,
+) -> Result<Option<ExperienceEntry>, Error> {
+	use crate::schema::character_sheets::dsl as cs;
+	use crate::schema::characters::dsl as ch;
+	use crate::schema::experience::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		// Locking the character keeps two spends from both passing the check
+		ch::characters
+			.filter(ch::id.eq(new_entry.character_id))
+			.select(ch::id)
+			.for_update()
+			.get_result::<uuid::Uuid>(conn)?;
+
+		if current_experience(new_entry.character_id, conn)? + (new_entry.amount as i64) < 0 {
+			return Ok(None);
+		}
+
+		if let Some((old_data, new_data)) = q_sheet {
+			let updated = diesel::update(
+				cs::character_sheets
+					.filter(cs::character_id.eq(new_entry.character_id))
+					.filter(cs::data.eq(old_data)),
+			)
+			.set((cs::data.eq(new_data), cs::updated_by.eq(&new_entry.updated_by)))
+			.execute(conn)?;
+			if updated == 0 {
+				return Err(NotFound);
+			}
+		}
+
+		let entry = diesel::insert_into(experience)
+			.values(&new_entry)
+			.get_result::<ExperienceEntry>(conn)?;
+
+		Ok(Some(entry))
+	})
+}
@@ -0,0 +1,309 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::result::Error::NotFound;
+use diesel::PgConnection;
+
+use crate::models::items::{InventoryEntry, InventoryItem, InventoryItemChanges, Item, ItemCategory, ItemTransfer};
+use crate::models::listing::{ListParams, Page, SortDirection};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+// The catalog in the order of names
+pub fn query_items(
+	q_category: Option<ItemCategory>,
+	params: &ListParams,
+	pool: &web::Data<Pool>,
+) -> Result<Page<Item>, Error> {
+	use crate::schema::items::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let mut query = items.into_boxed();
+	let mut count = items.into_boxed();
+	if let Some(q_category) = q_category {
+		query = query.filter(category.eq(q_category.as_str()));
+		count = count.filter(category.eq(q_category.as_str()));
+	}
+
+	let total = count.count().get_result::<i64>(conn)?;
+	let query = match params.direction {
+		Some(SortDirection::Desc) => query.order(name.desc()),
+		_ => query.order(name.asc()),
+	};
+	let items_page = query.limit(params.limit()).offset(params.offset()).load::<Item>(conn)?;
+
+	Ok(Page {
+		items: items_page,
+		total,
+	})
+}
+
+pub fn get_item(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Item, Error> {
+	use crate::schema::items::dsl::{id, items};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let item = items.filter(id.eq(q_id)).get_result::<Item>(conn)?;
+
+	Ok(item)
+}
+
+pub fn create_item(
+	q_name: String,
+	q_category: ItemCategory,
+	q_description: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Item, Error> {
+	use crate::schema::items::dsl::items;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_item = Item {
+		id: uuid::Uuid::new_v4(),
+		name: q_name,
+		category: q_category.as_str().to_string(),
+		description: q_description,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	let item = diesel::insert_into(items).values(&new_item).get_result::<Item>(conn)?;
+
+	Ok(item)
+}
+
+pub fn update_item(
+	q_id: uuid::Uuid,
+	q_name: String,
+	q_category: ItemCategory,
+	q_description: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Item, Error> {
+	use crate::schema::items::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let item = diesel::update(items)
+		.filter(id.eq(q_id))
+		.set((
+			name.eq(q_name),
+			category.eq(q_category.as_str()),
+			description.eq(q_description),
+			updated_by.eq(q_email),
+		))
+		.get_result::<Item>(conn)?;
+
+	Ok(item)
+}
+
+// Fails with a foreign key violation while a character carries the item
+pub fn delete_item(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+	use crate::schema::items::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let deleted = diesel::delete(items.filter(id.eq(q_id))).execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// The character's inventory, equipped items first
+pub fn query_inventory(q_character_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<InventoryEntry>, Error> {
+	use crate::schema::inventory_items::dsl as inv;
+	use crate::schema::items::dsl as it;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let entries = inv::inventory_items
+		.inner_join(it::items)
+		.filter(inv::character_id.eq(q_character_id))
+		.order((inv::equipped.desc(), it::name.asc()))
+		.select((inv::inventory_items::all_columns(), it::name, it::category))
+		.load::<(InventoryItem, String, String)>(conn)?
+		.into_iter()
+		.map(|(inventory_item, name, category)| InventoryEntry {
+			inventory_item,
+			name,
+			category,
+		})
+		.collect();
+
+	Ok(entries)
+}
+
+fn add_to_inventory(
+	q_character_id: uuid::Uuid,
+	q_item_id: uuid::Uuid,
+	q_quantity: i32,
+	q_email: &str,
+	conn: &PgConnection,
+) -> Result<InventoryItem, Error> {
+	use crate::schema::inventory_items::dsl::*;
+
+	let new_inventory_item = InventoryItem {
+		id: uuid::Uuid::new_v4(),
+		character_id: q_character_id,
+		item_id: q_item_id,
+		quantity: q_quantity,
+		equipped: false,
+		notes: String::new(),
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email.to_string(),
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	diesel::insert_into(inventory_items)
+		.values(&new_inventory_item)
+		.on_conflict((character_id, item_id))
+		.do_update()
+		.set((quantity.eq(quantity + q_quantity), updated_by.eq(q_email)))
+		.get_result::<InventoryItem>(conn)
+}
+
+// Adds to the quantity the character already has of the item
+pub fn add_inventory_item(
+	q_character_id: uuid::Uuid,
+	q_item_id: uuid::Uuid,
+	q_quantity: i32,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<InventoryItem, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	add_to_inventory(q_character_id, q_item_id, q_quantity, &q_email, conn)
+}
+
+// The character and item of an inventory row stay the same. Only GMs raise
+// quantities; None is returned when anyone else asks for more than there is.
+pub fn update_inventory_item(
+	q_character_id: uuid::Uuid,
+	q_id: uuid::Uuid,
+	changes: InventoryItemChanges,
+	q_isadmin: bool,
+	pool: &web::Data<Pool>,
+) -> Result<Option<InventoryItem>, Error> {
+	use crate::schema::inventory_items::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let old = inventory_items
+			.filter(id.eq(q_id))
+			.filter(character_id.eq(q_character_id))
+			.for_update()
+			.get_result::<InventoryItem>(conn)?;
+		let new_quantity = changes.quantity.unwrap_or(old.quantity);
+		if new_quantity > old.quantity && q_isadmin == false {
+			return Ok(None);
+		}
+
+		let inventory_item = diesel::update(inventory_items)
+			.filter(id.eq(q_id))
+			.set((
+				quantity.eq(new_quantity),
+				equipped.eq(changes.equipped),
+				notes.eq(changes.notes),
+				updated_by.eq(changes.updated_by),
+			))
+			.get_result::<InventoryItem>(conn)?;
+		Ok(Some(inventory_item))
+	})
+}
+
+pub fn delete_inventory_item(q_character_id: uuid::Uuid, q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+	use crate::schema::inventory_items::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let deleted = diesel::delete(inventory_items.filter(id.eq(q_id)).filter(character_id.eq(q_character_id))).execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// Moves items from one character's inventory to another's and logs the
+// transfer. Nothing is moved and None is returned when the giver has fewer
+// than asked. Given items are unequipped and lose the giver's notes.
+pub fn transfer_items(
+	q_from_character_id: uuid::Uuid,
+	q_to_character_id: uuid::Uuid,
+	q_item_id: uuid::Uuid,
+	q_quantity: i32,
+	q_note: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Option<ItemTransfer>, Error> {
+	use crate::schema::characters::dsl as ch;
+	use crate::schema::inventory_items::dsl as inv;
+	use crate::schema::item_transfers::dsl::item_transfers;
+	use crate::schema::items::dsl as it;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		ch::characters.filter(ch::id.eq(q_to_character_id)).select(ch::id).get_result::<uuid::Uuid>(conn)?;
+
+		let held = inv::inventory_items
+			.filter(inv::character_id.eq(q_from_character_id))
+			.filter(inv::item_id.eq(q_item_id))
+			.for_update()
+			.get_result::<InventoryItem>(conn)
+			.optional()?;
+		let held = match held {
+			Some(held) if held.quantity >= q_quantity => held,
+			_ => return Ok(None),
+		};
+
+		if held.quantity == q_quantity {
+			diesel::delete(inv::inventory_items.filter(inv::id.eq(held.id))).execute(conn)?;
+		} else {
+			diesel::update(inv::inventory_items.filter(inv::id.eq(held.id)))
+				.set((inv::quantity.eq(held.quantity - q_quantity), inv::updated_by.eq(&q_email)))
+				.execute(conn)?;
+		}
+		add_to_inventory(q_to_character_id, q_item_id, q_quantity, &q_email, conn)?;
+
+		let item_name = it::items.filter(it::id.eq(q_item_id)).select(it::name).get_result::<String>(conn)?;
+		let new_transfer = ItemTransfer {
+			id: uuid::Uuid::new_v4(),
+			item_id: q_item_id,
+			item_name,
+			from_character_id: q_from_character_id,
+			to_character_id: q_to_character_id,
+			quantity: q_quantity,
+			note: q_note,
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: q_email.clone(),
+			updated_at: chrono::Local::now().naive_local(),
+		};
+		let transfer = diesel::insert_into(item_transfers)
+			.values(&new_transfer)
+			.get_result::<ItemTransfer>(conn)?;
+
+		Ok(Some(transfer))
+	})
+}
+
+// Transfers to and from the character, newest first unless asked otherwise
+pub fn query_transfers(
+	q_character_id: uuid::Uuid,
+	params: &ListParams,
+	pool: &web::Data<Pool>,
+) -> Result<Page<ItemTransfer>, Error> {
+	use crate::schema::item_transfers::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let involved = from_character_id.eq(q_character_id).or(to_character_id.eq(q_character_id));
+	let total = item_transfers.filter(involved).count().get_result::<i64>(conn)?;
+
+	let query = item_transfers.filter(involved).into_boxed();
+	let query = match params.direction {
+		Some(SortDirection::Asc) => query.order((created_at.asc(), id.asc())),
+		_ => query.order((created_at.desc(), id.desc())),
+	};
+	let items = query
+		.limit(params.limit())
+		.offset(params.offset())
+		.load::<ItemTransfer>(conn)?;
+
+	Ok(Page { items, total })
+}
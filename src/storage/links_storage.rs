@@ -0,0 +1,157 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use crate::models::articles::Article;
+use crate::models::links::{Backlink, BrokenLink, ContentLink, SavedArticle};
+use crate::models::slugs::SlugOwner;
+use crate::models::users::Pool;
+use crate::storage::slugs_storage;
+use crate::wikilinks;
+use diesel::result::Error;
+
+// store_links and resolve_broken_links take a connection so that they run in
+// the transaction that saves the article or the link target.
+
+// Replaces the stored links of an article with the ones in its body
+pub fn store_links(
+	q_article_id: uuid::Uuid,
+	q_body: &str,
+	q_email: &str,
+	conn: &PgConnection,
+) -> Result<Vec<ContentLink>, Error> {
+	use crate::schema::content_links::dsl::*;
+
+	diesel::delete(content_links.filter(article_id.eq(q_article_id))).execute(conn)?;
+
+	let new_links = wikilinks::parse(q_body)
+		.into_iter()
+		.map(|link| {
+			let target = resolve_link(link.kind, &link.slug, conn)?;
+			Ok(ContentLink {
+				id: uuid::Uuid::new_v4(),
+				article_id: q_article_id,
+				link_text: link.text,
+				target_type: target.map(|(owner, _)| owner).or(link.kind).map(|owner| owner.as_str().to_string()),
+				target_slug: link.slug,
+				target_id: target.map(|(_, q_id)| q_id),
+				created_at: chrono::Local::now().naive_local(),
+				updated_by: q_email.to_string(),
+			})
+		})
+		.collect::<Result<Vec<ContentLink>, Error>>()?;
+
+	diesel::insert_into(content_links)
+		.values(&new_links)
+		.get_results::<ContentLink>(conn)
+}
+
+// Links without a kind go to a character before an article of the same slug
+fn resolve_link(
+	q_kind: Option<SlugOwner>,
+	q_slug: &str,
+	conn: &PgConnection,
+) -> Result<Option<(SlugOwner, uuid::Uuid)>, Error> {
+	let owners = match q_kind {
+		Some(owner) => vec![owner],
+		None => vec![SlugOwner::Character, SlugOwner::Article],
+	};
+	for owner in owners {
+		let current = match owner {
+			SlugOwner::Article => {
+				use crate::schema::articles::dsl::{articles, id, slug};
+				articles.select(id).filter(slug.eq(q_slug)).get_result::<uuid::Uuid>(conn).optional()?
+			}
+			SlugOwner::Character => {
+				use crate::schema::characters::dsl::{characters, id, slug};
+				characters.select(id).filter(slug.eq(q_slug)).get_result::<uuid::Uuid>(conn).optional()?
+			}
+		};
+		if let Some(q_id) = current {
+			return Ok(Some((owner, q_id)));
+		}
+		if let Some(old) = slugs_storage::query_slug_history(owner, q_slug, conn).optional()? {
+			return Ok(Some((owner, old.content_id)));
+		}
+	}
+	Ok(None)
+}
+
+// Points broken links written for the slug at newly created or renamed content
+pub fn resolve_broken_links(
+	q_owner: SlugOwner,
+	q_target_id: uuid::Uuid,
+	q_slug: &str,
+	conn: &PgConnection,
+) -> Result<usize, Error> {
+	use crate::schema::content_links::dsl::*;
+
+	diesel::update(content_links)
+		.filter(target_id.is_null())
+		.filter(target_slug.eq(q_slug))
+		.filter(target_type.eq(q_owner.as_str()).or(target_type.is_null()))
+		.set((target_type.eq(q_owner.as_str()), target_id.eq(q_target_id)))
+		.execute(conn)
+}
+
+pub fn query_article_links(q_article_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<ContentLink>, Error> {
+	use crate::schema::content_links::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let links = content_links
+		.filter(article_id.eq(q_article_id))
+		.order(link_text.asc())
+		.load::<ContentLink>(conn)?;
+
+	Ok(links)
+}
+
+// The article as returned after saving it, so that the author sees broken links at once
+pub fn with_broken_links(article: Article, pool: &web::Data<Pool>) -> Result<SavedArticle, Error> {
+	let broken_links = query_article_links(article.id, pool)?
+		.into_iter()
+		.filter(|link| link.target_id.is_none())
+		.map(|link| link.link_text)
+		.collect();
+
+	Ok(SavedArticle { article, broken_links })
+}
+
+pub fn query_backlinks(
+	q_owner: SlugOwner,
+	q_target_id: uuid::Uuid,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<Backlink>, Error> {
+	use crate::schema::articles::dsl as a;
+	use crate::schema::content_links::dsl as cl;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let backlinks = cl::content_links
+		.inner_join(a::articles)
+		.select((a::id, a::slug, a::title))
+		.filter(cl::target_type.eq(q_owner.as_str()))
+		.filter(cl::target_id.eq(q_target_id))
+		.distinct()
+		.order((a::title.asc(), a::id.asc()))
+		.load::<Backlink>(conn)?;
+
+	Ok(backlinks)
+}
+
+// Broken links in the articles the user is an author of
+pub fn query_broken_links(q_user_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<BrokenLink>, Error> {
+	use crate::schema::article_authors::dsl as aa;
+	use crate::schema::articles::dsl as a;
+	use crate::schema::content_links::dsl as cl;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let broken = cl::content_links
+		.inner_join(a::articles)
+		.select((a::id, a::slug, a::title, cl::link_text))
+		.filter(cl::target_id.is_null())
+		.filter(cl::article_id.eq_any(aa::article_authors.select(aa::article_id).filter(aa::user_id.eq(q_user_id))))
+		.order((a::title.asc(), a::id.asc(), cl::link_text.asc()))
+		.load::<BrokenLink>(conn)?;
+
+	Ok(broken)
+}
@@ -0,0 +1,250 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use std::collections::HashMap;
+
+use crate::mentions;
+use crate::models::links::Backlink;
+use crate::models::listing::{ListParams, Page};
+use crate::models::notifications::{Mention, Notification, NotificationKind, NotificationOptOut, NotificationSetting};
+use crate::models::slugs::SlugOwner;
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+// Stores the characters @mentioned in an article body and notifies the owners
+// of newly mentioned ones. Authors are not told about mentions of their own
+// characters. Takes a connection to run in the transaction saving the article.
+pub fn store_mentions(
+	q_article_id: uuid::Uuid,
+	q_body: &str,
+	q_email: &str,
+	conn: &PgConnection,
+) -> Result<Vec<Mention>, Error> {
+	use crate::schema::article_authors::dsl as aa;
+	use crate::schema::characters::dsl as ch;
+	use crate::schema::mentions::dsl as m;
+	use crate::schema::slug_history::dsl as history;
+
+	let slugs = mentions::parse(q_body);
+	let renamed = history::slug_history
+		.select(history::content_id)
+		.filter(history::content_type.eq(SlugOwner::Character.as_str()))
+		.filter(history::slug.eq_any(&slugs));
+	let owners: HashMap<uuid::Uuid, uuid::Uuid> = ch::characters
+		.select((ch::id, ch::user_id))
+		.filter(ch::slug.eq_any(&slugs).or(ch::id.eq_any(renamed)))
+		.load::<(uuid::Uuid, uuid::Uuid)>(conn)?
+		.into_iter()
+		.collect();
+
+	diesel::delete(
+		m::mentions
+			.filter(m::article_id.eq(q_article_id))
+			.filter(diesel::dsl::not(m::character_id.eq_any(owners.keys().cloned().collect::<Vec<_>>()))),
+	)
+	.execute(conn)?;
+	let existing = m::mentions
+		.select(m::character_id)
+		.filter(m::article_id.eq(q_article_id))
+		.load::<uuid::Uuid>(conn)?;
+
+	let new_mentions: Vec<Mention> = owners
+		.iter()
+		.filter(|(q_character_id, _)| !existing.contains(q_character_id))
+		.map(|(q_character_id, q_user_id)| Mention {
+			id: uuid::Uuid::new_v4(),
+			article_id: q_article_id,
+			character_id: *q_character_id,
+			user_id: *q_user_id,
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: q_email.to_string(),
+		})
+		.collect();
+	diesel::insert_into(m::mentions)
+		.values(&new_mentions)
+		.execute(conn)?;
+
+	let authors = aa::article_authors
+		.select(aa::user_id)
+		.filter(aa::article_id.eq(q_article_id))
+		.load::<uuid::Uuid>(conn)?;
+	for mention in new_mentions.iter().filter(|mention| !authors.contains(&mention.user_id)) {
+		notify(
+			mention.user_id,
+			NotificationKind::Mention,
+			Some(mention.article_id),
+			Some(mention.character_id),
+			q_email,
+			conn,
+		)?;
+	}
+
+	m::mentions
+		.filter(m::article_id.eq(q_article_id))
+		.load::<Mention>(conn)
+}
+
+// Adds a notification unless the user has opted out of the kind
+pub fn notify(
+	q_user_id: uuid::Uuid,
+	q_kind: NotificationKind,
+	q_article_id: Option<uuid::Uuid>,
+	q_character_id: Option<uuid::Uuid>,
+	q_email: &str,
+	conn: &PgConnection,
+) -> Result<Option<Notification>, Error> {
+	use crate::schema::notification_opt_outs::dsl as opt;
+	use crate::schema::notifications::dsl::notifications;
+
+	let opted_out = opt::notification_opt_outs
+		.filter(opt::user_id.eq(q_user_id))
+		.filter(opt::kind.eq(q_kind.as_str()))
+		.count()
+		.get_result::<i64>(conn)?;
+	if opted_out > 0 {
+		return Ok(None);
+	}
+
+	let new_notification = Notification {
+		id: uuid::Uuid::new_v4(),
+		user_id: q_user_id,
+		kind: q_kind.as_str().to_string(),
+		article_id: q_article_id,
+		character_id: q_character_id,
+		read_at: None,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email.to_string(),
+	};
+
+	diesel::insert_into(notifications)
+		.values(&new_notification)
+		.get_result::<Notification>(conn)
+		.map(Some)
+}
+
+// Articles mentioning the character
+pub fn query_mentioning_articles(q_character_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<Backlink>, Error> {
+	use crate::schema::articles::dsl as a;
+	use crate::schema::mentions::dsl as m;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let items = m::mentions
+		.inner_join(a::articles)
+		.select((a::id, a::slug, a::title))
+		.filter(m::character_id.eq(q_character_id))
+		.order((a::created_at.desc(), a::id.asc()))
+		.load::<Backlink>(conn)?;
+
+	Ok(items)
+}
+
+// Newest first
+pub fn query_notifications(
+	q_user_id: uuid::Uuid,
+	q_unread: bool,
+	params: &ListParams,
+	pool: &web::Data<Pool>,
+) -> Result<Page<Notification>, Error> {
+	use crate::schema::notifications::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let filtered = || {
+		let mut query = notifications.filter(user_id.eq(q_user_id)).into_boxed();
+		if q_unread {
+			query = query.filter(read_at.is_null());
+		}
+		query
+	};
+
+	let total = filtered().count().get_result::<i64>(conn)?;
+	let items = filtered()
+		.order((created_at.desc(), id.asc()))
+		.limit(params.limit())
+		.offset(params.offset())
+		.load::<Notification>(conn)?;
+
+	Ok(Page { items, total })
+}
+
+pub fn get_notification(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Notification, Error> {
+	use crate::schema::notifications::dsl::{id, notifications};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let notification = notifications.filter(id.eq(q_id)).get_result::<Notification>(conn)?;
+
+	Ok(notification)
+}
+
+// Marks one notification, or all of the user's when q_id is None, as read
+pub fn mark_read(
+	q_user_id: uuid::Uuid,
+	q_id: Option<uuid::Uuid>,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<usize, Error> {
+	use crate::schema::notifications::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let mut query = diesel::update(notifications)
+		.filter(user_id.eq(q_user_id))
+		.filter(read_at.is_null())
+		.into_boxed();
+	if let Some(q_id) = q_id {
+		query = query.filter(id.eq(q_id));
+	}
+
+	query
+		.set((read_at.eq(chrono::Local::now().naive_local()), updated_by.eq(q_email)))
+		.execute(conn)
+}
+
+pub fn query_settings(q_user_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<NotificationSetting>, Error> {
+	use crate::schema::notification_opt_outs::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let opted_out = notification_opt_outs
+		.select(kind)
+		.filter(user_id.eq(q_user_id))
+		.load::<String>(conn)?;
+
+	Ok(NotificationKind::ALL
+		.iter()
+		.map(|q_kind| NotificationSetting {
+			kind: *q_kind,
+			enabled: !opted_out.iter().any(|opt| opt == q_kind.as_str()),
+		})
+		.collect())
+}
+
+pub fn set_setting(
+	q_user_id: uuid::Uuid,
+	q_kind: NotificationKind,
+	q_enabled: bool,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<NotificationSetting>, Error> {
+	use crate::schema::notification_opt_outs::dsl::*;
+	{
+		let conn: &PgConnection = &pool.get().unwrap();
+
+		if q_enabled {
+			diesel::delete(notification_opt_outs.filter(user_id.eq(q_user_id)).filter(kind.eq(q_kind.as_str())))
+				.execute(conn)?;
+		} else {
+			let new_opt_out = NotificationOptOut {
+				id: uuid::Uuid::new_v4(),
+				user_id: q_user_id,
+				kind: q_kind.as_str().to_string(),
+				created_at: chrono::Local::now().naive_local(),
+				updated_by: q_email,
+			};
+			diesel::insert_into(notification_opt_outs)
+				.values(&new_opt_out)
+				.on_conflict((user_id, kind))
+				.do_nothing()
+				.execute(conn)?;
+		}
+	}
+
+	query_settings(q_user_id, pool)
+}
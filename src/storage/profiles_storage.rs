@@ -0,0 +1,63 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use crate::models::profiles::CharacterProfile;
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+// None while nobody has written a profile for the character
+pub fn get_profile(q_character_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Option<CharacterProfile>, Error> {
+	use crate::schema::character_profiles::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let profile = character_profiles
+		.filter(character_id.eq(q_character_id))
+		.get_result::<CharacterProfile>(conn)
+		.optional()?;
+
+	Ok(profile)
+}
+
+// Creates the profile or replaces the fields the player writes, leaving the GM notes
+pub fn save_profile(new_profile: CharacterProfile, pool: &web::Data<Pool>) -> Result<CharacterProfile, Error> {
+	use crate::schema::character_profiles::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let profile = diesel::insert_into(character_profiles)
+		.values(&new_profile)
+		.on_conflict(character_id)
+		.do_update()
+		.set((
+			portrait_url.eq(&new_profile.portrait_url),
+			bio.eq(&new_profile.bio),
+			affiliations.eq(&new_profile.affiliations),
+			portrait_visibility.eq(&new_profile.portrait_visibility),
+			bio_visibility.eq(&new_profile.bio_visibility),
+			affiliations_visibility.eq(&new_profile.affiliations_visibility),
+			sheet_visibility.eq(&new_profile.sheet_visibility),
+			updated_by.eq(&new_profile.updated_by),
+		))
+		.get_result::<CharacterProfile>(conn)?;
+
+	Ok(profile)
+}
+
+// Creates the profile or replaces the GM notes, leaving the player's fields
+pub fn save_gm_notes(new_profile: CharacterProfile, pool: &web::Data<Pool>) -> Result<CharacterProfile, Error> {
+	use crate::schema::character_profiles::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let profile = diesel::insert_into(character_profiles)
+		.values(&new_profile)
+		.on_conflict(character_id)
+		.do_update()
+		.set((
+			gm_notes.eq(&new_profile.gm_notes),
+			gm_notes_visibility.eq(&new_profile.gm_notes_visibility),
+			updated_by.eq(&new_profile.updated_by),
+		))
+		.get_result::<CharacterProfile>(conn)?;
+
+	Ok(profile)
+}
@@ -0,0 +1,169 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::result::Error::NotFound;
+use diesel::sql_types::{Array, Uuid};
+use diesel::PgConnection;
+use std::collections::BTreeMap;
+
+use crate::models::articles::Article;
+use crate::models::listing::Page;
+use crate::models::reactions::{ArticleReaction, ArticleWithReactions, Favorite, ReactionCount, ReactionType};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+pub fn query_reactions(q_article_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Vec<ArticleReaction>, Error> {
+	use crate::schema::article_reactions::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let items = article_reactions
+		.filter(article_id.eq(q_article_id))
+		.order((created_at.asc(), id.asc()))
+		.load::<ArticleReaction>(conn)?;
+
+	Ok(items)
+}
+
+pub fn create_reaction(
+	q_article_id: uuid::Uuid,
+	q_user_id: uuid::Uuid,
+	q_character_id: Option<uuid::Uuid>,
+	q_reaction: ReactionType,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<ArticleReaction, Error> {
+	use crate::schema::article_reactions::dsl::article_reactions;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_reaction = ArticleReaction {
+		id: uuid::Uuid::new_v4(),
+		article_id: q_article_id,
+		user_id: q_user_id,
+		character_id: q_character_id,
+		reaction: q_reaction.as_str().to_string(),
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+	};
+
+	let reaction = diesel::insert_into(article_reactions)
+		.values(&new_reaction)
+		.get_result::<ArticleReaction>(conn)?;
+
+	Ok(reaction)
+}
+
+pub fn delete_reaction(
+	q_article_id: uuid::Uuid,
+	q_user_id: uuid::Uuid,
+	q_character_id: Option<uuid::Uuid>,
+	q_reaction: ReactionType,
+	pool: &web::Data<Pool>,
+) -> Result<(), Error> {
+	use crate::schema::article_reactions::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let deleted = diesel::delete(
+		article_reactions
+			.filter(article_id.eq(q_article_id))
+			.filter(user_id.eq(q_user_id))
+			.filter(character_id.is_not_distinct_from(q_character_id))
+			.filter(reaction.eq(q_reaction.as_str())),
+	)
+	.execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// Adding a favorite twice keeps the first one
+pub fn create_favorite(
+	q_article_id: uuid::Uuid,
+	q_user_id: uuid::Uuid,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Favorite, Error> {
+	use crate::schema::favorites::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_favorite = Favorite {
+		id: uuid::Uuid::new_v4(),
+		article_id: q_article_id,
+		user_id: q_user_id,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+	};
+
+	diesel::insert_into(favorites)
+		.values(&new_favorite)
+		.on_conflict((user_id, article_id))
+		.do_nothing()
+		.execute(conn)?;
+
+	let favorite = favorites
+		.filter(user_id.eq(q_user_id))
+		.filter(article_id.eq(q_article_id))
+		.get_result::<Favorite>(conn)?;
+
+	Ok(favorite)
+}
+
+pub fn delete_favorite(q_article_id: uuid::Uuid, q_user_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+	use crate::schema::favorites::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let deleted =
+		diesel::delete(favorites.filter(article_id.eq(q_article_id)).filter(user_id.eq(q_user_id))).execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// Attaches reaction and favorite counts to the articles with two grouped queries
+pub fn with_reactions(items: Vec<Article>, pool: &web::Data<Pool>) -> Result<Vec<ArticleWithReactions>, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let ids: Vec<uuid::Uuid> = items.iter().map(|article| article.id).collect();
+	let counts = diesel::sql_query(
+		"SELECT article_id, reaction::text AS reaction, count(*) AS count
+		FROM article_reactions
+		WHERE article_id = ANY($1)
+		GROUP BY article_id, reaction
+		UNION ALL
+		SELECT article_id, 'favorite' AS reaction, count(*) AS count
+		FROM favorites
+		WHERE article_id = ANY($1)
+		GROUP BY article_id",
+	)
+	.bind::<Array<Uuid>, _>(&ids)
+	.load::<ReactionCount>(conn)?;
+
+	Ok(items
+		.into_iter()
+		.map(|article| {
+			let mut reactions = BTreeMap::new();
+			let mut favorites = 0;
+			for count in counts.iter().filter(|count| count.article_id == article.id) {
+				if count.reaction == "favorite" {
+					favorites = count.count;
+				} else {
+					reactions.insert(count.reaction.clone(), count.count);
+				}
+			}
+			ArticleWithReactions {
+				article,
+				reactions,
+				favorites,
+			}
+		})
+		.collect())
+}
+
+pub fn page_with_reactions(page: Page<Article>, pool: &web::Data<Pool>) -> Result<Page<ArticleWithReactions>, Error> {
+	Ok(Page {
+		items: with_reactions(page.items, pool)?,
+		total: page.total,
+	})
+}
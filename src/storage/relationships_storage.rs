@@ -0,0 +1,80 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::result::Error::NotFound;
+use diesel::PgConnection;
+
+use crate::models::relationships::{Relationship, RelationshipChanges};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+// Fails with a unique violation when the pair already has a relationship of the kind
+pub fn create_relationship(new_relationship: Relationship, pool: &web::Data<Pool>) -> Result<Relationship, Error> {
+	use crate::schema::relationships::dsl::relationships;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let relationship = diesel::insert_into(relationships)
+		.values(&new_relationship)
+		.get_result::<Relationship>(conn)?;
+
+	Ok(relationship)
+}
+
+// Only relationships from the character are found, others are not its to change
+pub fn update_relationship(
+	q_from_character_id: uuid::Uuid,
+	q_id: uuid::Uuid,
+	changes: RelationshipChanges,
+	pool: &web::Data<Pool>,
+) -> Result<Relationship, Error> {
+	use crate::schema::relationships::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let relationship = diesel::update(relationships)
+		.filter(id.eq(q_id))
+		.filter(from_character_id.eq(q_from_character_id))
+		.set((
+			kind.eq(changes.kind.as_str()),
+			directed.eq(changes.directed),
+			notes.eq(changes.notes),
+			visibility.eq(changes.visibility),
+			updated_by.eq(changes.updated_by),
+		))
+		.get_result::<Relationship>(conn)?;
+
+	Ok(relationship)
+}
+
+pub fn delete_relationship(q_from_character_id: uuid::Uuid, q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+	use crate::schema::relationships::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let deleted = diesel::delete(relationships.filter(id.eq(q_id)).filter(from_character_id.eq(q_from_character_id)))
+		.execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// Relationships from or to any of the characters, oldest first
+pub fn query_relationships_of(q_character_ids: &[uuid::Uuid], pool: &web::Data<Pool>) -> Result<Vec<Relationship>, Error> {
+	use crate::schema::relationships::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let items = relationships
+		.filter(from_character_id.eq_any(q_character_ids).or(to_character_id.eq_any(q_character_ids)))
+		.order((created_at.asc(), id.asc()))
+		.load::<Relationship>(conn)?;
+
+	Ok(items)
+}
+
+pub fn query_all_relationships(pool: &web::Data<Pool>) -> Result<Vec<Relationship>, Error> {
+	use crate::schema::relationships::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let items = relationships.order((created_at.asc(), id.asc())).load::<Relationship>(conn)?;
+
+	Ok(items)
+}
@@ -0,0 +1,173 @@
+use actix_web::web;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::PgConnection;
+use sha2::{Digest, Sha256};
+
+use crate::dice;
+use crate::models::listing::{ListParams, Page, SortDirection};
+use crate::models::rolls::{NewRoll, Roll, RollLogCheck};
+use crate::models::users::Pool;
+use crate::schema::rolls;
+use diesel::result::Error;
+
+// Covers everything logged about the roll except the times, which the
+// database sets on insert
+fn roll_hash(roll: &Roll) -> String {
+	let fields = serde_json::to_string(&(
+		&roll.prev_hash,
+		&roll.campaign,
+		roll.sequence,
+		roll.user_id,
+		roll.character_id,
+		&roll.character_name,
+		&roll.expression,
+		roll.seed,
+		&roll.result,
+		roll.total,
+		&roll.reason,
+	))
+	.unwrap_or_default();
+	format!("{:x}", Sha256::digest(fields.as_bytes()))
+}
+
+fn filtered_rolls(q_campaign: &str, params: &ListParams) -> rolls::BoxedQuery<'static, Pg> {
+	use crate::schema::rolls::dsl::*;
+
+	let mut query = rolls.filter(campaign.eq(q_campaign.to_string())).into_boxed();
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+	}
+	if let Some(q_user_id) = params.user_id {
+		query = query.filter(user_id.eq(q_user_id));
+	}
+	if let Some(q_character_id) = params.character_id {
+		query = query.filter(character_id.eq(q_character_id));
+	}
+	query
+}
+
+// The log is always in the order of the rolls, newest first unless asked otherwise
+pub fn query_rolls(q_campaign: &str, params: &ListParams, pool: &web::Data<Pool>) -> Result<Page<Roll>, Error> {
+	use crate::schema::rolls::dsl::sequence;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let total = filtered_rolls(q_campaign, params).count().get_result::<i64>(conn)?;
+
+	let query = filtered_rolls(q_campaign, params);
+	let query = match params.direction {
+		Some(SortDirection::Asc) => query.order(sequence.asc()),
+		_ => query.order(sequence.desc()),
+	};
+
+	let items = query.limit(params.limit()).offset(params.offset()).load::<Roll>(conn)?;
+
+	Ok(Page { items, total })
+}
+
+pub fn get_roll(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Roll, Error> {
+	use crate::schema::rolls::dsl::{id, rolls};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let roll = rolls.filter(id.eq(q_id)).get_result::<Roll>(conn)?;
+
+	Ok(roll)
+}
+
+// Appends the roll to the end of its campaign's log. Rolls of the same
+// campaign are appended one at a time.
+pub fn add_roll(new_roll: NewRoll, pool: &web::Data<Pool>) -> Result<Roll, Error> {
+	use crate::schema::rolls::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		diesel::sql_query("SELECT pg_advisory_xact_lock(hashtext($1))")
+			.bind::<Text, _>(&new_roll.campaign)
+			.execute(conn)?;
+
+		let last = rolls
+			.filter(campaign.eq(&new_roll.campaign))
+			.order(sequence.desc())
+			.select((sequence, hash))
+			.first::<(i64, String)>(conn)
+			.optional()?;
+		let (last_sequence, last_hash) = last.unwrap_or((0, String::new()));
+
+		let mut roll = Roll {
+			id: uuid::Uuid::new_v4(),
+			campaign: new_roll.campaign,
+			sequence: last_sequence + 1,
+			user_id: new_roll.user_id,
+			character_id: new_roll.character_id,
+			character_name: new_roll.character_name,
+			expression: new_roll.expression,
+			seed: new_roll.seed,
+			result: new_roll.result,
+			total: new_roll.total,
+			reason: new_roll.reason,
+			prev_hash: last_hash,
+			hash: String::new(),
+			created_at: chrono::Local::now().naive_local(),
+			updated_by: new_roll.updated_by,
+			updated_at: chrono::Local::now().naive_local(),
+		};
+		roll.hash = roll_hash(&roll);
+
+		diesel::insert_into(rolls).values(&roll).get_result::<Roll>(conn)
+	})
+}
+
+fn check_roll(roll: &Roll, expected_sequence: i64, expected_prev_hash: &str) -> Result<(), String> {
+	if roll.sequence != expected_sequence {
+		return Err(format!("expected roll {}", expected_sequence));
+	}
+	if roll.prev_hash != expected_prev_hash {
+		return Err(String::from("does not follow the previous roll"));
+	}
+	if roll.hash != roll_hash(roll) {
+		return Err(String::from("does not match its hash"));
+	}
+	let expression = dice::parse(&roll.expression)?;
+	if expression.roll_seeded(roll.seed).total != roll.total {
+		return Err(String::from("does not match the roll of its seed"));
+	}
+	Ok(())
+}
+
+// Walks the log of the campaign from the first roll, checking the chain of
+// hashes and rolling every expression again with its seed
+pub fn check_roll_log(q_campaign: &str, pool: &web::Data<Pool>) -> Result<RollLogCheck, Error> {
+	use crate::schema::rolls::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let log = rolls
+		.filter(campaign.eq(q_campaign))
+		.order(sequence.asc())
+		.load::<Roll>(conn)?;
+
+	let mut prev = String::new();
+	for (i, roll) in log.iter().enumerate() {
+		if let Err(problem) = check_roll(roll, i as i64 + 1, &prev) {
+			return Ok(RollLogCheck {
+				campaign: q_campaign.to_string(),
+				rolls: log.len() as i64,
+				valid: false,
+				broken_at: Some(roll.sequence),
+				problem: Some(problem),
+			});
+		}
+		prev = roll.hash.clone();
+	}
+
+	Ok(RollLogCheck {
+		campaign: q_campaign.to_string(),
+		rolls: log.len() as i64,
+		valid: true,
+		broken_at: None,
+		problem: None,
+	})
+}
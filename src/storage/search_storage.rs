@@ -0,0 +1,116 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Nullable, Text, Uuid};
+use diesel::PgConnection;
+
+use crate::models::search::{SearchCount, SearchHit};
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+// Every searchable row matching either the Finnish or the English query, ranked
+// by whichever configuration matched it better. $1 is the raw user query, $2
+// the viewer's user id (null when not signed in) and $3 whether they are a GM.
+// Characters are found through the indexed vectors of their names and
+// descriptions and of their profile bios, but only keep a match on the
+// description or the bio where the viewer may read it: descriptions are the
+// owner's and GMs', bios follow the profile's bio visibility.
+const HITS: &str = "
+	WITH q AS (
+		SELECT websearch_to_tsquery('finnish', $1) AS fi, websearch_to_tsquery('english', $1) AS en
+	),
+	found AS (
+		SELECT c.id FROM characters c, q WHERE c.search_fi @@ q.fi OR c.search_en @@ q.en
+		UNION
+		SELECT p.character_id FROM character_profiles p, q WHERE p.search_fi @@ q.fi OR p.search_en @@ q.en
+	),
+	readable AS (
+		SELECT c.id, c.name, c.description, coalesce(p.bio, '') AS bio,
+			c.search_fi, c.search_en,
+			coalesce(p.search_fi, ''::tsvector) AS bio_search_fi,
+			coalesce(p.search_en, ''::tsvector) AS bio_search_en,
+			$3 OR c.user_id = $2 AS description_readable,
+			$3 OR c.user_id = $2
+				OR coalesce(p.bio_visibility, 'public') = 'public'
+				OR (p.bio_visibility = 'party' AND $2 IS NOT NULL) AS bio_readable
+		FROM found f
+		JOIN characters c ON c.id = f.id
+		LEFT JOIN character_profiles p ON p.character_id = c.id
+	),
+	shown AS (
+		SELECT r.id, r.name,
+			CASE WHEN r.description_readable THEN r.description ELSE '' END AS description,
+			CASE WHEN r.bio_readable THEN r.bio ELSE '' END AS bio,
+			CASE WHEN r.description_readable THEN r.search_fi
+				ELSE setweight(to_tsvector('finnish', r.name), 'A') END
+				|| CASE WHEN r.bio_readable THEN r.bio_search_fi ELSE ''::tsvector END AS search_fi,
+			CASE WHEN r.description_readable THEN r.search_en
+				ELSE setweight(to_tsvector('english', r.name), 'A') END
+				|| CASE WHEN r.bio_readable THEN r.bio_search_en ELSE ''::tsvector END AS search_en
+		FROM readable r
+	),
+	hits AS (
+		SELECT 'article'::text AS content_type, a.id, a.title::text AS title,
+			(a.ingress || ' ' || a.body)::text AS document,
+			ts_rank(a.search_fi, q.fi) AS rank_fi, ts_rank(a.search_en, q.en) AS rank_en
+		FROM articles a, q
+		WHERE a.search_fi @@ q.fi OR a.search_en @@ q.en
+		UNION ALL
+		SELECT 'character'::text, r.id, r.name::text,
+			coalesce(nullif(concat_ws(' ', nullif(r.description, ''), nullif(r.bio, '')), ''), r.name)::text,
+			ts_rank(r.search_fi, q.fi), ts_rank(r.search_en, q.en)
+		FROM shown r, q
+		WHERE r.search_fi @@ q.fi OR r.search_en @@ q.en
+		UNION ALL
+		SELECT 'tag'::text, t.id, t.title::text, t.title::text,
+			ts_rank(t.search_fi, q.fi), ts_rank(t.search_en, q.en)
+		FROM tags t, q
+		WHERE t.search_fi @@ q.fi OR t.search_en @@ q.en
+	)";
+
+const HEADLINE_OPTIONS: &str = "StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10";
+
+pub fn search(
+	q_query: String,
+	q_user_id: Option<uuid::Uuid>,
+	q_isadmin: bool,
+	q_limit: i64,
+	q_offset: i64,
+	pool: &web::Data<Pool>,
+) -> Result<(Vec<SearchHit>, i64), Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let count = diesel::sql_query(format!("{} SELECT count(*) AS total FROM hits", HITS))
+		.bind::<Text, _>(&q_query)
+		.bind::<Nullable<Uuid>, _>(q_user_id)
+		.bind::<Bool, _>(q_isadmin)
+		.get_result::<SearchCount>(conn)?;
+
+	// Headlines are expensive, so they are only built for the requested page.
+	let hits = diesel::sql_query(format!(
+		"{hits},
+		page AS (
+			SELECT *, greatest(rank_fi, rank_en) AS rank
+			FROM hits
+			ORDER BY rank DESC, id
+			LIMIT $4 OFFSET $5
+		)
+		SELECT p.content_type, p.id, p.title,
+			CASE WHEN p.rank_fi >= p.rank_en
+				THEN ts_headline('finnish', p.document, q.fi, '{options}')
+				ELSE ts_headline('english', p.document, q.en, '{options}')
+			END AS snippet,
+			p.rank
+		FROM page p, q
+		ORDER BY p.rank DESC, p.id",
+		hits = HITS,
+		options = HEADLINE_OPTIONS,
+	))
+	.bind::<Text, _>(&q_query)
+	.bind::<Nullable<Uuid>, _>(q_user_id)
+	.bind::<Bool, _>(q_isadmin)
+	.bind::<BigInt, _>(q_limit)
+	.bind::<BigInt, _>(q_offset)
+	.load::<SearchHit>(conn)?;
+
+	Ok((hits, count.total))
+}
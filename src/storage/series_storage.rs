@@ -0,0 +1,243 @@
+use actix_web::web;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::result::Error::NotFound;
+use diesel::PgConnection;
+use std::collections::HashMap;
+
+use crate::models::listing::{ListParams, Page, SortDirection, SortField};
+use crate::models::reactions::ArticleWithReactions;
+use crate::models::series::{ArticleWithSeries, Series, SeriesArticle, SeriesEntry, SeriesIndex, SeriesNavigation};
+use crate::models::users::Pool;
+use crate::schema::series;
+use diesel::result::Error;
+
+fn filtered_series(params: &ListParams) -> series::BoxedQuery<'static, Pg> {
+	use crate::schema::series::dsl::*;
+
+	let mut query = series.into_boxed();
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+	}
+	if let Some(from) = params.updated_from {
+		query = query.filter(updated_at.ge(from));
+	}
+	if let Some(to) = params.updated_to {
+		query = query.filter(updated_at.lt(to));
+	}
+	if let Some(q_user_id) = params.user_id {
+		query = query.filter(user_id.eq(q_user_id));
+	}
+	query
+}
+
+pub fn query_series(params: &ListParams, pool: &web::Data<Pool>) -> Result<Page<Series>, Error> {
+	use crate::schema::series::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let total = filtered_series(params).count().get_result::<i64>(conn)?;
+
+	let query = filtered_series(params);
+	let query = match params.sort_or(SortField::Title, SortDirection::Asc) {
+		(SortField::CreatedAt, SortDirection::Asc) | (SortField::InWorldAt, SortDirection::Asc) => query.order(created_at.asc()),
+		(SortField::CreatedAt, SortDirection::Desc) | (SortField::InWorldAt, SortDirection::Desc) => query.order(created_at.desc()),
+		(SortField::UpdatedAt, SortDirection::Asc) => query.order(updated_at.asc()),
+		(SortField::UpdatedAt, SortDirection::Desc) => query.order(updated_at.desc()),
+		(SortField::Title, SortDirection::Asc) => query.order(title.asc()),
+		(SortField::Title, SortDirection::Desc) => query.order(title.desc()),
+	};
+
+	let items = query
+		.then_order_by(id.asc())
+		.limit(params.limit())
+		.offset(params.offset())
+		.load::<Series>(conn)?;
+
+	Ok(Page { items, total })
+}
+
+pub fn get_series(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Series, Error> {
+	use crate::schema::series::dsl::{id, series};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let item = series.filter(id.eq(q_id)).get_result::<Series>(conn)?;
+
+	Ok(item)
+}
+
+pub fn get_series_index(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<SeriesIndex, Error> {
+	use crate::schema::series::dsl::{id, series};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let item = series.filter(id.eq(q_id)).get_result::<Series>(conn)?;
+	let articles = load_entries(&[q_id], conn)?.remove(&q_id).unwrap_or_default();
+
+	Ok(SeriesIndex { series: item, articles })
+}
+
+// Entries of the given series in reading order, renumbered so that positions
+// have no gaps left by deleted articles
+fn load_entries(
+	q_series_ids: &[uuid::Uuid],
+	conn: &PgConnection,
+) -> Result<HashMap<uuid::Uuid, Vec<SeriesEntry>>, Error> {
+	use crate::schema::articles::dsl as a;
+	use crate::schema::series_articles::dsl as sa;
+
+	let entries = sa::series_articles
+		.inner_join(a::articles)
+		.select((sa::series_id, sa::article_id, a::slug, a::title, a::in_world_at, sa::position))
+		.filter(sa::series_id.eq_any(q_series_ids))
+		.order((sa::series_id.asc(), sa::position.asc()))
+		.load::<SeriesEntry>(conn)?;
+
+	let mut by_series: HashMap<uuid::Uuid, Vec<SeriesEntry>> = HashMap::new();
+	for mut entry in entries {
+		let list = by_series.entry(entry.series_id).or_default();
+		entry.position = list.len() as i16;
+		list.push(entry);
+	}
+
+	Ok(by_series)
+}
+
+pub fn create_series(
+	q_user_id: uuid::Uuid,
+	q_title: String,
+	q_description: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Series, Error> {
+	use crate::schema::series::dsl::series;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_series = Series {
+		id: uuid::Uuid::new_v4(),
+		user_id: q_user_id,
+		title: q_title,
+		description: q_description,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	let item = diesel::insert_into(series)
+		.values(&new_series)
+		.get_result::<Series>(conn)?;
+
+	Ok(item)
+}
+
+pub fn update_series(
+	q_id: uuid::Uuid,
+	q_title: String,
+	q_description: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<Series, Error> {
+	use crate::schema::series::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let item = diesel::update(series)
+		.filter(id.eq(q_id))
+		.set((title.eq(q_title), description.eq(q_description), updated_by.eq(q_email)))
+		.get_result::<Series>(conn)?;
+
+	Ok(item)
+}
+
+pub fn delete_series(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+	use crate::schema::series::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let deleted = diesel::delete(series.filter(id.eq(q_id))).execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// Replaces the articles of a series with the given ones in the given order. The
+// handler keeps the list short enough for the SMALLINT positions.
+pub fn set_series_articles(
+	q_id: uuid::Uuid,
+	q_article_ids: Vec<uuid::Uuid>,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<SeriesIndex, Error> {
+	use crate::schema::series::dsl::{id, series, updated_by};
+	use crate::schema::series_articles::dsl as sa;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let item = diesel::update(series)
+			.filter(id.eq(q_id))
+			.set(updated_by.eq(q_email.clone()))
+			.get_result::<Series>(conn)?;
+
+		diesel::delete(sa::series_articles.filter(sa::series_id.eq(q_id))).execute(conn)?;
+
+		let new_entries: Vec<SeriesArticle> = q_article_ids
+			.iter()
+			.enumerate()
+			.map(|(position, q_article_id)| SeriesArticle {
+				id: uuid::Uuid::new_v4(),
+				series_id: q_id,
+				article_id: *q_article_id,
+				position: position as i16,
+				created_at: chrono::Local::now().naive_local(),
+				updated_by: q_email.clone(),
+			})
+			.collect();
+		diesel::insert_into(sa::series_articles)
+			.values(&new_entries)
+			.execute(conn)?;
+
+		let articles = load_entries(&[q_id], conn)?.remove(&q_id).unwrap_or_default();
+		Ok(SeriesIndex { series: item, articles })
+	})
+}
+
+// Adds the previous and next article of every series the articles belong to
+pub fn with_series(items: Vec<ArticleWithReactions>, pool: &web::Data<Pool>) -> Result<Vec<ArticleWithSeries>, Error> {
+	use crate::schema::series::dsl as s;
+	use crate::schema::series_articles::dsl as sa;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let ids: Vec<uuid::Uuid> = items.iter().map(|item| item.article.id).collect();
+	let series_list = s::series
+		.filter(s::id.eq_any(sa::series_articles.select(sa::series_id).filter(sa::article_id.eq_any(ids))))
+		.order((s::title.asc(), s::id.asc()))
+		.load::<Series>(conn)?;
+	let series_ids: Vec<uuid::Uuid> = series_list.iter().map(|item| item.id).collect();
+	let entries = load_entries(&series_ids, conn)?;
+
+	Ok(items
+		.into_iter()
+		.map(|article| {
+			let navigation = series_list
+				.iter()
+				.filter_map(|item| {
+					let list = entries.get(&item.id)?;
+					let index = list.iter().position(|entry| entry.article_id == article.article.id)?;
+					Some(SeriesNavigation {
+						series_id: item.id,
+						series_title: item.title.clone(),
+						position: list[index].position,
+						total: list.len(),
+						previous: index.checked_sub(1).map(|previous| list[previous].clone()),
+						next: list.get(index + 1).cloned(),
+					})
+				})
+				.collect();
+			ArticleWithSeries {
+				article,
+				series: navigation,
+			}
+		})
+		.collect())
+}
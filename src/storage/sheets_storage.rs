@@ -0,0 +1,56 @@
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use crate::models::sheets::CharacterSheet;
+use crate::models::users::Pool;
+use diesel::result::Error;
+
+pub fn get_sheet(q_character_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<CharacterSheet, Error> {
+	use crate::schema::character_sheets::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let sheet = character_sheets
+		.filter(character_id.eq(q_character_id))
+		.get_result::<CharacterSheet>(conn)?;
+
+	Ok(sheet)
+}
+
+// Creates the character's sheet or replaces its data
+pub fn save_sheet(
+	q_character_id: uuid::Uuid,
+	q_ruleset: String,
+	q_ruleset_version: i32,
+	q_data: serde_json::Value,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<CharacterSheet, Error> {
+	use crate::schema::character_sheets::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_sheet = CharacterSheet {
+		id: uuid::Uuid::new_v4(),
+		character_id: q_character_id,
+		ruleset: q_ruleset.clone(),
+		ruleset_version: q_ruleset_version,
+		data: q_data.clone(),
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email.clone(),
+		updated_at: chrono::Local::now().naive_local(),
+	};
+
+	let sheet = diesel::insert_into(character_sheets)
+		.values(&new_sheet)
+		.on_conflict(character_id)
+		.do_update()
+		.set((
+			ruleset.eq(q_ruleset),
+			ruleset_version.eq(q_ruleset_version),
+			data.eq(q_data),
+			updated_by.eq(q_email),
+		))
+		.get_result::<CharacterSheet>(conn)?;
+
+	Ok(sheet)
+}
@@ -0,0 +1,110 @@
+use diesel::prelude::*;
+use diesel::PgConnection;
+use std::collections::HashSet;
+
+use crate::models::slugs::{SlugHistory, SlugOwner};
+use crate::utils::slugify;
+use diesel::result::Error;
+
+// These take a connection instead of the pool so that they can run inside the
+// transaction that creates or renames the content.
+
+// First free slug for the text: "kallio", "kallio-2", "kallio-3"... Slugs in the
+// history of other content stay reserved so that their old links keep working.
+pub fn unique_slug(
+	text: &str,
+	q_owner: SlugOwner,
+	q_content_id: uuid::Uuid,
+	conn: &PgConnection,
+) -> Result<String, Error> {
+	use crate::schema::slug_history::dsl as history;
+
+	let mut base = slugify(text);
+	if base.is_empty() {
+		base = q_owner.as_str().to_string();
+	}
+	let pattern = format!("{}%", base);
+
+	let mut taken: HashSet<String> = match q_owner {
+		SlugOwner::Article => {
+			use crate::schema::articles::dsl::{articles, id, slug};
+			articles
+				.select(slug)
+				.filter(slug.like(&pattern))
+				.filter(id.ne(q_content_id))
+				.load::<String>(conn)?
+		}
+		SlugOwner::Character => {
+			use crate::schema::characters::dsl::{characters, id, slug};
+			characters
+				.select(slug)
+				.filter(slug.like(&pattern))
+				.filter(id.ne(q_content_id))
+				.load::<String>(conn)?
+		}
+	}
+	.into_iter()
+	.collect();
+	taken.extend(
+		history::slug_history
+			.select(history::slug)
+			.filter(history::content_type.eq(q_owner.as_str()))
+			.filter(history::slug.like(&pattern))
+			.filter(history::content_id.ne(q_content_id))
+			.load::<String>(conn)?,
+	);
+
+	if !taken.contains(&base) {
+		return Ok(base);
+	}
+	Ok((2..)
+		.map(|n| format!("{}-{}", base, n))
+		.find(|candidate| !taken.contains(candidate))
+		.unwrap())
+}
+
+// Keeps the old slug resolving to the content. Going back to an earlier slug
+// takes it out of the history.
+pub fn record_slug_change(
+	q_owner: SlugOwner,
+	q_content_id: uuid::Uuid,
+	q_old_slug: String,
+	q_new_slug: &str,
+	q_email: String,
+	conn: &PgConnection,
+) -> Result<(), Error> {
+	use crate::schema::slug_history::dsl::*;
+
+	diesel::delete(
+		slug_history
+			.filter(content_type.eq(q_owner.as_str()))
+			.filter(content_id.eq(q_content_id))
+			.filter(slug.eq(q_new_slug)),
+	)
+	.execute(conn)?;
+
+	let old = SlugHistory {
+		id: uuid::Uuid::new_v4(),
+		content_type: q_owner.as_str().to_string(),
+		content_id: q_content_id,
+		slug: q_old_slug,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+	};
+	diesel::insert_into(slug_history)
+		.values(&old)
+		.on_conflict((content_type, slug))
+		.do_nothing()
+		.execute(conn)?;
+
+	Ok(())
+}
+
+pub fn query_slug_history(q_owner: SlugOwner, q_slug: &str, conn: &PgConnection) -> Result<SlugHistory, Error> {
+	use crate::schema::slug_history::dsl::*;
+
+	slug_history
+		.filter(content_type.eq(q_owner.as_str()))
+		.filter(slug.eq(q_slug))
+		.get_result::<SlugHistory>(conn)
+}
@@ -1,40 +1,217 @@
 use actix_web::web;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::Error::NotFound;
 use diesel::PgConnection;
 
-use crate::models::tags::{Tag, ContentTag, RichContentTag};
+use crate::models::listing::{ListParams, Page, SortDirection, SortField};
+use crate::models::tags::{
+	ContentTag, ContentType, RichContentTag, Tag, TagAlias, TagCount, TagDeletion, TagMerge, TagSuggestion,
+};
 use crate::models::users::Pool;
+use crate::schema::tags;
 use diesel::result::Error;
 
+sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+fn filtered_tags(params: &ListParams) -> tags::BoxedQuery<'static, Pg> {
+	use crate::schema::tags::dsl::*;
+
+	let mut query = tags.into_boxed();
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+	}
+	if let Some(from) = params.updated_from {
+		query = query.filter(updated_at.ge(from));
+	}
+	if let Some(to) = params.updated_to {
+		query = query.filter(updated_at.lt(to));
+	}
+	query
+}
+
 pub fn query_tags(
+	params: &ListParams,
 	pool: &web::Data<Pool>,
-) -> Result<Vec<Tag>, Error> {
-	use crate::schema::tags::dsl::{tags};
+) -> Result<Page<Tag>, Error> {
+	use crate::schema::tags::dsl::*;
 	let conn: &PgConnection = &pool.get().unwrap();
 
-	let tags_res = tags
+	let total = filtered_tags(params).count().get_result::<i64>(conn)?;
+
+	let query = filtered_tags(params);
+	let query = match params.sort_or(SortField::Title, SortDirection::Asc) {
+		(SortField::CreatedAt, SortDirection::Asc) | (SortField::InWorldAt, SortDirection::Asc) => query.order(created_at.asc()),
+		(SortField::CreatedAt, SortDirection::Desc) | (SortField::InWorldAt, SortDirection::Desc) => query.order(created_at.desc()),
+		(SortField::UpdatedAt, SortDirection::Asc) => query.order(updated_at.asc()),
+		(SortField::UpdatedAt, SortDirection::Desc) => query.order(updated_at.desc()),
+		(SortField::Title, SortDirection::Asc) => query.order(title.asc()),
+		(SortField::Title, SortDirection::Desc) => query.order(title.desc()),
+	};
+
+	let tags_res = query
+		.then_order_by(id.asc())
+		.limit(params.limit())
+		.offset(params.offset())
 		.load::<Tag>(conn)?;
 
-	Ok(tags_res)
+	Ok(Page {
+		items: tags_res,
+		total,
+	})
+}
+
+pub fn get_tag(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<Tag, Error> {
+	use crate::schema::tags::dsl::{id, tags};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let tag = tags.filter(id.eq(q_id)).get_result::<Tag>(conn)?;
+
+	Ok(tag)
 }
 
 pub fn query_content_tags(
+	q_content_type: ContentType,
 	q_content_id: uuid::Uuid,
 	pool: &web::Data<Pool>,
 ) -> Result<Vec<RichContentTag>, Error> {
-	use crate::schema::rich_contenttags::dsl::{content_id, rich_contenttags};
+	use crate::schema::rich_contenttags::dsl::{content_id, content_type, rich_contenttags, tag_title};
 	let conn: &PgConnection = &pool.get().unwrap();
 
 	let tags_res = rich_contenttags
+		.filter(content_type.eq(q_content_type.as_str()))
 		.filter(content_id.eq(&q_content_id))
+		.order(tag_title.asc())
 		.load::<RichContentTag>(conn)?;
 
 	Ok(tags_res)
 }
 
+// The user owning a piece of taggable content
+pub fn query_content_owner(
+	q_content_type: ContentType,
+	q_content_id: uuid::Uuid,
+	pool: &web::Data<Pool>,
+) -> Result<uuid::Uuid, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	match q_content_type {
+		ContentType::Article => {
+			use crate::schema::articles::dsl::{articles, id, user_id};
+			articles.filter(id.eq(q_content_id)).select(user_id).get_result(conn)
+		}
+		ContentType::Character => {
+			use crate::schema::characters::dsl::{characters, id, user_id};
+			characters.filter(id.eq(q_content_id)).select(user_id).get_result(conn)
+		}
+		ContentType::User => {
+			use crate::schema::users::dsl::{id, users};
+			users.filter(id.eq(q_content_id)).select(id).get_result(conn)
+		}
+	}
+}
+
+// Tags starting with or resembling the typed text, prefix matches first
+pub fn query_tag_suggestions(
+	q_text: String,
+	q_limit: i64,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<TagSuggestion>, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let text = q_text.to_lowercase();
+	let prefix = format!("{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+
+	let suggestions = diesel::sql_query(
+		"SELECT best.id, best.title, best.alias, u.count
+		FROM (
+			SELECT DISTINCT ON (m.id) m.id, m.title, m.alias, m.prefix_match, m.score
+			FROM (
+				SELECT t.id, t.title::text AS title, NULL::text AS alias,
+					lower(t.title) LIKE $2 AS prefix_match, similarity(lower(t.title), $1) AS score
+				FROM tags t
+				WHERE lower(t.title) LIKE $2 OR lower(t.title) % $1
+				UNION ALL
+				SELECT t.id, t.title::text, a.alias::text,
+					lower(a.alias) LIKE $2, similarity(lower(a.alias), $1)
+				FROM tag_aliases a
+				JOIN tags t ON t.id = a.tag_id
+				WHERE lower(a.alias) LIKE $2 OR lower(a.alias) % $1
+			) m
+			ORDER BY m.id, m.prefix_match DESC, m.score DESC
+		) best
+		CROSS JOIN LATERAL (
+			SELECT count(*) AS count FROM contenttags c WHERE c.tag_id = best.id
+		) u
+		ORDER BY best.prefix_match DESC, best.score DESC, u.count DESC, best.title
+		LIMIT $3",
+	)
+	.bind::<diesel::sql_types::Text, _>(&text)
+	.bind::<diesel::sql_types::Text, _>(&prefix)
+	.bind::<diesel::sql_types::BigInt, _>(q_limit)
+	.load::<TagSuggestion>(conn)?;
+
+	Ok(suggestions)
+}
+
+// Tag cloud data: every tag with its number of content links, optionally
+// counting only links to one type of content
+pub fn query_tag_usage(
+	q_content_type: Option<ContentType>,
+	q_limit: i64,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<TagCount>, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let usage = diesel::sql_query(
+		"SELECT t.id, t.title::text AS title, count(c.id) AS count
+		FROM tags t
+		LEFT JOIN contenttags c ON c.tag_id = t.id AND ($1::text IS NULL OR c.content_type = $1)
+		GROUP BY t.id, t.title
+		ORDER BY count DESC, t.title
+		LIMIT $2",
+	)
+	.bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(q_content_type.map(|t| t.as_str()))
+	.bind::<diesel::sql_types::BigInt, _>(q_limit)
+	.load::<TagCount>(conn)?;
+
+	Ok(usage)
+}
+
+// Tags most often found on the same content as the given tag
+pub fn query_related_tags(
+	q_tag_id: uuid::Uuid,
+	q_limit: i64,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<TagCount>, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let related = diesel::sql_query(
+		"SELECT t.id, t.title::text AS title, count(*) AS count
+		FROM contenttags base
+		JOIN contenttags other
+			ON other.content_type = base.content_type
+			AND other.content_id = base.content_id
+			AND other.tag_id <> base.tag_id
+		JOIN tags t ON t.id = other.tag_id
+		WHERE base.tag_id = $1
+		GROUP BY t.id, t.title
+		ORDER BY count DESC, t.title
+		LIMIT $2",
+	)
+	.bind::<diesel::sql_types::Uuid, _>(q_tag_id)
+	.bind::<diesel::sql_types::BigInt, _>(q_limit)
+	.load::<TagCount>(conn)?;
+
+	Ok(related)
+}
+
 pub fn create_tag(
 	q_title: String,
+	q_parent_id: Option<uuid::Uuid>,
 	q_email: String,
 	pool: &web::Data<Pool>,
 ) -> Result<Tag, Error> {
@@ -46,6 +223,8 @@ pub fn create_tag(
 		title: q_title,
 		updated_by: q_email,
 		created_at: chrono::Local::now().naive_local(),
+		updated_at: chrono::Local::now().naive_local(),
+		parent_id: q_parent_id,
 	};
 
 	let tag = diesel::insert_into(tags)
@@ -55,8 +234,197 @@ pub fn create_tag(
 	Ok(tag)
 }
 
+pub fn query_tag_children(
+	q_tag_id: uuid::Uuid,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<Tag>, Error> {
+	use crate::schema::tags::dsl::{parent_id, tags, title};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let tags_res = tags
+		.filter(parent_id.eq(q_tag_id))
+		.order(title.asc())
+		.load::<Tag>(conn)?;
+
+	Ok(tags_res)
+}
+
+// The parent, grandparent and so on of a tag, nearest first
+pub fn query_tag_ancestor_ids(
+	q_tag_id: uuid::Uuid,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<uuid::Uuid>, Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	tag_ancestor_ids(q_tag_id, conn)
+}
+
+fn tag_ancestor_ids(q_tag_id: uuid::Uuid, conn: &PgConnection) -> Result<Vec<uuid::Uuid>, Error> {
+	use crate::schema::tags::dsl::{id, parent_id, tags};
+
+	let mut ancestors: Vec<uuid::Uuid> = Vec::new();
+	let mut current = q_tag_id;
+	while let Some(parent) = tags
+		.filter(id.eq(current))
+		.select(parent_id)
+		.get_result::<Option<uuid::Uuid>>(conn)?
+	{
+		if parent == q_tag_id || ancestors.contains(&parent) {
+			break;
+		}
+		ancestors.push(parent);
+		current = parent;
+	}
+
+	Ok(ancestors)
+}
+
+// Finds the canonical tag for a title or any of its aliases, ignoring case
+pub fn resolve_tag(q_title: String, pool: &web::Data<Pool>) -> Result<Tag, Error> {
+	use crate::schema::tag_aliases::dsl::{alias, tag_aliases, tag_id};
+	use crate::schema::tags::dsl::{id, tags, title};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let tag = tags
+		.filter(lower(title).eq(lower(&q_title)))
+		.first::<Tag>(conn)
+		.optional()?;
+	if let Some(tag) = tag {
+		return Ok(tag);
+	}
+
+	let tag = tags
+		.filter(id.nullable().eq(tag_aliases.select(tag_id).filter(lower(alias).eq(lower(&q_title))).single_value()))
+		.first::<Tag>(conn)?;
+
+	Ok(tag)
+}
+
+pub fn query_tag_aliases(
+	q_tag_id: uuid::Uuid,
+	pool: &web::Data<Pool>,
+) -> Result<Vec<TagAlias>, Error> {
+	use crate::schema::tag_aliases::dsl::{alias, tag_aliases, tag_id};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let aliases = tag_aliases
+		.filter(tag_id.eq(q_tag_id))
+		.order(alias.asc())
+		.load::<TagAlias>(conn)?;
+
+	Ok(aliases)
+}
+
+pub fn create_tag_alias(
+	q_tag_id: uuid::Uuid,
+	q_alias: String,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<TagAlias, Error> {
+	use crate::schema::tag_aliases::dsl::tag_aliases;
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	let new_alias = TagAlias {
+		id: uuid::Uuid::new_v4(),
+		tag_id: q_tag_id,
+		alias: q_alias,
+		created_at: chrono::Local::now().naive_local(),
+		updated_by: q_email,
+	};
+
+	let tag_alias = diesel::insert_into(tag_aliases)
+		.values(&new_alias)
+		.get_result::<TagAlias>(conn)?;
+
+	Ok(tag_alias)
+}
+
+pub fn delete_tag_alias(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+	let conn: &PgConnection = &pool.get().unwrap();
+	use crate::schema::tag_aliases::dsl::*;
+
+	let deleted = diesel::delete(tag_aliases.filter(id.eq(q_id))).execute(conn)?;
+
+	if deleted > 0 {
+		return Ok(());
+	}
+	Err(NotFound)
+}
+
+// Moves everything from the source tag to the target tag and removes the source.
+// The source title becomes an alias of the target so old links keep resolving.
+pub fn merge_tags(
+	q_source_id: uuid::Uuid,
+	q_target_id: uuid::Uuid,
+	q_email: String,
+	pool: &web::Data<Pool>,
+) -> Result<TagMerge, Error> {
+	use crate::schema::contenttags::dsl as ct;
+	use crate::schema::tag_aliases::dsl as ta;
+	use crate::schema::tags::dsl::{id, parent_id, tags, updated_by};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		let source = tags.filter(id.eq(q_source_id)).for_update().get_result::<Tag>(conn)?;
+		tags.filter(id.eq(q_target_id)).for_update().get_result::<Tag>(conn)?;
+
+		// Content already tagged with the target would end up tagged twice
+		let dropped_links = diesel::sql_query(
+			"DELETE FROM contenttags s
+			USING contenttags t
+			WHERE s.tag_id = $1
+				AND t.tag_id = $2
+				AND t.content_type = s.content_type
+				AND t.content_id = s.content_id",
+		)
+		.bind::<diesel::sql_types::Uuid, _>(q_source_id)
+		.bind::<diesel::sql_types::Uuid, _>(q_target_id)
+		.execute(conn)?;
+
+		let moved_links = diesel::update(ct::contenttags.filter(ct::tag_id.eq(q_source_id)))
+			.set((ct::tag_id.eq(q_target_id), ct::updated_by.eq(&q_email)))
+			.execute(conn)?;
+
+		// A target nested anywhere under the source takes the source's place in the
+		// hierarchy, otherwise the source's children would end up its descendants
+		if tag_ancestor_ids(q_target_id, conn)?.contains(&q_source_id) {
+			diesel::update(tags.filter(id.eq(q_target_id)))
+				.set((parent_id.eq(source.parent_id), updated_by.eq(&q_email)))
+				.execute(conn)?;
+		}
+		diesel::update(tags.filter(parent_id.eq(q_source_id)))
+			.set((parent_id.eq(q_target_id), updated_by.eq(&q_email)))
+			.execute(conn)?;
+
+		diesel::update(ta::tag_aliases.filter(ta::tag_id.eq(q_source_id)))
+			.set((ta::tag_id.eq(q_target_id), ta::updated_by.eq(&q_email)))
+			.execute(conn)?;
+
+		diesel::delete(tags.filter(id.eq(q_source_id))).execute(conn)?;
+
+		diesel::insert_into(ta::tag_aliases)
+			.values(&TagAlias {
+				id: uuid::Uuid::new_v4(),
+				tag_id: q_target_id,
+				alias: source.title,
+				created_at: chrono::Local::now().naive_local(),
+				updated_by: q_email.clone(),
+			})
+			.execute(conn)?;
+
+		let tag = tags.filter(id.eq(q_target_id)).get_result::<Tag>(conn)?;
+
+		Ok(TagMerge {
+			tag,
+			moved_links,
+			dropped_links,
+		})
+	})
+}
+
 pub fn create_content_tag(
 	q_tag_id: uuid::Uuid,
+	q_content_type: ContentType,
 	q_content_id: uuid::Uuid,
 	q_email: String,
 	pool: &web::Data<Pool>,
@@ -67,6 +435,7 @@ pub fn create_content_tag(
 	let new_tag = ContentTag {
 		id: uuid::Uuid::new_v4(),
 		tag_id: q_tag_id,
+		content_type: q_content_type.as_str().to_string(),
 		content_id: q_content_id,
 		updated_by: q_email,
 		created_at: chrono::Local::now().naive_local(),
@@ -79,11 +448,22 @@ pub fn create_content_tag(
 	Ok(tag)
 }
 
-pub fn delete_tag(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error> {
+pub fn delete_content_tag(
+	q_tag_id: uuid::Uuid,
+	q_content_type: ContentType,
+	q_content_id: uuid::Uuid,
+	pool: &web::Data<Pool>,
+) -> Result<(), Error> {
 	let conn: &PgConnection = &pool.get().unwrap();
-	use crate::schema::articles::dsl::*;
+	use crate::schema::contenttags::dsl::*;
 
-	let deleted = diesel::delete(articles.filter(id.eq(q_id))).execute(conn)?;
+	let deleted = diesel::delete(
+		contenttags
+			.filter(tag_id.eq(q_tag_id))
+			.filter(content_type.eq(q_content_type.as_str()))
+			.filter(content_id.eq(q_content_id)),
+	)
+	.execute(conn)?;
 
 	if deleted > 0 {
 		return Ok(());
@@ -91,9 +471,40 @@ pub fn delete_tag(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Result<(), Error>
 	Err(NotFound)
 }
 
+pub fn delete_tag(q_id: uuid::Uuid, q_cascade: bool, pool: &web::Data<Pool>) -> Result<TagDeletion, Error> {
+	use crate::schema::contenttags::dsl::{contenttags, tag_id};
+	use crate::schema::tags::dsl::{id, tags};
+	let conn: &PgConnection = &pool.get().unwrap();
+
+	conn.transaction::<_, Error, _>(|| {
+		// Locking the tag keeps new links from appearing while it is deleted
+		tags.filter(id.eq(q_id)).select(id).for_update().get_result::<uuid::Uuid>(conn)?;
+
+		let link_count = contenttags.filter(tag_id.eq(q_id)).count().get_result::<i64>(conn)?;
+		if link_count > 0 && !q_cascade {
+			return Ok(TagDeletion {
+				tag_id: q_id,
+				link_count,
+				deleted: false,
+			});
+		}
+
+		diesel::delete(contenttags.filter(tag_id.eq(q_id))).execute(conn)?;
+		diesel::delete(tags.filter(id.eq(q_id))).execute(conn)?;
+
+		Ok(TagDeletion {
+			tag_id: q_id,
+			link_count,
+			deleted: true,
+		})
+	})
+}
+
+// A q_parent_id of None leaves the parent as it is, Some(None) un-nests the tag.
 pub fn update_tag(
 	q_uuid: uuid::Uuid,
 	q_title: String,
+	q_parent_id: Option<Option<uuid::Uuid>>,
 	q_email: String,
 	pool: &web::Data<Pool>,
 ) -> Result<Tag, Error> {
@@ -105,9 +516,167 @@ pub fn update_tag(
 		.filter(id.eq(q_uuid))
 		.set((
 			title.eq(q_title),
+			q_parent_id.map(|q_parent_id| parent_id.eq(q_parent_id)),
 			updated_by.eq(q_email),
 		))
 		.get_result::<Tag>(conn)?;
 
 	Ok(tag)
-}
\ No newline at end of file
+}
+
+// These run against a real database, e.g.
+// TEST_DATABASE_URL=postgres://localhost/hki2050_test cargo test -- --ignored
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::models::articles::{Article, ArticleChanges};
+	use crate::storage::{articles_storage, characters_storage, users_storage};
+	use diesel::r2d2::{self, ConnectionManager};
+
+	fn test_pool() -> web::Data<Pool> {
+		let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+		let conn = PgConnection::establish(&database_url).unwrap();
+		diesel_migrations::run_pending_migrations(&conn).unwrap();
+		let manager = ConnectionManager::<PgConnection>::new(database_url);
+		web::Data::new(r2d2::Pool::builder().max_size(2).build(manager).unwrap())
+	}
+
+	fn test_article(pool: &web::Data<Pool>) -> Article {
+		let name = uuid::Uuid::new_v4().to_string();
+		let email = format!("{}@example.com", name);
+		let user = users_storage::create(email.clone(), name.clone(), String::from("x"), pool).unwrap();
+		let character =
+			characters_storage::create_character(user.id, name.clone(), String::new(), email.clone(), pool).unwrap();
+		let new_article = ArticleChanges {
+			character_id: character.id,
+			title: name,
+			ingress: String::new(),
+			body: String::new(),
+			in_world_at: None,
+			updated_by: email,
+		};
+		articles_storage::create_article(user.id, new_article, pool).unwrap()
+	}
+
+	fn test_tag(pool: &web::Data<Pool>) -> Tag {
+		create_tag(uuid::Uuid::new_v4().to_string(), None, String::from("test"), pool).unwrap()
+	}
+
+	fn link_count(q_tag_id: uuid::Uuid, pool: &web::Data<Pool>) -> i64 {
+		use crate::schema::contenttags::dsl::{contenttags, tag_id};
+		let conn: &PgConnection = &pool.get().unwrap();
+		contenttags.filter(tag_id.eq(q_tag_id)).count().get_result(conn).unwrap()
+	}
+
+	fn tag_exists(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> bool {
+		use crate::schema::tags::dsl::{id, tags};
+		let conn: &PgConnection = &pool.get().unwrap();
+		tags.filter(id.eq(q_id)).first::<Tag>(conn).optional().unwrap().is_some()
+	}
+
+	fn article_exists(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> bool {
+		articles_storage::get_article(q_id, pool).is_ok()
+	}
+
+	#[test]
+	#[ignore]
+	fn delete_unused_tag() {
+		let pool = test_pool();
+		let tag = test_tag(&pool);
+
+		let deletion = delete_tag(tag.id, false, &pool).unwrap();
+
+		assert!(deletion.deleted);
+		assert_eq!(deletion.link_count, 0);
+		assert!(!tag_exists(tag.id, &pool));
+	}
+
+	#[test]
+	#[ignore]
+	fn delete_linked_tag_is_refused_without_cascade() {
+		let pool = test_pool();
+		let article = test_article(&pool);
+		let tag = test_tag(&pool);
+		create_content_tag(tag.id, ContentType::Article, article.id, String::from("test"), &pool).unwrap();
+
+		let deletion = delete_tag(tag.id, false, &pool).unwrap();
+
+		assert!(!deletion.deleted);
+		assert_eq!(deletion.link_count, 1);
+		assert!(tag_exists(tag.id, &pool));
+		assert_eq!(link_count(tag.id, &pool), 1);
+	}
+
+	#[test]
+	#[ignore]
+	fn delete_linked_tag_with_cascade_keeps_articles() {
+		let pool = test_pool();
+		let first = test_article(&pool);
+		let second = test_article(&pool);
+		let tag = test_tag(&pool);
+		create_content_tag(tag.id, ContentType::Article, first.id, String::from("test"), &pool).unwrap();
+		create_content_tag(tag.id, ContentType::Article, second.id, String::from("test"), &pool).unwrap();
+
+		let deletion = delete_tag(tag.id, true, &pool).unwrap();
+
+		assert!(deletion.deleted);
+		assert_eq!(deletion.link_count, 2);
+		assert!(!tag_exists(tag.id, &pool));
+		assert_eq!(link_count(tag.id, &pool), 0);
+		assert!(article_exists(first.id, &pool));
+		assert!(article_exists(second.id, &pool));
+	}
+
+	fn parent_of(q_id: uuid::Uuid, pool: &web::Data<Pool>) -> Option<uuid::Uuid> {
+		use crate::schema::tags::dsl::{id, tags};
+		let conn: &PgConnection = &pool.get().unwrap();
+		tags.filter(id.eq(q_id)).get_result::<Tag>(conn).unwrap().parent_id
+	}
+
+	// With source > child > target the target used to keep its parent while the
+	// child moved under it, leaving the two each other's parents
+	#[test]
+	#[ignore]
+	fn merge_into_nested_target_keeps_hierarchy_acyclic() {
+		let pool = test_pool();
+		let root = test_tag(&pool);
+		let source = create_tag(uuid::Uuid::new_v4().to_string(), Some(root.id), String::from("test"), &pool).unwrap();
+		let child = create_tag(uuid::Uuid::new_v4().to_string(), Some(source.id), String::from("test"), &pool).unwrap();
+		let target = create_tag(uuid::Uuid::new_v4().to_string(), Some(child.id), String::from("test"), &pool).unwrap();
+
+		merge_tags(source.id, target.id, String::from("test"), &pool).unwrap();
+
+		assert!(!tag_exists(source.id, &pool));
+		assert_eq!(parent_of(target.id, &pool), Some(root.id));
+		assert_eq!(parent_of(child.id, &pool), Some(target.id));
+		assert_eq!(query_tag_ancestor_ids(child.id, &pool).unwrap(), vec![target.id, root.id]);
+	}
+
+	// A rename without a parent used to un-nest the tag
+	#[test]
+	#[ignore]
+	fn update_without_parent_keeps_nesting() {
+		let pool = test_pool();
+		let root = test_tag(&pool);
+		let child = create_tag(uuid::Uuid::new_v4().to_string(), Some(root.id), String::from("test"), &pool).unwrap();
+
+		update_tag(child.id, uuid::Uuid::new_v4().to_string(), None, String::from("test"), &pool).unwrap();
+		assert_eq!(parent_of(child.id, &pool), Some(root.id));
+
+		update_tag(child.id, uuid::Uuid::new_v4().to_string(), Some(None), String::from("test"), &pool).unwrap();
+		assert_eq!(parent_of(child.id, &pool), None);
+	}
+
+	// delete_tag used to delete from articles with the given id
+	#[test]
+	#[ignore]
+	fn delete_tag_never_touches_articles() {
+		let pool = test_pool();
+		let article = test_article(&pool);
+
+		let res = delete_tag(article.id, true, &pool);
+
+		assert!(matches!(res, Err(NotFound)));
+		assert!(article_exists(article.id, &pool));
+	}
+}
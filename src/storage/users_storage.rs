@@ -1,18 +1,59 @@
+use crate::models::listing::{ListParams, Page, SortDirection, SortField};
 use crate::models::users::{Pool, User};
+use crate::schema::users;
 use crate::utils::hash_password;
 use actix_web::web;
+use diesel::pg::Pg;
 use diesel::result::Error;
 use diesel::{prelude::*, PgConnection};
 use log::{info};
 use Error::NotFound;
 
-pub fn query_all(pool: &web::Data<Pool>) -> Result<Vec<User>, Error> {
-	use crate::schema::users::dsl::{username, users};
-	let conn: &PgConnection = &pool.get().unwrap();
+fn filtered_users(params: &ListParams) -> users::BoxedQuery<'static, Pg> {
+	use crate::schema::users::dsl::*;
+
+	let mut query = users.into_boxed();
+	if let Some(from) = params.created_from {
+		query = query.filter(created_at.ge(from));
+	}
+	if let Some(to) = params.created_to {
+		query = query.filter(created_at.lt(to));
+	}
+	if let Some(from) = params.updated_from {
+		query = query.filter(updated_at.ge(from));
+	}
+	if let Some(to) = params.updated_to {
+		query = query.filter(updated_at.lt(to));
+	}
+	if let Some(q_user_id) = params.user_id {
+		query = query.filter(id.eq(q_user_id));
+	}
+	query
+}
 
-	let items = users.order(username.asc()).load::<User>(conn)?;
+pub fn query_all(params: &ListParams, pool: &web::Data<Pool>) -> Result<Page<User>, Error> {
+	use crate::schema::users::dsl::*;
+	let conn: &PgConnection = &pool.get().unwrap();
 
-	Ok(items)
+	let total = filtered_users(params).count().get_result::<i64>(conn)?;
+
+	let query = filtered_users(params);
+	let query = match params.sort_or(SortField::Title, SortDirection::Asc) {
+		(SortField::CreatedAt, SortDirection::Asc) | (SortField::InWorldAt, SortDirection::Asc) => query.order(created_at.asc()),
+		(SortField::CreatedAt, SortDirection::Desc) | (SortField::InWorldAt, SortDirection::Desc) => query.order(created_at.desc()),
+		(SortField::UpdatedAt, SortDirection::Asc) => query.order(updated_at.asc()),
+		(SortField::UpdatedAt, SortDirection::Desc) => query.order(updated_at.desc()),
+		(SortField::Title, SortDirection::Asc) => query.order(username.asc()),
+		(SortField::Title, SortDirection::Desc) => query.order(username.desc()),
+	};
+
+	let items = query
+		.then_order_by(id.asc())
+		.limit(params.limit())
+		.offset(params.offset())
+		.load::<User>(conn)?;
+
+	Ok(Page { items, total })
 }
 
 pub fn get_by_email(q_email: String, pool: &web::Data<Pool>) -> Result<User, Error> {
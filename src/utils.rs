@@ -26,3 +26,63 @@ pub fn verify(hash: &str, password: &str) -> Result<bool, ServiceError> {
 		ServiceError::Unauthorized
 	})
 }
+
+const SLUG_MAX_LENGTH: usize = 80;
+
+// Lowercase ASCII words joined with dashes. Finnish and other common Latin
+// letters are transliterated, anything else separates words.
+pub fn slugify(text: &str) -> String {
+	let mut slug = String::with_capacity(text.len());
+	for c in text.chars().flat_map(char::to_lowercase) {
+		let ascii = match c {
+			'ä' | 'å' | 'á' | 'à' | 'â' => "a",
+			'ö' | 'ø' | 'ó' | 'ò' | 'ô' | 'õ' => "o",
+			'é' | 'è' | 'ê' | 'ë' => "e",
+			'ü' | 'ú' | 'ù' | 'û' => "u",
+			'í' | 'ì' | 'î' | 'ï' => "i",
+			'š' => "s",
+			'ž' => "z",
+			'ç' => "c",
+			'ñ' => "n",
+			'ß' => "ss",
+			'æ' => "ae",
+			c if c.is_ascii_alphanumeric() => {
+				slug.push(c);
+				continue;
+			}
+			_ => "-",
+		};
+		if ascii != "-" || !slug.ends_with('-') {
+			slug.push_str(ascii);
+		}
+		if slug.len() >= SLUG_MAX_LENGTH {
+			break;
+		}
+	}
+	slug.truncate(SLUG_MAX_LENGTH);
+	slug.trim_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slugify_transliterates_finnish() {
+		assert_eq!(slugify("Äänekosken öinen sää"), "aanekosken-oinen-saa");
+		assert_eq!(slugify("Åland & Töölö"), "aland-toolo");
+	}
+
+	#[test]
+	fn slugify_collapses_separators() {
+		assert_eq!(slugify("  --Hello,   World!--  "), "hello-world");
+		assert_eq!(slugify("Straße 2050"), "strasse-2050");
+		assert_eq!(slugify("???"), "");
+	}
+
+	#[test]
+	fn slugify_limits_length() {
+		let slug = slugify(&"ä".repeat(200));
+		assert_eq!(slug.len(), SLUG_MAX_LENGTH);
+	}
+}
@@ -0,0 +1,99 @@
+// Wiki-style links in article bodies. [[Kalle Kärkkäinen]] links to a character
+// or, failing that, an article with that slug; [[character:kalle]] and
+// [[article:kaupunki-palaa]] pick the kind. Text after | is a label shown
+// instead of the target: [[character:kalle|Kalle]].
+use crate::models::slugs::SlugOwner;
+use crate::utils::slugify;
+
+// Longer link texts are not links but something else in double brackets
+pub const LINK_TEXT_MAX_LENGTH: usize = 200;
+
+#[derive(Debug, PartialEq)]
+pub struct WikiLink {
+	// Target as written, without the label
+	pub text: String,
+	pub kind: Option<SlugOwner>,
+	pub slug: String,
+}
+
+// Links in the order they first appear, each link text once
+pub fn parse(body: &str) -> Vec<WikiLink> {
+	let mut links: Vec<WikiLink> = Vec::new();
+	let mut rest = body;
+
+	while let Some(start) = rest.find("[[") {
+		rest = &rest[start + 2..];
+		let end = match rest.find("]]") {
+			Some(end) => end,
+			None => break,
+		};
+		let mut inner = &rest[..end];
+		rest = &rest[end + 2..];
+
+		// "[[not [[Kalle]]" links to Kalle
+		if let Some(nested) = inner.rfind("[[") {
+			inner = &inner[nested + 2..];
+		}
+		if inner.contains('\n') {
+			continue;
+		}
+
+		let text = inner.split('|').next().unwrap_or_default().trim();
+		let (kind, target) = if let Some(target) = text.strip_prefix("article:") {
+			(Some(SlugOwner::Article), target)
+		} else if let Some(target) = text.strip_prefix("character:") {
+			(Some(SlugOwner::Character), target)
+		} else {
+			(None, text)
+		};
+		let slug = slugify(target);
+		if slug.is_empty() || text.len() > LINK_TEXT_MAX_LENGTH || links.iter().any(|link| link.text == text) {
+			continue;
+		}
+
+		links.push(WikiLink {
+			text: text.to_string(),
+			kind,
+			slug,
+		});
+	}
+
+	links
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_finds_names_and_prefixed_slugs() {
+		let links = parse("[[Kalle Kärkkäinen]] kirjoitti [[article:kaupunki-palaa]] ja [[character:maija|Maijan]] kanssa.");
+		assert_eq!(
+			links,
+			vec![
+				WikiLink {
+					text: String::from("Kalle Kärkkäinen"),
+					kind: None,
+					slug: String::from("kalle-karkkainen"),
+				},
+				WikiLink {
+					text: String::from("article:kaupunki-palaa"),
+					kind: Some(SlugOwner::Article),
+					slug: String::from("kaupunki-palaa"),
+				},
+				WikiLink {
+					text: String::from("character:maija"),
+					kind: Some(SlugOwner::Character),
+					slug: String::from("maija"),
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn parse_skips_repeats_and_malformed_links() {
+		let links = parse("[[Kalle]] [[Kalle|hän]] [[ ]] [[rivi\nvaihto]] [[ei [[Maija]] [[auki");
+		let texts: Vec<&str> = links.iter().map(|link| link.text.as_str()).collect();
+		assert_eq!(texts, vec!["Kalle", "Maija"]);
+	}
+}